@@ -22,7 +22,10 @@ pub enum Statement {
 pub struct Instruction {
     pub opcode: Mnemonics,
     pub operand: Operand,
+    /// Where parsing the instruction ended, reported in errors.
     pub position: Position,
+    /// Where the mnemonic starts, to map the assembled instruction back to its source line.
+    pub start: Position,
 }
 
 impl Instruction {
@@ -31,6 +34,7 @@ impl Instruction {
             opcode,
             operand,
             position,
+            start: position,
         }
     }
 }
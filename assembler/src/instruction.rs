@@ -136,6 +136,7 @@ pub fn instruction_to_byte(instruction: Instruction) -> AssemblerResult<u8> {
             addressing_mode, ..
         },
         position,
+        ..
     } = instruction.clone();
 
     use AddressingMode::*;
@@ -74,6 +74,7 @@ pub struct Assembler<'a> {
     pub source: &'a str,
     pointer: usize,
     labels: HashMap<String, u16>,
+    lines: Vec<(u16, usize)>,
 }
 
 impl<'a> Assembler<'a> {
@@ -82,6 +83,7 @@ impl<'a> Assembler<'a> {
             source,
             pointer: 0,
             labels: HashMap::new(),
+            lines: Vec::new(),
         }
     }
 
@@ -97,9 +99,12 @@ impl<'a> Assembler<'a> {
         }
 
         self.pointer = 0;
+        self.lines.clear();
 
         for statement in p.0 {
             if let Statement::Instruction(instruction) = statement {
+                self.lines
+                    .push((self.pointer as u16 + 0x8000, instruction.start.0));
                 bytes.extend(self.assemble_instruction(instruction)?)
             }
         }
@@ -107,6 +112,23 @@ impl<'a> Assembler<'a> {
         Ok(bytes)
     }
 
+    /// Labels of the assembled program, with their absolute addresses.
+    pub fn symbols(&self) -> Vec<(String, u16)> {
+        let mut symbols = self
+            .labels
+            .iter()
+            .map(|(label, address)| (label.clone(), *address + 0x8000))
+            .collect::<Vec<_>>();
+
+        symbols.sort_by_key(|(_, address)| *address);
+        symbols
+    }
+
+    /// Absolute address and source line (1-based) of every assembled instruction, in address order.
+    pub fn lines(&self) -> &[(u16, usize)] {
+        &self.lines
+    }
+
     fn assemble_instruction(&mut self, instruction: Instruction) -> AssemblerResult<Vec<u8>> {
         let operand = self.assemble_operand_data(instruction.clone())?;
         let instruction = Instruction {
@@ -179,6 +201,7 @@ impl<'a> Assembler<'a> {
                 addressing_mode,
             },
             position,
+            ..
         } = instruction;
 
         let value = if let Some(value) = value {
@@ -357,4 +380,28 @@ FOO:
             ]
         );
     }
+
+    #[test]
+    fn test_symbols() {
+        let s = r#"
+LDX #$00
+LOOP:
+    INX
+    BNE LOOP
+END:
+    BRK
+        "#;
+
+        let mut assembler = Assembler::new(s);
+        assembler.assemble().unwrap();
+
+        assert_eq!(
+            assembler.symbols(),
+            vec![("LOOP".to_string(), 0x8002), ("END".to_string(), 0x8005)]
+        );
+        assert_eq!(
+            assembler.lines(),
+            &[(0x8000, 2), (0x8002, 4), (0x8003, 5), (0x8005, 7)]
+        );
+    }
 }
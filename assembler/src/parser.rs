@@ -95,6 +95,7 @@ impl<'a> Parser<'a> {
             self.next_token()?;
             Statement::Label(identifier.to_string())
         } else {
+            let start = self.position;
             let instruction = Mnemonics::to_mnemonics(identifier, self.position)?;
             self.next_token()?;
             let operand = self.parse_operand()?;
@@ -103,6 +104,7 @@ impl<'a> Parser<'a> {
                 opcode: instruction,
                 operand,
                 position: self.position,
+                start,
             })
         })
     }
@@ -2,7 +2,11 @@ use crate::{ui::*, View, DEBUG_OUTPUT, DEBUG_UPDATE, IS_RUNNING};
 use assembler::Assembler;
 use chrono::prelude::*;
 use eframe::egui::*;
-use emulator::{memory::Memory, Cpu6502, CpuDebugger, DebugKind, Debugger};
+use emulator::{
+    breakpoint::WatchKind, call_stack::CallStack, expression::Expression, history::History,
+    memory::Memory, provenance::Provenance, symbols::Symbols, write_log::WriteLog, Cpu6502,
+    CpuDebugger, DebugKind, Debugger, StopReason,
+};
 use std::{sync::atomic::Ordering, thread, time::Duration};
 
 #[derive(Default)]
@@ -10,19 +14,17 @@ pub struct AppDebugger;
 
 impl Debugger for AppDebugger {
     fn debug(&mut self, msg: &str, kind: DebugKind) {
-        unsafe {
-            DEBUG_OUTPUT.push((
-                Local::now().format("%H:%M:%S").to_string(),
-                msg.to_string(),
-                kind,
-            ));
+        DEBUG_OUTPUT.lock().unwrap().push((
+            Local::now().format("%H:%M:%S").to_string(),
+            msg.to_string(),
+            kind,
+        ));
 
-            DEBUG_UPDATE = true;
+        DEBUG_UPDATE.store(true, Ordering::Relaxed);
 
-            // if true && kind != DebugKind::Info {
-            //     IS_RUNNING.store(false, Ordering::Relaxed);
-            // }
-        }
+        // if true && kind != DebugKind::Info {
+        //     IS_RUNNING.store(false, Ordering::Relaxed);
+        // }
     }
 }
 
@@ -44,12 +46,54 @@ impl Default for Settings {
     }
 }
 
+/// Write history query from the memory dump context menu.
+#[derive(Clone, Copy)]
+pub struct WriteQuery {
+    pub address: u16,
+    /// Only show writes within the last `instructions` instructions.
+    pub instructions: Option<u64>,
+}
+
+/// Input of the "add breakpoint" form. `watch` is `None` for execution breakpoints.
+#[derive(Default)]
+pub struct BreakpointForm {
+    pub watch: Option<WatchKind>,
+    pub start: String,
+    pub end: String,
+    pub value: String,
+    pub condition: String,
+    pub temporary: bool,
+}
+
+/// Input of the trace window, and the last trace comparison.
+#[derive(Default)]
+pub struct TraceForm {
+    pub start: String,
+    pub stop: String,
+    pub diff: Option<String>,
+    /// Chip selects of VCD recordings, `name=start-end` separated by commas.
+    pub chip_selects: String,
+}
+
 pub struct WindowVisibility {
     pub display: bool,
     pub source: bool,
     pub memory_dump: bool,
     pub debugger: bool,
     pub disassembler: bool,
+    pub provenance: bool,
+    pub breakpoints: bool,
+    pub watches: bool,
+    pub call_stack: bool,
+    pub profiler: bool,
+    pub coverage: bool,
+    pub heatmap: bool,
+    pub self_modifying: bool,
+    pub sanitizer: bool,
+    pub stack_sanitizer: bool,
+    pub clobber: bool,
+    pub trace: bool,
+    pub replay: bool,
 }
 
 impl Default for WindowVisibility {
@@ -60,6 +104,19 @@ impl Default for WindowVisibility {
             memory_dump: true,
             debugger: true,
             disassembler: false,
+            provenance: false,
+            breakpoints: false,
+            watches: false,
+            call_stack: false,
+            profiler: false,
+            coverage: false,
+            heatmap: false,
+            self_modifying: false,
+            sanitizer: false,
+            stack_sanitizer: false,
+            clobber: false,
+            trace: false,
+            replay: false,
         }
     }
 }
@@ -68,6 +125,8 @@ impl Default for WindowVisibility {
 pub struct App {
     pub emulator: Cpu6502<AppDebugger>,
     pub source_input: String,
+    /// Source of the loaded program, for reports mapped to source lines.
+    pub loaded_source: String,
     pub memory_dump_range: (u16, u16),
     pub memory_dump_range_input: (String, String),
     pub error: Option<String>,
@@ -75,26 +134,49 @@ pub struct App {
     pub settings: Settings,
     pub window_visibility: WindowVisibility,
     pub disassembled: Vec<(usize, String, String)>,
+    pub write_query: Option<WriteQuery>,
+    pub provenance_input: String,
+    pub breakpoint_form: BreakpointForm,
+    pub watches: Vec<Expression>,
+    pub watch_input: String,
+    pub console_input: String,
+    pub clobber_input: String,
+    pub trace_form: TraceForm,
 }
 
 impl App {
     pub fn new(program: &str) -> App {
-        let src = Assembler::new(program).assemble().unwrap();
+        let mut assembler = Assembler::new(program);
+        let src = assembler.assemble().unwrap();
 
         let memory = Memory::new();
 
         let mut emulator = Cpu6502::<AppDebugger>::new(memory);
+        emulator.enable_history(
+            History::DEFAULT_CAPACITY,
+            History::DEFAULT_CHECKPOINT_INTERVAL,
+        );
+        emulator.write_log = Some(WriteLog::default());
+        emulator.provenance = Some(Provenance::default());
+        emulator.call_stack = Some(CallStack::default());
 
         emulator.reset();
         emulator.load(&src);
+        emulator.symbols = Symbols::new(assembler.symbols());
+
+        for (address, line) in assembler.lines() {
+            emulator.symbols.insert_line(*address, *line);
+        }
 
         Self {
             emulator,
             source_input: program.to_string(),
+            loaded_source: program.to_string(),
             memory_dump_range: (0x0000, 0x00FF),
             memory_dump_range_input: ("0000".to_string(), "00FF".to_string()),
             error: None,
             key_input: String::new(),
+            provenance_input: "A".to_string(),
             ..Default::default()
         }
     }
@@ -115,6 +197,128 @@ impl eframe::App for App {
                 .show(ctx, |ui| DisassemblerUi.ui(ui, self));
         }
 
+        if self.window_visibility.provenance {
+            Window::new("Provenance")
+                .default_width(350.)
+                .default_height(200.)
+                .default_pos((700., 350.))
+                .resizable(true)
+                .show(ctx, |ui| ProvenanceUi.ui(ui, self));
+        }
+
+        if self.window_visibility.breakpoints {
+            Window::new("Breakpoints")
+                .default_width(300.)
+                .default_height(200.)
+                .default_pos((700., 600.))
+                .resizable(true)
+                .show(ctx, |ui| BreakpointsUi.ui(ui, self));
+        }
+
+        if self.window_visibility.watches {
+            Window::new("Watches")
+                .default_width(250.)
+                .default_height(150.)
+                .default_pos((400., 600.))
+                .resizable(true)
+                .show(ctx, |ui| WatchesUi.ui(ui, self));
+        }
+
+        if self.window_visibility.call_stack {
+            Window::new("Call Stack")
+                .default_width(250.)
+                .default_height(200.)
+                .default_pos((400., 350.))
+                .resizable(true)
+                .show(ctx, |ui| CallStackUi.ui(ui, self));
+        }
+
+        if self.window_visibility.profiler {
+            Window::new("Profiler")
+                .default_width(500.)
+                .default_height(300.)
+                .resizable(true)
+                .show(ctx, |ui| ProfilerUi.ui(ui, self));
+        }
+
+        if self.window_visibility.coverage {
+            Window::new("Coverage")
+                .default_width(500.)
+                .default_height(400.)
+                .resizable(true)
+                .show(ctx, |ui| CoverageUi.ui(ui, self));
+        }
+
+        if self.window_visibility.heatmap {
+            Window::new("Heatmap")
+                .default_width(520.)
+                .resizable(false)
+                .show(ctx, |ui| HeatmapUi.ui(ui, self));
+        }
+
+        if self.window_visibility.self_modifying {
+            Window::new("Self-Modifying Code")
+                .default_width(600.)
+                .default_height(250.)
+                .resizable(true)
+                .show(ctx, |ui| SelfModifyingUi.ui(ui, self));
+        }
+
+        if self.window_visibility.sanitizer {
+            Window::new("Sanitizer")
+                .default_width(500.)
+                .default_height(250.)
+                .resizable(true)
+                .show(ctx, |ui| SanitizerUi.ui(ui, self));
+        }
+
+        if self.window_visibility.stack_sanitizer {
+            Window::new("Stack Sanitizer")
+                .default_width(500.)
+                .default_height(250.)
+                .resizable(true)
+                .show(ctx, |ui| StackSanitizerUi.ui(ui, self));
+        }
+
+        if self.window_visibility.clobber {
+            Window::new("Interrupt Clobbers")
+                .default_width(500.)
+                .default_height(250.)
+                .resizable(true)
+                .show(ctx, |ui| ClobberUi.ui(ui, self));
+        }
+
+        if self.window_visibility.trace {
+            Window::new("Trace")
+                .default_width(600.)
+                .default_height(200.)
+                .resizable(true)
+                .show(ctx, |ui| TraceUi.ui(ui, self));
+        }
+
+        if self.window_visibility.replay {
+            Window::new("Input Replay")
+                .default_width(400.)
+                .default_height(250.)
+                .resizable(true)
+                .show(ctx, |ui| ReplayUi.ui(ui, self));
+        }
+
+        if self.write_query.is_some() {
+            let mut open = true;
+
+            Window::new("Write History")
+                .default_width(250.)
+                .default_height(200.)
+                .resizable(true)
+                .open(&mut open)
+                .show(ctx, |ui| WriteHistory.ui(ui, self));
+
+            if !open {
+                self.write_query = None;
+            }
+        }
+
         TopBottomPanel::top("menu_bar").show(ctx, |ui| MenuBar.ui(ui, self));
         TopBottomPanel::bottom("status_bar").show(ctx, |ui| StatusBar.ui(ui, self));
 
@@ -208,13 +412,20 @@ impl eframe::App for App {
         }
 
         if IS_RUNNING.load(Ordering::Relaxed) {
-            let op = self.emulator.step();
+            let stop = self.emulator.step();
 
             thread::sleep(Duration::from_millis(self.settings.step_delay));
 
-            if op == 0x00 {
+            if let Some(reason) = stop {
                 IS_RUNNING.store(false, Ordering::Relaxed);
-                self.emulator.debug("Program finished");
+
+                match reason {
+                    StopReason::Brk => self.emulator.debug("Program finished"),
+                    reason => self
+                        .emulator
+                        .debugger
+                        .debug(&reason.to_string(), DebugKind::Warn),
+                }
             }
         }
     }
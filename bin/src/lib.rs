@@ -1,13 +1,14 @@
 use eframe::egui;
 use emulator::DebugKind;
-use std::sync::atomic::AtomicBool;
+use std::sync::{atomic::AtomicBool, Mutex};
 
 pub mod app;
 pub mod ui;
 
 /// (time, message, kind)
-pub static mut DEBUG_OUTPUT: Vec<(String, String, DebugKind)> = Vec::new();
-pub static mut DEBUG_UPDATE: bool = false;
+pub static DEBUG_OUTPUT: Mutex<Vec<(String, String, DebugKind)>> = Mutex::new(Vec::new());
+/// Whether new messages arrived since the debugger output was last drawn.
+pub static DEBUG_UPDATE: AtomicBool = AtomicBool::new(false);
 pub static IS_RUNNING: AtomicBool = AtomicBool::new(false);
 
 pub trait View {
@@ -0,0 +1,179 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::{
+    breakpoint::{Breakpoint, BreakpointId, WatchKind},
+    expression::Expression,
+};
+
+pub struct BreakpointsUi;
+
+impl BreakpointsUi {
+    fn hex(input: &str) -> &str {
+        input
+            .trim()
+            .trim_start_matches('$')
+            .trim_start_matches("0x")
+    }
+
+    fn kind_label(watch: Option<WatchKind>) -> &'static str {
+        match watch {
+            None => "execute",
+            Some(WatchKind::Read) => "read",
+            Some(WatchKind::Write) => "write",
+            Some(WatchKind::Access) => "access",
+        }
+    }
+
+    /// Builds a breakpoint from the form, or returns an error message.
+    fn breakpoint(app: &App) -> Result<Breakpoint, String> {
+        let form = &app.breakpoint_form;
+        let start = u16::from_str_radix(Self::hex(&form.start), 16)
+            .map_err(|_| "Invalid address".to_string())?;
+
+        let mut breakpoint = match form.watch {
+            None => Breakpoint::execute(start),
+            Some(kind) => {
+                let end = if form.end.trim().is_empty() {
+                    start
+                } else {
+                    u16::from_str_radix(Self::hex(&form.end), 16)
+                        .map_err(|_| "Invalid end address".to_string())?
+                };
+
+                if end < start {
+                    return Err("End address is before start address".to_string());
+                }
+
+                let mut breakpoint = Breakpoint::watch(start..=end, kind);
+
+                if !form.value.trim().is_empty() {
+                    let value = u8::from_str_radix(Self::hex(&form.value), 16)
+                        .map_err(|_| "Invalid value".to_string())?;
+                    breakpoint = breakpoint.with_value(value);
+                }
+
+                breakpoint
+            }
+        };
+
+        if !form.condition.trim().is_empty() {
+            let condition = Expression::parse(&form.condition).map_err(|e| e.to_string())?;
+            breakpoint = breakpoint.with_condition(condition);
+        }
+
+        if form.temporary {
+            breakpoint = breakpoint.temporary();
+        }
+
+        Ok(breakpoint)
+    }
+
+    fn hex_input(ui: &mut Ui, text: &mut String, hint: &str) {
+        ui.add(
+            TextEdit::singleline(text)
+                .desired_width(40.)
+                .hint_text(hint)
+                .font(FontId::new(10., FontFamily::Monospace))
+                .text_color(Color32::WHITE),
+        );
+    }
+}
+
+impl View for BreakpointsUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let form = &mut app.breakpoint_form;
+
+            ComboBox::from_id_source("breakpoint_kind")
+                .width(64.)
+                .selected_text(Self::kind_label(form.watch))
+                .show_ui(ui, |ui| {
+                    for watch in [
+                        None,
+                        Some(WatchKind::Read),
+                        Some(WatchKind::Write),
+                        Some(WatchKind::Access),
+                    ] {
+                        ui.selectable_value(&mut form.watch, watch, Self::kind_label(watch));
+                    }
+                });
+
+            Self::hex_input(ui, &mut form.start, "addr");
+
+            if form.watch.is_some() {
+                ui.add(Label::new("~"));
+                Self::hex_input(ui, &mut form.end, "end");
+                ui.add(Label::new("="));
+                Self::hex_input(ui, &mut form.value, "any");
+            }
+
+            ui.add(Label::new("if"));
+            ui.add(
+                TextEdit::singleline(&mut form.condition)
+                    .desired_width(120.)
+                    .hint_text("condition")
+                    .font(FontId::new(10., FontFamily::Monospace))
+                    .text_color(Color32::WHITE),
+            )
+            .on_hover_text("e.g. A == $10 && X > 3");
+
+            ui.checkbox(&mut form.temporary, "temporary");
+
+            if ui.button("add").clicked() {
+                match Self::breakpoint(app) {
+                    Ok(breakpoint) => {
+                        app.emulator.breakpoints.add(breakpoint);
+                        app.error = None;
+                    }
+                    Err(e) => app.error = Some(e),
+                }
+            }
+        });
+
+        ui.separator();
+
+        let mut removed: Option<BreakpointId> = None;
+        let mut toggled: Option<(BreakpointId, bool)> = None;
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for breakpoint in app.emulator.breakpoints.iter() {
+                    ui.horizontal(|ui| {
+                        let mut enabled = breakpoint.enabled;
+                        if ui.checkbox(&mut enabled, "").changed() {
+                            toggled = Some((breakpoint.id, enabled));
+                        }
+
+                        ui.add(Label::new(
+                            RichText::new(breakpoint.to_string()).monospace().color(
+                                if breakpoint.enabled {
+                                    Color32::WHITE
+                                } else {
+                                    Color32::GRAY
+                                },
+                            ),
+                        ));
+                        ui.separator();
+                        ui.add(Label::new(
+                            RichText::new(format!("{} hits", breakpoint.hits))
+                                .monospace()
+                                .color(Color32::LIGHT_BLUE),
+                        ));
+
+                        if ui.button("remove").clicked() {
+                            removed = Some(breakpoint.id);
+                        }
+                    });
+                }
+            });
+
+        if let Some((id, enabled)) = toggled {
+            app.emulator.breakpoints.set_enabled(id, enabled);
+        }
+
+        if let Some(id) = removed {
+            app.emulator.breakpoints.remove(id);
+        }
+    }
+}
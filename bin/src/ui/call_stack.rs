@@ -0,0 +1,47 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+
+pub struct CallStackUi;
+
+impl View for CallStackUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        let call_stack = match &app.emulator.call_stack {
+            Some(call_stack) => call_stack,
+            None => return,
+        };
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for (i, frame) in app.emulator.backtrace().iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(format!("#{i}"))
+                                .monospace()
+                                .color(Color32::LIGHT_BLUE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(frame.to_string())
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                    });
+                }
+
+                let desyncs = call_stack.desyncs().rev().take(10).collect::<Vec<_>>();
+
+                if !desyncs.is_empty() {
+                    ui.separator();
+                    ui.add(Label::new("Desyncs (newest first)"));
+
+                    for desync in desyncs {
+                        ui.add(Label::new(
+                            RichText::new(desync.to_string())
+                                .monospace()
+                                .color(Color32::YELLOW),
+                        ));
+                    }
+                }
+            });
+    }
+}
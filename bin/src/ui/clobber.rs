@@ -0,0 +1,85 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::clobber::ClobberChecker;
+
+pub struct ClobberUi;
+
+impl ClobberUi {
+    /// Parses comma or space separated zero page addresses (`10, $11, 0x12`).
+    fn parse(input: &str) -> Result<Vec<u8>, String> {
+        input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|address| !address.is_empty())
+            .map(|address| {
+                let hex = address.trim_start_matches('$').trim_start_matches("0x");
+                u8::from_str_radix(hex, 16).map_err(|e| format!("{address}: {e}"))
+            })
+            .collect()
+    }
+}
+
+impl View for ClobberUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.clobber_checker.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.clobber_checker = enabled.then(ClobberChecker::default);
+            }
+
+            if let Some(clobber_checker) = &mut app.emulator.clobber_checker {
+                if ui.button("clear").clicked() {
+                    clobber_checker.clear();
+                }
+            }
+        });
+
+        let clobber_checker = match &mut app.emulator.clobber_checker {
+            Some(clobber_checker) => clobber_checker,
+            None => return,
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Zero page");
+
+            let response = ui.add(
+                TextEdit::singleline(&mut app.clobber_input)
+                    .desired_width(150.)
+                    .hint_text("10, 11")
+                    .font(TextStyle::Monospace),
+            );
+
+            if response.changed() {
+                match Self::parse(&app.clobber_input) {
+                    Ok(zero_page) => {
+                        clobber_checker.zero_page = zero_page;
+                        app.error = None;
+                    }
+                    Err(e) => app.error = Some(e),
+                }
+            }
+        });
+
+        ui.separator();
+        ui.add(Label::new("Clobbering handlers (newest first)"));
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for clobber in clobber_checker.clobbers().rev() {
+                    ui.horizontal(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(app.emulator.symbols.describe(clobber.handler))
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(clobber.to_string())
+                                .monospace()
+                                .color(Color32::YELLOW),
+                        ));
+                    });
+                }
+            });
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::{breakpoint::Breakpoint, expression::Expression, DebugKind, Debugger};
+
+/// Debugger command line.
+///
+/// * `<expression>` or `print <expression>`: evaluate an expression
+/// * `break <address> [if <condition>]`: add an execution breakpoint
+/// * `watch <expression>`: add a watch entry
+pub struct Console;
+
+impl Console {
+    fn run(app: &mut App, command: &str) -> Result<String, String> {
+        let (name, rest) = match command.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "break" => {
+                let (address, condition) = match rest.split_once(" if ") {
+                    Some((address, condition)) => (address, Some(condition)),
+                    None => (rest, None),
+                };
+
+                let address = app
+                    .emulator
+                    .evaluate_str(address)
+                    .map_err(|e| e.to_string())?;
+                let mut breakpoint = Breakpoint::execute(address as u16);
+
+                if let Some(condition) = condition {
+                    let condition = Expression::parse(condition).map_err(|e| e.to_string())?;
+                    breakpoint = breakpoint.with_condition(condition);
+                }
+
+                let id = app.emulator.breakpoints.add(breakpoint);
+                Ok(format!("Breakpoint #{id} at 0x{:04X}", address as u16))
+            }
+            "watch" => {
+                let expression = Expression::parse(rest).map_err(|e| e.to_string())?;
+                let message = format!("Watching {expression}");
+
+                app.watches.push(expression);
+                app.window_visibility.watches = true;
+
+                Ok(message)
+            }
+            _ => {
+                let expression = if name == "print" { rest } else { command };
+                let value = app
+                    .emulator
+                    .evaluate_str(expression)
+                    .map_err(|e| e.to_string())?;
+
+                Ok(format!("{expression} = ${:04X} ({value})", value as u16))
+            }
+        }
+    }
+}
+
+impl View for Console {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        let input = ui
+            .add(
+                TextEdit::singleline(&mut app.console_input)
+                    .desired_width(200.)
+                    .hint_text("> expression, break, watch")
+                    .font(FontId::new(10., FontFamily::Monospace))
+                    .text_color(Color32::WHITE),
+            )
+            .on_hover_text("A == $10 && X > 3\nbreak loop+2 if [$0200] != 0\nwatch word[$FB]");
+
+        if input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+            let command = app.console_input.trim().to_string();
+
+            if command.is_empty() {
+                return;
+            }
+
+            match Console::run(app, &command) {
+                Ok(message) => app.emulator.debug(&message),
+                Err(e) => app
+                    .emulator
+                    .debugger
+                    .debug(&format!("{command}: {e}"), DebugKind::Warn),
+            }
+
+            app.console_input.clear();
+            input.request_focus();
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::coverage::Coverage;
+use std::fs;
+
+pub struct CoverageUi;
+
+impl View for CoverageUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.coverage.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.coverage = enabled.then(Coverage::default);
+            }
+
+            let coverage = match &mut app.emulator.coverage {
+                Some(coverage) => coverage,
+                None => return,
+            };
+
+            if ui.button("reset").clicked() {
+                coverage.clear();
+            }
+
+            if ui.button("export LCOV").clicked() {
+                let dialog = tinyfiledialogs::save_file_dialog_with_filter(
+                    "Export LCOV",
+                    "coverage.info",
+                    &["*.info"],
+                    "LCOV tracefile (*.info)",
+                );
+
+                if let Some(path) = dialog {
+                    let lcov = app
+                        .emulator
+                        .coverage_lcov("program.asm")
+                        .unwrap_or_default();
+
+                    if let Err(e) = fs::write(path, lcov) {
+                        app.error = Some(e.to_string());
+                    }
+                }
+            }
+
+            if ui.button("save report").clicked() {
+                let dialog = tinyfiledialogs::save_file_dialog("Save report", "coverage.txt");
+
+                if let Some(path) = dialog {
+                    let report = app
+                        .emulator
+                        .coverage_report(&app.loaded_source)
+                        .unwrap_or_default();
+
+                    if let Err(e) = fs::write(path, report) {
+                        app.error = Some(e.to_string());
+                    }
+                }
+            }
+        });
+
+        ui.separator();
+
+        if let Some(report) = app.emulator.coverage_report(&app.loaded_source) {
+            ScrollArea::both()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    ui.add(Label::new(RichText::new(report).monospace()));
+                });
+        }
+    }
+}
@@ -1,21 +1,41 @@
-use crate::{app::App, View, DEBUG_OUTPUT, DEBUG_UPDATE};
+use crate::{app::App, ui::Console, View, DEBUG_OUTPUT, DEBUG_UPDATE};
 use eframe::egui::*;
-use emulator::{CpuDebugger, DebugKind};
+use emulator::{replay::InputEvent, CpuDebugger, DebugKind, Debugger, StopReason};
+use std::sync::atomic::Ordering;
 
 pub struct DebuggerUi;
 
 impl View for DebuggerUi {
     fn ui(&mut self, ui: &mut Ui, app: &mut App) {
         ui.horizontal(|ui| {
+            if ui.button("step back").clicked() && !app.emulator.step_back() {
+                app.emulator.debug("No earlier state in history");
+            }
+
             if ui.button("step").clicked() {
-                app.emulator.step();
+                match app.emulator.step() {
+                    Some(StopReason::Brk) | None => {}
+                    Some(reason) => app
+                        .emulator
+                        .debugger
+                        .debug(&reason.to_string(), DebugKind::Warn),
+                }
+            }
+
+            if ui.button("IRQ").clicked() {
+                app.emulator.input(InputEvent::Irq);
+            }
+
+            if ui.button("NMI").clicked() {
+                app.emulator.input(InputEvent::Nmi);
             }
 
             if ui.button("clear").clicked() {
-                unsafe {
-                    DEBUG_OUTPUT.clear();
-                }
+                DEBUG_OUTPUT.lock().unwrap().clear();
             }
+
+            ui.separator();
+            Console.ui(ui, app);
         });
     }
 }
@@ -28,7 +48,9 @@ impl View for DebuggerOutput {
             .auto_shrink([false, false])
             .show(ui, |ui| {
                 ui.vertical(|ui| {
-                    let messages = unsafe { DEBUG_OUTPUT.clone() };
+                    let messages = DEBUG_OUTPUT.lock().unwrap().clone();
+                    let update = DEBUG_UPDATE.load(Ordering::Relaxed);
+
                     for debug in messages {
                         ui.horizontal(|ui| {
                             let time = Label::new(
@@ -43,7 +65,7 @@ impl View for DebuggerOutput {
                             };
                             let msg = Label::new(RichText::new(debug.1).monospace().color(color));
 
-                            if unsafe { DEBUG_UPDATE } {
+                            if update {
                                 ui.add(time);
                                 ui.add(msg);
                                 ui.scroll_to_cursor(Some(Align::Center));
@@ -56,10 +78,6 @@ impl View for DebuggerOutput {
                 });
             });
 
-        if unsafe { DEBUG_UPDATE } {
-            unsafe {
-                DEBUG_UPDATE = false;
-            }
-        }
+        DEBUG_UPDATE.store(false, Ordering::Relaxed);
     }
 }
@@ -1,5 +1,6 @@
 use crate::{app::App, View};
 use eframe::egui::*;
+use emulator::replay::InputEvent;
 
 pub struct Display;
 
@@ -24,7 +25,10 @@ impl View for Display {
             if !k.is_empty() {
                 let ascii = k[0].as_bytes()[0];
                 app.key_input = format!("{} ({ascii}) (0x00FF = 0x{ascii:X})", k[0]);
-                app.emulator.memory.mem[0x00FF] = ascii;
+                app.emulator.input(InputEvent::Poke {
+                    address: 0x00FF,
+                    data: ascii,
+                });
             }
         });
         ui.vertical_centered(|ui| {
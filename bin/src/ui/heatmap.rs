@@ -0,0 +1,100 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::heatmap::Heatmap;
+
+pub struct HeatmapUi;
+
+impl HeatmapUi {
+    const SCALE: f32 = 2.;
+
+    /// Maps `count` to `0..=255` on a log scale, so rarely accessed addresses stay visible.
+    fn intensity(count: u32, max: u32) -> u8 {
+        if count == 0 {
+            return 0;
+        }
+
+        let intensity = (count as f32).ln_1p() / (max as f32).ln_1p();
+        (64. + intensity * 191.) as u8
+    }
+}
+
+impl View for HeatmapUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.heatmap.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.heatmap = enabled.then(Heatmap::default);
+            }
+
+            let heatmap = match &mut app.emulator.heatmap {
+                Some(heatmap) => heatmap,
+                None => return,
+            };
+
+            if ui.button("reset").clicked() {
+                heatmap.clear();
+            }
+
+            if ui
+                .button("decay")
+                .on_hover_text("Halve every count")
+                .clicked()
+            {
+                heatmap.decay(0.5);
+            }
+
+            ui.label(RichText::new("R").color(Color32::RED));
+            ui.label("write");
+            ui.label(RichText::new("G").color(Color32::GREEN));
+            ui.label("read");
+            ui.label(RichText::new("B").color(Color32::LIGHT_BLUE));
+            ui.label("execute");
+        });
+
+        let heatmap = match &app.emulator.heatmap {
+            Some(heatmap) => heatmap,
+            None => return,
+        };
+
+        ui.separator();
+
+        // One pixel per address, one row per page
+        let max = heatmap.max();
+        let mut color_image = ColorImage::new([256, 256], Color32::BLACK);
+
+        for address in 0..=0xFFFF_u16 {
+            let counts = heatmap.get(address);
+
+            color_image[(address as usize & 0xFF, address as usize >> 8)] = Color32::from_rgb(
+                Self::intensity(counts.writes, max.writes),
+                Self::intensity(counts.reads, max.reads),
+                Self::intensity(counts.executes, max.executes),
+            );
+        }
+
+        let texture = ui
+            .ctx()
+            .load_texture("heatmap", color_image, TextureOptions::NEAREST);
+
+        let response = ui.image(&texture, Vec2::splat(256. * Self::SCALE));
+
+        if let Some(position) = response.hover_pos() {
+            let offset = (position - response.rect.min) / Self::SCALE;
+            let (x, y) = (
+                offset.x.clamp(0., 255.) as u16,
+                offset.y.clamp(0., 255.) as u16,
+            );
+            let address = y << 8 | x;
+            let counts = heatmap.get(address);
+
+            response.on_hover_text(
+                RichText::new(format!(
+                    "${address:04X}\nread    {}\nwrite   {}\nexecute {}",
+                    counts.reads, counts.writes, counts.executes
+                ))
+                .monospace(),
+            );
+        }
+    }
+}
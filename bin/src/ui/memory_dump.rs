@@ -1,6 +1,9 @@
-use crate::{app::App, View};
+use crate::{
+    app::{App, WriteQuery},
+    View,
+};
 use eframe::egui::*;
-use emulator::memory::memory_hexdump;
+use emulator::{memory::memory_hexdump, write_log::WriteLog};
 
 pub struct MemoryDumpOptions;
 
@@ -61,8 +64,33 @@ impl View for MemoryDump {
                                 .monospace(),
                         ));
                         ui.separator();
-                        for j in i.1 {
-                            ui.add(Label::new(RichText::new(format!(" {:02X}", j)).monospace()));
+                        for (offset, j) in i.1.iter().enumerate() {
+                            let address = i.0.wrapping_add(offset as u16);
+                            ui.add(
+                                Label::new(RichText::new(format!(" {:02X}", j)).monospace())
+                                    .sense(Sense::click()),
+                            )
+                            .context_menu(|ui| {
+                                ui.label(RichText::new(format!("0x{address:04X}")).monospace());
+                                ui.separator();
+                                if ui.button("who last wrote this?").clicked() {
+                                    app.write_query = Some(WriteQuery {
+                                        address,
+                                        instructions: None,
+                                    });
+                                    ui.close_menu();
+                                }
+                                if ui
+                                    .button("writes in the last 10,000 instructions")
+                                    .clicked()
+                                {
+                                    app.write_query = Some(WriteQuery {
+                                        address,
+                                        instructions: Some(WriteLog::DEFAULT_LIMIT),
+                                    });
+                                    ui.close_menu();
+                                }
+                            });
                         }
                         ui.separator();
                         for j in i.2 {
@@ -79,3 +107,78 @@ impl View for MemoryDump {
             });
     }
 }
+
+pub struct WriteHistory;
+
+impl View for WriteHistory {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        let query = match app.write_query {
+            Some(query) => query,
+            None => return,
+        };
+
+        let records = match query.instructions {
+            Some(instructions) => app.emulator.recent_writes(query.address, instructions),
+            None => match &app.emulator.write_log {
+                Some(write_log) => write_log.writes(query.address).copied().collect(),
+                None => Vec::new(),
+            },
+        };
+
+        ui.add(Label::new(
+            RichText::new(match query.instructions {
+                Some(instructions) => format!(
+                    "Writes to 0x{:04X} in the last {} instructions",
+                    query.address, instructions
+                ),
+                None => format!("Writes to 0x{:04X}", query.address),
+            })
+            .strong()
+            .monospace(),
+        ));
+
+        match records.last() {
+            Some(last) => ui.add(Label::new(
+                RichText::new(format!(
+                    "Last written by 0x{:04X} at cycle {}",
+                    last.pc, last.cycle
+                ))
+                .monospace()
+                .color(Color32::WHITE),
+            )),
+            None => ui.add(Label::new(
+                RichText::new("No writes recorded")
+                    .monospace()
+                    .color(Color32::YELLOW),
+            )),
+        };
+
+        ui.separator();
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for record in records.iter().rev() {
+                    ui.horizontal(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(format!("#{:<8}", record.cycle))
+                                .monospace()
+                                .color(Color32::LIGHT_BLUE),
+                        ));
+                        ui.separator();
+                        ui.add(Label::new(
+                            RichText::new(format!("0x{:04X}", record.pc))
+                                .monospace()
+                                .color(Color32::from_rgb(50, 180, 80)),
+                        ));
+                        ui.separator();
+                        ui.add(Label::new(
+                            RichText::new(format!("{:02X} -> {:02X}", record.old, record.new))
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                    });
+                }
+            });
+    }
+}
@@ -1,10 +1,116 @@
 use crate::{app::App, View, IS_RUNNING};
 use assembler::{disassemble, Assembler};
 use eframe::egui::{menu::menu_button, *};
-use std::{fs, sync::atomic::Ordering};
+use emulator::{
+    memory::ORG,
+    program::{Format, Program},
+    symbols::Symbols,
+};
+use std::{fs, path::Path, sync::atomic::Ordering};
 
 pub struct MenuBar;
 
+impl MenuBar {
+    /// Parses a program file, detecting its format from the extension or the contents.
+    /// Files in no known format are loaded as raw binaries. `None` if cancelled.
+    fn load_program(path: &str, binary: &[u8]) -> Result<Option<Program>, String> {
+        let format = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
+            .or_else(|| Format::detect(binary));
+
+        match format {
+            Some(Format::O65) => Self::o65_program(binary),
+            Some(format) => {
+                Self::entry_point(Program::parse(binary, format).map_err(|e| e.to_string())?)
+            }
+            None => Self::raw_program(binary),
+        }
+    }
+
+    /// Asks where to start a program without an entry point, e.g. a PRG file,
+    /// defaulting to its first segment. `None` if cancelled.
+    fn entry_point(program: Program) -> Result<Option<Program>, String> {
+        if program.entry.is_some() {
+            return Ok(Some(program));
+        }
+
+        let default = program
+            .segments
+            .first()
+            .map_or(ORG, |(address, _)| *address);
+
+        let Some(input) = tinyfiledialogs::input_box(
+            "Load program",
+            "Start address (hex)",
+            &format!("{default:04X}"),
+        ) else {
+            return Ok(None);
+        };
+
+        let hex = input.trim().trim_start_matches('$');
+        let entry = u16::from_str_radix(hex, 16).map_err(|e| format!("{input}: {e}"))?;
+
+        Ok(Some(program.with_entry(entry)))
+    }
+
+    /// Asks where to relocate an o65 object, defaulting to the address it was assembled for.
+    fn o65_program(binary: &[u8]) -> Result<Option<Program>, String> {
+        let program = Program::o65(binary, None).map_err(|e| e.to_string())?;
+        let default = program.entry.unwrap_or(ORG);
+
+        let Some(input) = tinyfiledialogs::input_box(
+            "Load o65 object",
+            "Relocate to address (hex)",
+            &format!("{default:04X}"),
+        ) else {
+            return Ok(None);
+        };
+
+        let hex = input.trim().trim_start_matches('$');
+        let base = u16::from_str_radix(hex, 16).map_err(|e| format!("{input}: {e}"))?;
+
+        Program::o65(binary, Some(base))
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Asks where to load a raw binary, `None` if cancelled.
+    ///
+    /// ROM images of 8, 16, 32 or 64 KiB default to ending at `$FFFF`, other binaries to `$8000`.
+    /// Binaries ending at `$FFFF` start at their reset vector, others at their load address.
+    fn raw_program(binary: &[u8]) -> Result<Option<Program>, String> {
+        let default = match binary.len() {
+            0x2000 | 0x4000 | 0x8000 | 0x10000 => 0x10000 - binary.len(),
+            _ => ORG as usize,
+        };
+
+        let Some(input) = tinyfiledialogs::input_box(
+            "Load binary",
+            "Load address (hex)",
+            &format!("{default:04X}"),
+        ) else {
+            return Ok(None);
+        };
+
+        let hex = input.trim().trim_start_matches('$');
+        let address = u16::from_str_radix(hex, 16).map_err(|e| format!("{input}: {e}"))?;
+
+        if address as usize + binary.len() > 0x10000 {
+            return Err(format!(
+                "{} bytes do not fit at ${address:04X}",
+                binary.len()
+            ));
+        }
+
+        match Program::rom(binary) {
+            Some(program) if program.segments[0].0 == address => Ok(Some(program)),
+            _ => Ok(Some(Program::raw(address, binary))),
+        }
+    }
+}
+
 impl View for MenuBar {
     fn ui(&mut self, ui: &mut Ui, app: &mut App) {
         ui.horizontal(|ui| {
@@ -52,13 +158,36 @@ impl View for MenuBar {
                     let dialog = tinyfiledialogs::open_file_dialog("Load binary", "", None);
 
                     if let Some(path) = dialog {
-                        let binary = fs::read(path);
+                        let binary = fs::read(&path);
                         match binary {
                             Ok(binary) => {
+                                let program = match Self::load_program(&path, &binary) {
+                                    Ok(Some(program)) => program,
+                                    Ok(None) => return,
+                                    Err(e) => {
+                                        app.error = Some(e);
+                                        return;
+                                    }
+                                };
+
                                 app.emulator.reset();
-                                app.emulator.load(&binary);
+                                app.emulator.load_program(&program);
+                                app.emulator.symbols.clear();
+
+                                // Disassemble the segment execution starts in
+                                let code = program
+                                    .segments
+                                    .iter()
+                                    .find(|(address, data)| {
+                                        program.entry.is_some_and(|entry| {
+                                            (*address as usize..*address as usize + data.len())
+                                                .contains(&(entry as usize))
+                                        })
+                                    })
+                                    .or(program.segments.first())
+                                    .map_or(&[][..], |(_, data)| data);
 
-                                app.source_input = match disassemble(&binary) {
+                                app.source_input = match disassemble(code) {
                                     Ok(disassembled) => disassembled
                                         .iter()
                                         .map(|(_, _, i)| i.clone())
@@ -105,6 +234,136 @@ impl View for MenuBar {
                 {
                     app.window_visibility.disassembler = !app.window_visibility.disassembler;
                 }
+                if ui
+                    .button(if app.window_visibility.provenance {
+                        "Close Provenance"
+                    } else {
+                        "Open Provenance"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.provenance = !app.window_visibility.provenance;
+                }
+                if ui
+                    .button(if app.window_visibility.breakpoints {
+                        "Close Breakpoints"
+                    } else {
+                        "Open Breakpoints"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.breakpoints = !app.window_visibility.breakpoints;
+                }
+                if ui
+                    .button(if app.window_visibility.watches {
+                        "Close Watches"
+                    } else {
+                        "Open Watches"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.watches = !app.window_visibility.watches;
+                }
+                if ui
+                    .button(if app.window_visibility.call_stack {
+                        "Close Call Stack"
+                    } else {
+                        "Open Call Stack"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.call_stack = !app.window_visibility.call_stack;
+                }
+                if ui
+                    .button(if app.window_visibility.profiler {
+                        "Close Profiler"
+                    } else {
+                        "Open Profiler"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.profiler = !app.window_visibility.profiler;
+                }
+                if ui
+                    .button(if app.window_visibility.coverage {
+                        "Close Coverage"
+                    } else {
+                        "Open Coverage"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.coverage = !app.window_visibility.coverage;
+                }
+                if ui
+                    .button(if app.window_visibility.heatmap {
+                        "Close Heatmap"
+                    } else {
+                        "Open Heatmap"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.heatmap = !app.window_visibility.heatmap;
+                }
+                if ui
+                    .button(if app.window_visibility.self_modifying {
+                        "Close Self-Modifying Code"
+                    } else {
+                        "Open Self-Modifying Code"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.self_modifying = !app.window_visibility.self_modifying;
+                }
+                if ui
+                    .button(if app.window_visibility.sanitizer {
+                        "Close Sanitizer"
+                    } else {
+                        "Open Sanitizer"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.sanitizer = !app.window_visibility.sanitizer;
+                }
+                if ui
+                    .button(if app.window_visibility.stack_sanitizer {
+                        "Close Stack Sanitizer"
+                    } else {
+                        "Open Stack Sanitizer"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.stack_sanitizer = !app.window_visibility.stack_sanitizer;
+                }
+                if ui
+                    .button(if app.window_visibility.clobber {
+                        "Close Interrupt Clobbers"
+                    } else {
+                        "Open Interrupt Clobbers"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.clobber = !app.window_visibility.clobber;
+                }
+                if ui
+                    .button(if app.window_visibility.trace {
+                        "Close Trace"
+                    } else {
+                        "Open Trace"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.trace = !app.window_visibility.trace;
+                }
+                if ui
+                    .button(if app.window_visibility.replay {
+                        "Close Input Replay"
+                    } else {
+                        "Open Input Replay"
+                    })
+                    .clicked()
+                {
+                    app.window_visibility.replay = !app.window_visibility.replay;
+                }
             });
 
             ui.separator();
@@ -128,7 +387,8 @@ impl View for MenuBar {
             }
 
             if ui.button("Load").clicked() {
-                let src = match Assembler::new(&app.source_input).assemble() {
+                let mut assembler = Assembler::new(&app.source_input);
+                let src = match assembler.assemble() {
                     Ok(src) => src,
                     Err(e) => {
                         app.error = Some(e.to_string());
@@ -138,6 +398,13 @@ impl View for MenuBar {
 
                 app.emulator.reset();
                 app.emulator.load(&src);
+                app.emulator.symbols = Symbols::new(assembler.symbols());
+
+                for (address, line) in assembler.lines() {
+                    app.emulator.symbols.insert_line(*address, *line);
+                }
+
+                app.loaded_source = app.source_input.clone();
                 app.error = None;
             }
 
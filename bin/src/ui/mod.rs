@@ -1,17 +1,45 @@
+pub mod breakpoints;
+pub mod call_stack;
+pub mod clobber;
+pub mod console;
+pub mod coverage;
 pub mod debugger;
 pub mod disassembler;
 pub mod display;
+pub mod heatmap;
 pub mod memory_dump;
 pub mod menu;
+pub mod profiler;
+pub mod provenance;
+pub mod replay;
+pub mod sanitizer;
+pub mod self_modifying;
 pub mod settings;
 pub mod source_input;
+pub mod stack_sanitizer;
 pub mod status;
+pub mod trace;
+pub mod watches;
 
+pub use breakpoints::*;
+pub use call_stack::*;
+pub use clobber::*;
+pub use console::*;
+pub use coverage::*;
 pub use debugger::*;
 pub use disassembler::*;
 pub use display::*;
+pub use heatmap::*;
 pub use memory_dump::*;
 pub use menu::*;
+pub use profiler::*;
+pub use provenance::*;
+pub use replay::*;
+pub use sanitizer::*;
+pub use self_modifying::*;
 pub use settings::*;
 pub use source_input::*;
+pub use stack_sanitizer::*;
 pub use status::*;
+pub use trace::*;
+pub use watches::*;
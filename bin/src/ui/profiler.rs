@@ -0,0 +1,66 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::profiler::Profiler;
+use std::fs;
+
+pub struct ProfilerUi;
+
+impl View for ProfilerUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.profiler.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.profiler = enabled.then(Profiler::default);
+            }
+
+            let profiler = match &mut app.emulator.profiler {
+                Some(profiler) => profiler,
+                None => return,
+            };
+
+            if ui.button("reset").clicked() {
+                profiler.clear();
+            }
+
+            if ui.button("export flame graph").clicked() {
+                let dialog = tinyfiledialogs::save_file_dialog_with_filter(
+                    "Export flame graph",
+                    "profile.folded",
+                    &["*.folded"],
+                    "Folded stacks (*.folded)",
+                );
+
+                if let Some(path) = dialog {
+                    if let Err(e) = fs::write(path, profiler.folded(&app.emulator.symbols)) {
+                        app.error = Some(e.to_string());
+                    }
+                }
+            }
+
+            if ui.button("save report").clicked() {
+                let dialog = tinyfiledialogs::save_file_dialog("Save report", "profile.txt");
+
+                if let Some(path) = dialog {
+                    let report = profiler.report(&app.emulator.symbols, usize::MAX);
+
+                    if let Err(e) = fs::write(path, report) {
+                        app.error = Some(e.to_string());
+                    }
+                }
+            }
+        });
+
+        ui.separator();
+
+        if let Some(profiler) = &app.emulator.profiler {
+            let report = profiler.report(&app.emulator.symbols, 20);
+
+            ScrollArea::both()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    ui.add(Label::new(RichText::new(report).monospace()));
+                });
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{app::App, View};
+use assembler::disassemble;
+use eframe::egui::*;
+use emulator::{
+    memory::MemoryBus,
+    provenance::{Location, Origin},
+    registers::Register,
+};
+
+pub struct ProvenanceUi;
+
+impl ProvenanceUi {
+    fn parse_location(input: &str) -> Option<Location> {
+        let input = input.trim();
+
+        Some(match input.to_uppercase().as_str() {
+            "A" => Location::Register(Register::A),
+            "X" => Location::Register(Register::X),
+            "Y" => Location::Register(Register::Y),
+            "P" => Location::Register(Register::P),
+            "SP" => Location::Register(Register::SP),
+            _ => {
+                let address = input.trim_start_matches('$').trim_start_matches("0x");
+                Location::Memory(u16::from_str_radix(address, 16).ok()?)
+            }
+        })
+    }
+
+    fn instruction(app: &App, origin: &Origin) -> String {
+        let bytes = (0..3)
+            .map(|i| app.emulator.memory.peek(origin.pc.wrapping_add(i)))
+            .collect::<Vec<_>>();
+
+        match disassemble(&bytes) {
+            Ok(disassembled) if !disassembled.is_empty() => disassembled[0].2.clone(),
+            _ => format!("??? (0x{:02X})", origin.opcode),
+        }
+    }
+}
+
+impl View for ProvenanceUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            ui.add(Label::new("Location"));
+            ui.add(
+                TextEdit::singleline(&mut app.provenance_input)
+                    .desired_width(64.)
+                    .font(FontId::new(10., FontFamily::Monospace))
+                    .text_color(Color32::WHITE),
+            )
+            .on_hover_text("A, X, Y, P, SP or a memory address ($0010)");
+        });
+
+        ui.separator();
+
+        let location = match Self::parse_location(&app.provenance_input) {
+            Some(location) => location,
+            None => {
+                ui.add(Label::new(
+                    RichText::new("Invalid location")
+                        .monospace()
+                        .color(Color32::RED),
+                ));
+                return;
+            }
+        };
+
+        let provenance = match &app.emulator.provenance {
+            Some(provenance) => provenance,
+            None => return,
+        };
+
+        let chain = provenance.chain(location);
+
+        if chain.is_empty() {
+            ui.add(Label::new(
+                RichText::new(format!("{location} has no known origin"))
+                    .monospace()
+                    .color(Color32::YELLOW),
+            ));
+            return;
+        }
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for origin in chain {
+                    ui.horizontal(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(format!("0x{:04X}", origin.pc))
+                                .monospace()
+                                .color(Color32::from_rgb(50, 180, 80)),
+                        ));
+                        ui.separator();
+                        ui.add(Label::new(
+                            RichText::new(format!("{:<12}", Self::instruction(app, origin)))
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.separator();
+                        ui.add(Label::new(RichText::new(origin.to_string()).monospace()));
+                    });
+                    ui.separator();
+                }
+            });
+    }
+}
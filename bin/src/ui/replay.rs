@@ -0,0 +1,96 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::replay::Replay;
+
+pub struct ReplayUi;
+
+impl View for ReplayUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            if ui.button("record").clicked() {
+                app.emulator.replay = Some(Replay::recording());
+            }
+
+            if ui.button("load replay").clicked() {
+                let dialog = tinyfiledialogs::open_file_dialog("Load replay", "", None);
+
+                if let Some(path) = dialog {
+                    match Replay::load(path) {
+                        Ok(replay) => {
+                            app.emulator.replay = Some(replay);
+                            app.error = None;
+                        }
+                        Err(e) => app.error = Some(e.to_string()),
+                    }
+                }
+            }
+
+            if let Some(replay) = &app.emulator.replay {
+                if !replay.is_replaying() && ui.button("save").clicked() {
+                    let dialog = tinyfiledialogs::save_file_dialog("Save replay", "inputs.replay");
+
+                    if let Some(path) = dialog {
+                        if let Err(e) = replay.save(path) {
+                            app.error = Some(e.to_string());
+                        }
+                    }
+                }
+
+                if ui.button("stop").clicked() {
+                    app.emulator.replay = None;
+                }
+            }
+        });
+
+        let replay = match &app.emulator.replay {
+            Some(replay) => replay,
+            None => {
+                ui.label("Inputs are not recorded");
+                return;
+            }
+        };
+
+        if replay.is_replaying() {
+            ui.label(if replay.is_finished() {
+                "Replay finished"
+            } else {
+                "Replaying, other inputs are ignored"
+            });
+        } else {
+            ui.label(format!("Recording ({} inputs)", replay.records().len()));
+        }
+
+        ui.label(
+            RichText::new("Inputs are timed from the last reset: reset and reload the program before recording or replaying")
+                .color(Color32::GRAY),
+        );
+
+        ui.separator();
+        ui.add(Label::new("Inputs (newest first)"));
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                Grid::new("replay").striped(true).show(ui, |ui| {
+                    for record in replay.records().iter().rev() {
+                        ui.add(Label::new(
+                            RichText::new(format!("{:>8}", record.instruction))
+                                .monospace()
+                                .color(Color32::LIGHT_BLUE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(format!("{:>10}", record.cycle))
+                                .monospace()
+                                .color(Color32::GRAY),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(record.event.to_string())
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::sanitizer::Sanitizer;
+
+pub struct SanitizerUi;
+
+impl View for SanitizerUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.sanitizer.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.sanitizer = enabled.then(Sanitizer::default);
+            }
+
+            if let Some(sanitizer) = &mut app.emulator.sanitizer {
+                ui.checkbox(&mut sanitizer.stop, "stop on violation");
+
+                if ui.button("clear").clicked() {
+                    sanitizer.clear();
+                }
+            }
+        });
+
+        let sanitizer = match &app.emulator.sanitizer {
+            Some(sanitizer) => sanitizer,
+            None => return,
+        };
+
+        ui.separator();
+        ui.add(Label::new("Violations (newest first)"));
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                Grid::new("sanitizer").striped(true).show(ui, |ui| {
+                    for violation in sanitizer.violations().rev() {
+                        ui.add(Label::new(
+                            RichText::new(format!("{:>8}", violation.cycle))
+                                .monospace()
+                                .color(Color32::LIGHT_BLUE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(app.emulator.symbols.describe(violation.pc))
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(violation.to_string())
+                                .monospace()
+                                .color(Color32::YELLOW),
+                        ));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
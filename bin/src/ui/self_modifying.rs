@@ -0,0 +1,56 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::self_modifying::SelfModifyingCode;
+
+pub struct SelfModifyingUi;
+
+impl View for SelfModifyingUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.self_modifying.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.self_modifying = enabled.then(SelfModifyingCode::default);
+            }
+
+            if let Some(self_modifying) = &mut app.emulator.self_modifying {
+                if ui.button("clear").clicked() {
+                    self_modifying.clear();
+                }
+            }
+        });
+
+        let self_modifying = match &app.emulator.self_modifying {
+            Some(self_modifying) => self_modifying,
+            None => return,
+        };
+
+        ui.separator();
+        ui.add(Label::new("Modifications (newest first)"));
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                Grid::new("self_modifying").striped(true).show(ui, |ui| {
+                    for modification in self_modifying.modifications().rev() {
+                        ui.add(Label::new(
+                            RichText::new(format!("{:>8}", modification.cycle))
+                                .monospace()
+                                .color(Color32::LIGHT_BLUE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(app.emulator.symbols.describe(modification.pc))
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(modification.to_string())
+                                .monospace()
+                                .color(Color32::YELLOW),
+                        ));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
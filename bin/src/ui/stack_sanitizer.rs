@@ -0,0 +1,60 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::{stack_sanitizer::StackSanitizer, DebugKind};
+
+pub struct StackSanitizerUi;
+
+impl View for StackSanitizerUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let mut enabled = app.emulator.stack_sanitizer.is_some();
+
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                app.emulator.stack_sanitizer = enabled.then(StackSanitizer::default);
+            }
+
+            if let Some(stack_sanitizer) = &mut app.emulator.stack_sanitizer {
+                ui.checkbox(&mut stack_sanitizer.stop, "stop on issue");
+
+                if ui.button("clear").clicked() {
+                    stack_sanitizer.clear();
+                }
+            }
+        });
+
+        let stack_sanitizer = match &app.emulator.stack_sanitizer {
+            Some(stack_sanitizer) => stack_sanitizer,
+            None => return,
+        };
+
+        ui.label(match stack_sanitizer.high_water_mark() {
+            Some(address) => format!("High-water mark: 0x{address:04X}"),
+            None => "High-water mark: -".to_string(),
+        });
+
+        ui.separator();
+        ui.add(Label::new("Issues (newest first)"));
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for issue in stack_sanitizer.issues().rev() {
+                    let color = match issue.kind() {
+                        DebugKind::Error => Color32::RED,
+                        _ => Color32::YELLOW,
+                    };
+
+                    ui.horizontal(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(app.emulator.symbols.describe(issue.pc()))
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.add(Label::new(
+                            RichText::new(issue.to_string()).monospace().color(color),
+                        ));
+                    });
+                }
+            });
+    }
+}
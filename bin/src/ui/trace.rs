@@ -0,0 +1,226 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::{
+    trace::{TraceState, Tracer, Trigger},
+    trace_diff::TraceDiff,
+    vcd::VcdWriter,
+};
+use std::ops::RangeInclusive;
+
+pub struct TraceUi;
+
+impl TraceUi {
+    /// Parses a trigger: a hex address range (`8000`, `8000-80FF`) or a cycle count (`5000cyc`).
+    fn parse(input: &str) -> Result<Option<Trigger>, String> {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(cycle) = input.strip_suffix("cyc") {
+            return cycle
+                .trim()
+                .parse()
+                .map(|cycle| Some(Trigger::Cycle(cycle)))
+                .map_err(|e| format!("{input}: {e}"));
+        }
+
+        Ok(Some(Trigger::Pc(Self::parse_range(input)?)))
+    }
+
+    /// Parses a hex address range, `8000` or `8000-80FF`.
+    fn parse_range(input: &str) -> Result<RangeInclusive<u16>, String> {
+        let hex = |address: &str| {
+            let address = address.trim().trim_start_matches('$');
+            u16::from_str_radix(address, 16).map_err(|e| format!("{address}: {e}"))
+        };
+
+        let (start, end) = input.split_once('-').unwrap_or((input, input));
+
+        Ok(hex(start)?..=hex(end)?)
+    }
+
+    /// Parses comma separated chip selects, `ram=0000-7FFF, rom=8000-FFFF`.
+    fn parse_chip_selects(input: &str) -> Result<Vec<(String, RangeInclusive<u16>)>, String> {
+        input
+            .split(',')
+            .filter(|chip_select| !chip_select.trim().is_empty())
+            .map(|chip_select| {
+                let (name, range) = chip_select
+                    .split_once('=')
+                    .ok_or_else(|| format!("{chip_select}: expected name=range"))?;
+
+                Ok((name.trim().to_string(), Self::parse_range(range)?))
+            })
+            .collect()
+    }
+
+    fn record_vcd(app: &mut App) -> Result<(), String> {
+        let chip_selects = Self::parse_chip_selects(&app.trace_form.chip_selects)?;
+
+        let dialog = tinyfiledialogs::save_file_dialog_with_filter(
+            "Save waveform",
+            "bus.vcd",
+            &["*.vcd"],
+            "Value Change Dump (*.vcd)",
+        );
+
+        if let Some(path) = dialog {
+            let mut vcd = VcdWriter::file(path).map_err(|e| e.to_string())?;
+            vcd.chip_selects = chip_selects;
+            app.emulator.vcd = Some(vcd);
+        }
+
+        Ok(())
+    }
+
+    fn start(app: &mut App) -> Result<(), String> {
+        let start = Self::parse(&app.trace_form.start)?;
+        let stop = Self::parse(&app.trace_form.stop)?;
+
+        let dialog = tinyfiledialogs::save_file_dialog_with_filter(
+            "Save trace",
+            "trace.log",
+            &["*.log"],
+            "Trace log (*.log)",
+        );
+
+        if let Some(path) = dialog {
+            let mut tracer = Tracer::file(path).map_err(|e| e.to_string())?;
+            tracer.start = start;
+            tracer.stop = stop;
+            app.emulator.tracer = Some(tracer);
+        }
+
+        Ok(())
+    }
+
+    /// Compares two trace files picked by the user.
+    fn diff(app: &mut App) -> Result<(), String> {
+        let left = tinyfiledialogs::open_file_dialog("Left trace", "", None);
+        let right = left
+            .as_ref()
+            .and_then(|_| tinyfiledialogs::open_file_dialog("Right trace", "", None));
+
+        if let (Some(left), Some(right)) = (left, right) {
+            let divergence = TraceDiff::default()
+                .compare_files(left, right)
+                .map_err(|e| e.to_string())?;
+
+            app.trace_form.diff = Some(match divergence {
+                Some(divergence) => divergence.to_string(),
+                None => "Traces are identical".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl View for TraceUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        Grid::new("trace_triggers").num_columns(2).show(ui, |ui| {
+            ui.label("Start");
+            ui.add(
+                TextEdit::singleline(&mut app.trace_form.start)
+                    .desired_width(150.)
+                    .hint_text("8000-80FF / 5000cyc")
+                    .font(TextStyle::Monospace),
+            );
+            ui.end_row();
+
+            ui.label("Stop");
+            ui.add(
+                TextEdit::singleline(&mut app.trace_form.stop)
+                    .desired_width(150.)
+                    .hint_text("8000-80FF / 5000cyc")
+                    .font(TextStyle::Monospace),
+            );
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("start trace").clicked() {
+                app.error = Self::start(app).err();
+            }
+
+            if app.emulator.tracer.is_some() && ui.button("stop trace").clicked() {
+                // Dropping the tracer flushes the file
+                app.emulator.tracer = None;
+            }
+
+            if ui.button("compare traces").clicked() {
+                app.error = Self::diff(app).err();
+            }
+        });
+
+        if let Some(diff) = &app.trace_form.diff {
+            ui.separator();
+            ScrollArea::both()
+                .id_source("trace_diff")
+                .max_height(200.)
+                .show(ui, |ui| {
+                    ui.add(Label::new(
+                        RichText::new(diff).monospace().color(Color32::YELLOW),
+                    ));
+                });
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Chip selects");
+            ui.add(
+                TextEdit::singleline(&mut app.trace_form.chip_selects)
+                    .desired_width(250.)
+                    .hint_text("ram=0000-7FFF, rom=8000-FFFF")
+                    .font(TextStyle::Monospace),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            if app.emulator.vcd.is_none() {
+                if ui.button("record VCD").clicked() {
+                    app.error = Self::record_vcd(app).err();
+                }
+            } else if ui.button("stop VCD").clicked() {
+                app.emulator.vcd = None;
+            }
+
+            if let Some(e) = app.emulator.vcd.as_ref().and_then(VcdWriter::error) {
+                ui.label(RichText::new(e.to_string()).color(Color32::RED));
+            }
+        });
+
+        ui.separator();
+
+        let tracer = match &app.emulator.tracer {
+            Some(tracer) => tracer,
+            None => {
+                ui.label("Not tracing");
+                return;
+            }
+        };
+
+        let state = match tracer.state() {
+            TraceState::Waiting => "waiting for start trigger",
+            TraceState::Tracing => "tracing",
+            TraceState::Done => "done",
+        };
+
+        ui.label(format!("{} ({} lines)", state, tracer.lines()));
+
+        if let Some(e) = tracer.error() {
+            ui.label(RichText::new(e.to_string()).color(Color32::RED));
+        }
+
+        ui.separator();
+        ui.add(Label::new("Next instruction"));
+        ui.add(Label::new(
+            RichText::new(app.emulator.trace_line())
+                .monospace()
+                .color(Color32::WHITE),
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{app::App, View};
+use eframe::egui::*;
+use emulator::expression::Expression;
+
+pub struct WatchesUi;
+
+impl View for WatchesUi {
+    fn ui(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            let input = ui
+                .add(
+                    TextEdit::singleline(&mut app.watch_input)
+                        .desired_width(160.)
+                        .hint_text("expression")
+                        .font(FontId::new(10., FontFamily::Monospace))
+                        .text_color(Color32::WHITE),
+                )
+                .on_hover_text("e.g. word[$FB], [label+2], A & $80");
+
+            let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+
+            if ui.button("add").clicked() || submitted {
+                match Expression::parse(&app.watch_input) {
+                    Ok(expression) => {
+                        app.watches.push(expression);
+                        app.watch_input.clear();
+                        app.error = None;
+                    }
+                    Err(e) => app.error = Some(e.to_string()),
+                }
+            }
+        });
+
+        ui.separator();
+
+        let mut removed = None;
+
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for (i, watch) in app.watches.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(Label::new(
+                            RichText::new(watch.to_string())
+                                .monospace()
+                                .color(Color32::WHITE),
+                        ));
+                        ui.separator();
+
+                        let value = match app.emulator.evaluate(watch) {
+                            Ok(value) => RichText::new(format!("${:04X} ({value})", value as u16))
+                                .monospace()
+                                .color(Color32::from_rgb(50, 180, 80)),
+                            Err(e) => RichText::new(e.to_string()).monospace().color(Color32::RED),
+                        };
+                        ui.add(Label::new(value));
+
+                        if ui.button("remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+            });
+
+        if let Some(i) = removed {
+            app.watches.remove(i);
+        }
+    }
+}
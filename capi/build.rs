@@ -0,0 +1,17 @@
+use std::env;
+
+/// Regenerates `include/emulator6502.h` from the exported functions and types.
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).unwrap();
+
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{crate_dir}/src/lib.rs"))
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(format!("{crate_dir}/include/emulator6502.h"));
+
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
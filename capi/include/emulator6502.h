@@ -0,0 +1,169 @@
+/* C API of the 6502 emulator and assembler. */
+
+#ifndef EMULATOR6502_H
+#define EMULATOR6502_H
+
+/* Generated by cbindgen from capi/src, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Kind of an assembler error.
+typedef enum EmuAssemblerErrorKind {
+  EMU_ASSEMBLER_ERROR_KIND_ILLEGAL_CHARACTER,
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_NUMBER,
+  EMU_ASSEMBLER_ERROR_KIND_UNEXPECTED_TOKEN,
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_OPERAND,
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_LABEL,
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_INSTRUCTION,
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_MNEMONIC,
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_OPCODE,
+  // The source is not valid UTF-8.
+  EMU_ASSEMBLER_ERROR_KIND_INVALID_ENCODING,
+} EmuAssemblerErrorKind;
+
+// Result of the functions that can fail.
+typedef enum EmuStatus {
+  EMU_STATUS_OK,
+  EMU_STATUS_NULL_POINTER,
+  // The address range does not fit in the 64 KiB address space.
+  EMU_STATUS_OUT_OF_RANGE,
+  EMU_STATUS_INVALID_ARGUMENT,
+} EmuStatus;
+
+// Why `emu_cpu_step` or `emu_cpu_run` stopped.
+typedef enum EmuStop {
+  // The instruction was executed, and nothing stopped execution.
+  EMU_STOP_NONE,
+  // A `BRK` instruction was executed.
+  EMU_STOP_BRK,
+  EMU_STOP_BREAKPOINT,
+  EMU_STOP_SANITIZER,
+  // The instruction or cycle budget ran out.
+  EMU_STOP_BUDGET,
+  // The emulator panicked, the CPU state is unspecified.
+  EMU_STOP_PANIC,
+  EMU_STOP_NULL_POINTER,
+  // The stack sanitizer flagged a stack misuse.
+  EMU_STOP_STACK_SANITIZER,
+} EmuStop;
+
+// Result of `emu_assemble`: the assembled bytes, or an error.
+typedef struct EmuAssembly EmuAssembly;
+
+// A CPU with 64 KiB of RAM, where ranges can be mapped to callbacks with `emu_cpu_map`.
+typedef struct EmuCpu EmuCpu;
+
+// CPU registers.
+typedef struct EmuRegisters {
+  uint8_t a;
+  uint8_t x;
+  uint8_t y;
+  uint8_t p;
+  uint8_t sp;
+  uint16_t pc;
+} EmuRegisters;
+
+// Called when the CPU reads a mapped address, returns the data on the bus.
+typedef uint8_t (*EmuReadCallback)(void *user_data, uint16_t address);
+
+// Called when the CPU writes a mapped address.
+typedef void (*EmuWriteCallback)(void *user_data, uint16_t address, uint8_t data);
+
+// An assembler error. `message` is owned by the `EmuAssembly` it came from.
+typedef struct EmuAssemblerError {
+  enum EmuAssemblerErrorKind kind;
+  size_t line;
+  size_t column;
+  const char *message;
+} EmuAssemblerError;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a CPU in the reset state, free it with `emu_cpu_free`.
+struct EmuCpu *emu_cpu_new(void);
+
+void emu_cpu_free(struct EmuCpu *cpu);
+
+// Resets the registers and counters, and clears RAM. Mappings are kept.
+enum EmuStatus emu_cpu_reset(struct EmuCpu *cpu);
+
+// Copies `len` bytes from `data` to RAM at `address`, bypassing mapped callbacks.
+enum EmuStatus emu_cpu_load(struct EmuCpu *cpu, uint16_t address, const uint8_t *data, size_t len);
+
+// Copies `len` bytes of RAM at `address` to `data`, bypassing mapped callbacks.
+enum EmuStatus emu_cpu_read_memory(const struct EmuCpu *cpu,
+                                   uint16_t address,
+                                   uint8_t *data,
+                                   size_t len);
+
+// Reads RAM without side effects, `0` for a `NULL` CPU.
+uint8_t emu_cpu_peek(const struct EmuCpu *cpu, uint16_t address);
+
+// Writes RAM without side effects.
+void emu_cpu_poke(struct EmuCpu *cpu, uint16_t address, uint8_t data);
+
+enum EmuStatus emu_cpu_get_registers(const struct EmuCpu *cpu, struct EmuRegisters *registers);
+
+enum EmuStatus emu_cpu_set_registers(struct EmuCpu *cpu, const struct EmuRegisters *registers);
+
+// Number of cycles elapsed since the last reset.
+uint64_t emu_cpu_cycles(const struct EmuCpu *cpu);
+
+// Number of instructions executed since the last reset.
+uint64_t emu_cpu_instructions(const struct EmuCpu *cpu);
+
+// Requests an interrupt, taken after the current instruction once interrupts are enabled.
+void emu_cpu_irq(struct EmuCpu *cpu);
+
+// Requests a non-maskable interrupt, taken after the current instruction.
+void emu_cpu_nmi(struct EmuCpu *cpu);
+
+// Maps CPU accesses to `start..=end` to callbacks, called with `user_data`.
+//
+// Either callback may be `NULL`, those accesses then go to RAM.
+// Later mappings take precedence over earlier overlapping ones.
+enum EmuStatus emu_cpu_map(struct EmuCpu *cpu,
+                           uint16_t start,
+                           uint16_t end,
+                           EmuReadCallback read,
+                           EmuWriteCallback write,
+                           void *user_data);
+
+// Removes the mappings of exactly `start..=end`.
+enum EmuStatus emu_cpu_unmap(struct EmuCpu *cpu, uint16_t start, uint16_t end);
+
+// Executes one instruction.
+enum EmuStop emu_cpu_step(struct EmuCpu *cpu);
+
+// Executes instructions until one stops execution, or a budget runs out.
+//
+// `max_instructions` and `max_cycles` count from this call, `0` is no limit.
+// Returns `EMU_STOP_NONE` only when `max_instructions` is 1 and nothing else stopped.
+enum EmuStop emu_cpu_run(struct EmuCpu *cpu, uint64_t max_instructions, uint64_t max_cycles);
+
+// Assembles the NUL-terminated `source`, the program starts at `0x8000`.
+//
+// Returns `NULL` if `source` is `NULL`. The result must be freed with `emu_assembly_free`.
+struct EmuAssembly *emu_assemble(const char *source);
+
+void emu_assembly_free(struct EmuAssembly *assembly);
+
+bool emu_assembly_is_ok(const struct EmuAssembly *assembly);
+
+// The assembled bytes, with their count in `len`, or `NULL` if assembling failed.
+const uint8_t *emu_assembly_bytes(const struct EmuAssembly *assembly, size_t *len);
+
+// Writes the error to `error`, returns `false` if assembling succeeded.
+bool emu_assembly_error(const struct EmuAssembly *assembly, struct EmuAssemblerError *error);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* EMULATOR6502_H */
@@ -0,0 +1,145 @@
+use assembler::{Assembler, AssemblerError, AssemblerErrorKind, Position};
+use std::ffi::{c_char, CStr, CString};
+
+/// Kind of an assembler error.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmuAssemblerErrorKind {
+    IllegalCharacter,
+    InvalidNumber,
+    UnexpectedToken,
+    InvalidOperand,
+    InvalidLabel,
+    InvalidInstruction,
+    InvalidMnemonic,
+    InvalidOpcode,
+    /// The source is not valid UTF-8.
+    InvalidEncoding,
+}
+
+impl From<&AssemblerErrorKind> for EmuAssemblerErrorKind {
+    fn from(kind: &AssemblerErrorKind) -> Self {
+        match kind {
+            AssemblerErrorKind::IllegalCharacter(_) => EmuAssemblerErrorKind::IllegalCharacter,
+            AssemblerErrorKind::InvalidNumber => EmuAssemblerErrorKind::InvalidNumber,
+            AssemblerErrorKind::UnexpectedToken { .. } | AssemblerErrorKind::UnexpectedToken2 => {
+                EmuAssemblerErrorKind::UnexpectedToken
+            }
+            AssemblerErrorKind::InvalidOperand(_) => EmuAssemblerErrorKind::InvalidOperand,
+            AssemblerErrorKind::InvalidLabel(_) => EmuAssemblerErrorKind::InvalidLabel,
+            AssemblerErrorKind::InvalidInstruction(..) => EmuAssemblerErrorKind::InvalidInstruction,
+            AssemblerErrorKind::InvalidMnemonic(_) => EmuAssemblerErrorKind::InvalidMnemonic,
+            AssemblerErrorKind::InvalidOpcode(_) => EmuAssemblerErrorKind::InvalidOpcode,
+        }
+    }
+}
+
+/// An assembler error. `message` is owned by the `EmuAssembly` it came from.
+#[repr(C)]
+pub struct EmuAssemblerError {
+    pub kind: EmuAssemblerErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub message: *const c_char,
+}
+
+struct Error {
+    kind: EmuAssemblerErrorKind,
+    position: Position,
+    message: CString,
+}
+
+impl From<AssemblerError> for Error {
+    fn from(error: AssemblerError) -> Self {
+        Error {
+            kind: (&error.kind).into(),
+            position: error.position,
+            // Messages quote the source, which cannot contain NUL bytes as it came from a C string
+            message: CString::new(error.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Result of `emu_assemble`: the assembled bytes, or an error.
+pub struct EmuAssembly {
+    result: Result<Vec<u8>, Error>,
+}
+
+/// Assembles the NUL-terminated `source`, the program starts at `0x8000`.
+///
+/// Returns `NULL` if `source` is `NULL`. The result must be freed with `emu_assembly_free`.
+#[no_mangle]
+pub unsafe extern "C" fn emu_assemble(source: *const c_char) -> *mut EmuAssembly {
+    if source.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    let result = match CStr::from_ptr(source).to_str() {
+        Ok(source) => Assembler::new(source).assemble().map_err(Error::from),
+        Err(e) => Err(Error {
+            kind: EmuAssemblerErrorKind::InvalidEncoding,
+            position: Position::default(),
+            message: CString::new(e.to_string()).unwrap_or_default(),
+        }),
+    };
+
+    Box::into_raw(Box::new(EmuAssembly { result }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emu_assembly_free(assembly: *mut EmuAssembly) {
+    if !assembly.is_null() {
+        drop(Box::from_raw(assembly));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emu_assembly_is_ok(assembly: *const EmuAssembly) -> bool {
+    assembly
+        .as_ref()
+        .is_some_and(|assembly| assembly.result.is_ok())
+}
+
+/// The assembled bytes, with their count in `len`, or `NULL` if assembling failed.
+#[no_mangle]
+pub unsafe extern "C" fn emu_assembly_bytes(
+    assembly: *const EmuAssembly,
+    len: *mut usize,
+) -> *const u8 {
+    let bytes = match assembly.as_ref().map(|assembly| &assembly.result) {
+        Some(Ok(bytes)) => bytes,
+        _ => return std::ptr::null(),
+    };
+
+    if let Some(len) = len.as_mut() {
+        *len = bytes.len();
+    }
+
+    bytes.as_ptr()
+}
+
+/// Writes the error to `error`, returns `false` if assembling succeeded.
+#[no_mangle]
+pub unsafe extern "C" fn emu_assembly_error(
+    assembly: *const EmuAssembly,
+    error: *mut EmuAssemblerError,
+) -> bool {
+    let (e, error) = match (
+        assembly.as_ref().map(|assembly| &assembly.result),
+        error.as_mut(),
+    ) {
+        (Some(Err(e)), Some(error)) => (e, error),
+        _ => return false,
+    };
+
+    let Position(line, column) = e.position;
+
+    *error = EmuAssemblerError {
+        kind: e.kind,
+        line,
+        column,
+        message: e.message.as_ptr(),
+    };
+
+    true
+}
@@ -0,0 +1,352 @@
+//! # C API
+//!
+//! Exposes the emulator and the assembler to C and C++, see `include/emulator6502.h`.
+//!
+//! # Safety
+//!
+//! Every pointer passed to these functions must be `NULL` or valid for the access,
+//! and handles must come from the matching `*_new` function and not be used after being freed.
+//! Functions given a `NULL` handle do nothing, and return `EMU_STATUS_NULL_POINTER` where they return a status.
+//! Callbacks must not unwind, and must not use the CPU they are called from.
+#![allow(clippy::missing_safety_doc)]
+
+mod assembler;
+mod memory;
+
+pub use assembler::*;
+pub use memory::*;
+
+use emulator::{
+    cpu::NoneDebuggerCpu, memory::MemoryBus, registers::RegisterState, CpuDebugger, InputEvent,
+    StopReason,
+};
+use std::{
+    ffi::c_void,
+    panic::{self, AssertUnwindSafe},
+    slice,
+};
+
+/// Result of the functions that can fail.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmuStatus {
+    Ok,
+    NullPointer,
+    /// The address range does not fit in the 64 KiB address space.
+    OutOfRange,
+    InvalidArgument,
+}
+
+/// Why `emu_cpu_step` or `emu_cpu_run` stopped.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmuStop {
+    /// The instruction was executed, and nothing stopped execution.
+    None,
+    /// A `BRK` instruction was executed.
+    Brk,
+    Breakpoint,
+    Sanitizer,
+    /// The instruction or cycle budget ran out.
+    Budget,
+    /// The emulator panicked, the CPU state is unspecified.
+    Panic,
+    NullPointer,
+    /// The stack sanitizer flagged a stack misuse.
+    StackSanitizer,
+}
+
+impl From<Option<StopReason>> for EmuStop {
+    fn from(reason: Option<StopReason>) -> Self {
+        match reason {
+            None => EmuStop::None,
+            Some(StopReason::Brk) => EmuStop::Brk,
+            Some(StopReason::Breakpoint(_)) => EmuStop::Breakpoint,
+            Some(StopReason::Sanitizer(_)) => EmuStop::Sanitizer,
+            Some(StopReason::StackSanitizer(_)) => EmuStop::StackSanitizer,
+        }
+    }
+}
+
+/// CPU registers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmuRegisters {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub p: u8,
+    pub sp: u8,
+    pub pc: u16,
+}
+
+impl From<RegisterState> for EmuRegisters {
+    fn from(state: RegisterState) -> Self {
+        EmuRegisters {
+            a: state.a,
+            x: state.x,
+            y: state.y,
+            p: state.p,
+            sp: state.sp,
+            pc: state.pc,
+        }
+    }
+}
+
+impl From<EmuRegisters> for RegisterState {
+    fn from(registers: EmuRegisters) -> Self {
+        RegisterState {
+            a: registers.a,
+            x: registers.x,
+            y: registers.y,
+            p: registers.p,
+            sp: registers.sp,
+            pc: registers.pc,
+        }
+    }
+}
+
+/// A CPU with 64 KiB of RAM, where ranges can be mapped to callbacks with `emu_cpu_map`.
+pub struct EmuCpu {
+    cpu: NoneDebuggerCpu<CallbackMemory>,
+}
+
+/// Start and length of `address..address + len`, if it fits in the address space.
+fn range(address: u16, len: usize) -> Option<std::ops::Range<usize>> {
+    let start = address as usize;
+    let end = start.checked_add(len)?;
+
+    (end <= 0x10000).then_some(start..end)
+}
+
+/// Creates a CPU in the reset state, free it with `emu_cpu_free`.
+#[no_mangle]
+pub extern "C" fn emu_cpu_new() -> *mut EmuCpu {
+    let mut cpu = NoneDebuggerCpu::new(CallbackMemory::default());
+    cpu.reset();
+
+    Box::into_raw(Box::new(EmuCpu { cpu }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_free(cpu: *mut EmuCpu) {
+    if !cpu.is_null() {
+        drop(Box::from_raw(cpu));
+    }
+}
+
+/// Resets the registers and counters, and clears RAM. Mappings are kept.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_reset(cpu: *mut EmuCpu) -> EmuStatus {
+    let Some(cpu) = cpu.as_mut() else {
+        return EmuStatus::NullPointer;
+    };
+
+    cpu.cpu.reset();
+    EmuStatus::Ok
+}
+
+/// Copies `len` bytes from `data` to RAM at `address`, bypassing mapped callbacks.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_load(
+    cpu: *mut EmuCpu,
+    address: u16,
+    data: *const u8,
+    len: usize,
+) -> EmuStatus {
+    let Some(cpu) = cpu.as_mut() else {
+        return EmuStatus::NullPointer;
+    };
+
+    if data.is_null() && len > 0 {
+        return EmuStatus::NullPointer;
+    }
+
+    let Some(range) = range(address, len) else {
+        return EmuStatus::OutOfRange;
+    };
+
+    if len > 0 {
+        cpu.cpu.memory.ram.mem[range].copy_from_slice(slice::from_raw_parts(data, len));
+    }
+
+    EmuStatus::Ok
+}
+
+/// Copies `len` bytes of RAM at `address` to `data`, bypassing mapped callbacks.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_read_memory(
+    cpu: *const EmuCpu,
+    address: u16,
+    data: *mut u8,
+    len: usize,
+) -> EmuStatus {
+    let Some(cpu) = cpu.as_ref() else {
+        return EmuStatus::NullPointer;
+    };
+
+    if data.is_null() && len > 0 {
+        return EmuStatus::NullPointer;
+    }
+
+    let Some(range) = range(address, len) else {
+        return EmuStatus::OutOfRange;
+    };
+
+    if len > 0 {
+        slice::from_raw_parts_mut(data, len).copy_from_slice(&cpu.cpu.memory.ram.mem[range]);
+    }
+
+    EmuStatus::Ok
+}
+
+/// Reads RAM without side effects, `0` for a `NULL` CPU.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_peek(cpu: *const EmuCpu, address: u16) -> u8 {
+    cpu.as_ref().map_or(0, |cpu| cpu.cpu.memory.peek(address))
+}
+
+/// Writes RAM without side effects.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_poke(cpu: *mut EmuCpu, address: u16, data: u8) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.input(InputEvent::Poke { address, data });
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_get_registers(
+    cpu: *const EmuCpu,
+    registers: *mut EmuRegisters,
+) -> EmuStatus {
+    let (Some(cpu), Some(registers)) = (cpu.as_ref(), registers.as_mut()) else {
+        return EmuStatus::NullPointer;
+    };
+
+    *registers = cpu.cpu.registers.state().into();
+    EmuStatus::Ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_set_registers(
+    cpu: *mut EmuCpu,
+    registers: *const EmuRegisters,
+) -> EmuStatus {
+    let (Some(cpu), Some(registers)) = (cpu.as_mut(), registers.as_ref()) else {
+        return EmuStatus::NullPointer;
+    };
+
+    cpu.cpu.registers.restore((*registers).into());
+    EmuStatus::Ok
+}
+
+/// Number of cycles elapsed since the last reset.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_cycles(cpu: *const EmuCpu) -> u64 {
+    cpu.as_ref().map_or(0, |cpu| cpu.cpu.cycles)
+}
+
+/// Number of instructions executed since the last reset.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_instructions(cpu: *const EmuCpu) -> u64 {
+    cpu.as_ref().map_or(0, |cpu| cpu.cpu.instructions)
+}
+
+/// Requests an interrupt, taken after the current instruction once interrupts are enabled.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_irq(cpu: *mut EmuCpu) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.input(InputEvent::Irq);
+    }
+}
+
+/// Requests a non-maskable interrupt, taken after the current instruction.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_nmi(cpu: *mut EmuCpu) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.input(InputEvent::Nmi);
+    }
+}
+
+/// Maps CPU accesses to `start..=end` to callbacks, called with `user_data`.
+///
+/// Either callback may be `NULL`, those accesses then go to RAM.
+/// Later mappings take precedence over earlier overlapping ones.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_map(
+    cpu: *mut EmuCpu,
+    start: u16,
+    end: u16,
+    read: EmuReadCallback,
+    write: EmuWriteCallback,
+    user_data: *mut c_void,
+) -> EmuStatus {
+    let Some(cpu) = cpu.as_mut() else {
+        return EmuStatus::NullPointer;
+    };
+
+    if start > end || (read.is_none() && write.is_none()) {
+        return EmuStatus::InvalidArgument;
+    }
+
+    cpu.cpu.memory.map(start, end, read, write, user_data);
+    EmuStatus::Ok
+}
+
+/// Removes the mappings of exactly `start..=end`.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_unmap(cpu: *mut EmuCpu, start: u16, end: u16) -> EmuStatus {
+    let Some(cpu) = cpu.as_mut() else {
+        return EmuStatus::NullPointer;
+    };
+
+    if cpu.cpu.memory.unmap(start, end) {
+        EmuStatus::Ok
+    } else {
+        EmuStatus::InvalidArgument
+    }
+}
+
+/// Executes one instruction.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_step(cpu: *mut EmuCpu) -> EmuStop {
+    emu_cpu_run(cpu, 1, 0)
+}
+
+/// Executes instructions until one stops execution, or a budget runs out.
+///
+/// `max_instructions` and `max_cycles` count from this call, `0` is no limit.
+/// Returns `EMU_STOP_NONE` only when `max_instructions` is 1 and nothing else stopped.
+#[no_mangle]
+pub unsafe extern "C" fn emu_cpu_run(
+    cpu: *mut EmuCpu,
+    max_instructions: u64,
+    max_cycles: u64,
+) -> EmuStop {
+    let Some(cpu) = cpu.as_mut() else {
+        return EmuStop::NullPointer;
+    };
+
+    let cpu = &mut cpu.cpu;
+    let start_instructions = cpu.instructions;
+    let start_cycles = cpu.cycles;
+
+    let run = panic::catch_unwind(AssertUnwindSafe(|| loop {
+        if max_cycles != 0 && cpu.cycles - start_cycles >= max_cycles {
+            return EmuStop::Budget;
+        }
+
+        let stop = EmuStop::from(cpu.step());
+        let executed = cpu.instructions - start_instructions;
+
+        if stop != EmuStop::None || max_instructions == 1 {
+            return stop;
+        }
+
+        if max_instructions != 0 && executed >= max_instructions {
+            return EmuStop::Budget;
+        }
+    }));
+
+    run.unwrap_or(EmuStop::Panic)
+}
@@ -0,0 +1,148 @@
+use emulator::{
+    memory::{Memory, MemoryBus},
+    NoneDebugger,
+};
+use std::{ffi::c_void, slice::SliceIndex};
+
+/// Called when the CPU reads a mapped address, returns the data on the bus.
+pub type EmuReadCallback = Option<unsafe extern "C" fn(user_data: *mut c_void, address: u16) -> u8>;
+
+/// Called when the CPU writes a mapped address.
+pub type EmuWriteCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, address: u16, data: u8)>;
+
+struct Mapping {
+    start: u16,
+    end: u16,
+    read: EmuReadCallback,
+    write: EmuWriteCallback,
+    user_data: *mut c_void,
+}
+
+impl Mapping {
+    fn contains(&self, address: u16) -> bool {
+        (self.start..=self.end).contains(&address)
+    }
+}
+
+/// # Callback Memory
+///
+/// RAM where address ranges can be mapped to callbacks of the host.
+///
+/// Accesses of the CPU go to the most recently mapped range with a callback for the access,
+/// or to RAM if there is none. `peek` and `poke` always access RAM, so inspecting memory
+/// has no side effects on the devices.
+#[derive(Default)]
+pub struct CallbackMemory {
+    pub ram: Memory<NoneDebugger>,
+    mappings: Vec<Mapping>,
+}
+
+impl CallbackMemory {
+    pub fn map(
+        &mut self,
+        start: u16,
+        end: u16,
+        read: EmuReadCallback,
+        write: EmuWriteCallback,
+        user_data: *mut c_void,
+    ) {
+        self.mappings.push(Mapping {
+            start,
+            end,
+            read,
+            write,
+            user_data,
+        });
+    }
+
+    /// Removes the mappings of exactly `start..=end`, returns whether there were any.
+    pub fn unmap(&mut self, start: u16, end: u16) -> bool {
+        let len = self.mappings.len();
+        self.mappings
+            .retain(|mapping| (mapping.start, mapping.end) != (start, end));
+
+        self.mappings.len() != len
+    }
+}
+
+impl MemoryBus for CallbackMemory {
+    type Data = u8;
+    type Addr = u16;
+
+    fn rom(&mut self, program: &[Self::Data]) {
+        self.ram.rom(program);
+    }
+
+    /// Clears RAM, the mappings are kept.
+    fn reset(&mut self) {
+        self.ram.reset();
+    }
+
+    fn write(&mut self, address: Self::Addr, data: Self::Data) {
+        let mapping = self
+            .mappings
+            .iter()
+            .rev()
+            .find(|mapping| mapping.write.is_some() && mapping.contains(address));
+
+        match mapping {
+            Some(Mapping {
+                write: Some(write),
+                user_data,
+                ..
+            }) => unsafe { write(*user_data, address, data) },
+            _ => self.ram.write(address, data),
+        }
+    }
+
+    fn read(&mut self, address: Self::Addr) -> Self::Data {
+        let mapping = self
+            .mappings
+            .iter()
+            .rev()
+            .find(|mapping| mapping.read.is_some() && mapping.contains(address));
+
+        match mapping {
+            Some(Mapping {
+                read: Some(read),
+                user_data,
+                ..
+            }) => unsafe { read(*user_data, address) },
+            _ => self.ram.read(address),
+        }
+    }
+
+    fn write_addr(&mut self, address: Self::Addr, data: Self::Addr) {
+        let [lsb, msb] = data.to_le_bytes();
+
+        self.write(address, lsb);
+        self.write(address.wrapping_add(1), msb);
+    }
+
+    fn read_addr(&mut self, address: Self::Addr) -> Self::Addr {
+        let lsb = self.read(address);
+        let msb = self.read(address.wrapping_add(1));
+
+        u16::from_le_bytes([lsb, msb])
+    }
+
+    fn peek(&self, address: Self::Addr) -> Self::Data {
+        self.ram.peek(address)
+    }
+
+    fn poke(&mut self, address: Self::Addr, data: Self::Data) {
+        self.ram.poke(address, data);
+    }
+
+    fn slice(
+        &mut self,
+        range: impl SliceIndex<[Self::Data], Output = [Self::Data]>,
+    ) -> &[Self::Data] {
+        self.ram.slice(range)
+    }
+
+    fn restore(&mut self, data: &[Self::Data]) {
+        self.ram.restore(data);
+    }
+}
@@ -0,0 +1,9 @@
+#!/bin/sh
+# Builds the library and runs the C API tests against the static library.
+set -e
+cd "$(dirname "$0")"
+
+cargo build
+mkdir -p target
+cc -std=c99 -Wall -Wextra -Werror -Iinclude tests/test.c target/debug/libemulator6502.a -lpthread -ldl -lm -o target/capi_test
+./target/capi_test
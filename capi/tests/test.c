@@ -0,0 +1,153 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "emulator6502.h"
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #condition);                                           \
+            return 1;                                                      \
+        }                                                                  \
+    } while (0)
+
+/* Serial port at $6000: writes are collected, reads return the next input byte. */
+typedef struct {
+    char output[64];
+    size_t output_len;
+    const char *input;
+} Serial;
+
+static uint8_t serial_read(void *user_data, uint16_t address) {
+    Serial *serial = user_data;
+    (void)address;
+
+    return *serial->input ? (uint8_t)*serial->input++ : 0;
+}
+
+static void serial_write(void *user_data, uint16_t address, uint8_t data) {
+    Serial *serial = user_data;
+    (void)address;
+
+    if (serial->output_len < sizeof(serial->output) - 1) {
+        serial->output[serial->output_len++] = (char)data;
+    }
+}
+
+/* Echoes the serial input in upper case, until a NUL byte. */
+static const char *ECHO =
+    "LOOP:\n"
+    "    LDA $6000\n"
+    "    BEQ END\n"
+    "    AND #$DF\n"
+    "    STA $6000\n"
+    "    JMP LOOP\n"
+    "END:\n"
+    "    BRK\n";
+
+static int test_assemble_and_run(void) {
+    EmuAssembly *assembly = emu_assemble(ECHO);
+    CHECK(emu_assembly_is_ok(assembly));
+
+    size_t len = 0;
+    const uint8_t *bytes = emu_assembly_bytes(assembly, &len);
+    CHECK(bytes != NULL && len == 14);
+
+    EmuCpu *cpu = emu_cpu_new();
+    CHECK(emu_cpu_load(cpu, 0x8000, bytes, len) == EMU_STATUS_OK);
+    emu_assembly_free(assembly);
+
+    Serial serial = {.input = "hello"};
+    CHECK(emu_cpu_map(cpu, 0x6000, 0x6000, serial_read, serial_write, &serial) == EMU_STATUS_OK);
+
+    CHECK(emu_cpu_run(cpu, 0, 0) == EMU_STOP_BRK);
+    CHECK(strcmp(serial.output, "HELLO") == 0);
+    CHECK(emu_cpu_instructions(cpu) == 5 * 5 + 3);
+
+    /* Mapped writes do not reach RAM */
+    CHECK(emu_cpu_peek(cpu, 0x6000) == 0);
+
+    EmuRegisters registers;
+    CHECK(emu_cpu_get_registers(cpu, &registers) == EMU_STATUS_OK);
+    CHECK(registers.a == 0 && registers.pc == 0x800E);
+
+    emu_cpu_free(cpu);
+    return 0;
+}
+
+static int test_budgets(void) {
+    /* $8000 INX, $8001 JMP $8000 */
+    const uint8_t loop[] = {0xE8, 0x4C, 0x00, 0x80};
+
+    EmuCpu *cpu = emu_cpu_new();
+    CHECK(emu_cpu_load(cpu, 0x8000, loop, sizeof(loop)) == EMU_STATUS_OK);
+
+    CHECK(emu_cpu_step(cpu) == EMU_STOP_NONE);
+    CHECK(emu_cpu_run(cpu, 9, 0) == EMU_STOP_BUDGET);
+    CHECK(emu_cpu_instructions(cpu) == 10);
+
+    uint64_t cycles = emu_cpu_cycles(cpu);
+    CHECK(emu_cpu_run(cpu, 0, 100) == EMU_STOP_BUDGET);
+    CHECK(emu_cpu_cycles(cpu) - cycles >= 100 && emu_cpu_cycles(cpu) - cycles < 105);
+
+    EmuRegisters registers = {.x = 0x41, .sp = 0xFF, .pc = 0x8000};
+    CHECK(emu_cpu_set_registers(cpu, &registers) == EMU_STATUS_OK);
+    CHECK(emu_cpu_step(cpu) == EMU_STOP_NONE);
+    CHECK(emu_cpu_get_registers(cpu, &registers) == EMU_STATUS_OK);
+    CHECK(registers.x == 0x42 && registers.pc == 0x8001);
+
+    emu_cpu_free(cpu);
+    return 0;
+}
+
+static int test_memory(void) {
+    const uint8_t data[] = {1, 2, 3};
+    uint8_t out[3] = {0};
+
+    EmuCpu *cpu = emu_cpu_new();
+    CHECK(emu_cpu_load(cpu, 0xFFFD, data, sizeof(data)) == EMU_STATUS_OK);
+    CHECK(emu_cpu_load(cpu, 0xFFFE, data, sizeof(data)) == EMU_STATUS_OUT_OF_RANGE);
+    CHECK(emu_cpu_read_memory(cpu, 0xFFFD, out, sizeof(out)) == EMU_STATUS_OK);
+    CHECK(memcmp(data, out, sizeof(out)) == 0);
+
+    emu_cpu_poke(cpu, 0x0200, 0xAB);
+    CHECK(emu_cpu_peek(cpu, 0x0200) == 0xAB);
+
+    CHECK(emu_cpu_map(cpu, 0x10, 0x0F, serial_read, NULL, NULL) == EMU_STATUS_INVALID_ARGUMENT);
+    CHECK(emu_cpu_map(cpu, 0x10, 0x1F, NULL, NULL, NULL) == EMU_STATUS_INVALID_ARGUMENT);
+    CHECK(emu_cpu_unmap(cpu, 0x10, 0x1F) == EMU_STATUS_INVALID_ARGUMENT);
+
+    CHECK(emu_cpu_load(NULL, 0, data, sizeof(data)) == EMU_STATUS_NULL_POINTER);
+    CHECK(emu_cpu_run(NULL, 0, 0) == EMU_STOP_NULL_POINTER);
+
+    emu_cpu_free(cpu);
+    return 0;
+}
+
+static int test_assembler_error(void) {
+    EmuAssembly *assembly = emu_assemble("LDA #$01\nFOO $10\n");
+    CHECK(!emu_assembly_is_ok(assembly));
+    CHECK(emu_assembly_bytes(assembly, NULL) == NULL);
+
+    EmuAssemblerError error;
+    CHECK(emu_assembly_error(assembly, &error));
+    CHECK(error.kind == EMU_ASSEMBLER_ERROR_KIND_INVALID_MNEMONIC);
+    CHECK(error.line == 2);
+    CHECK(strstr(error.message, "FOO") != NULL);
+
+    emu_assembly_free(assembly);
+    CHECK(emu_assemble(NULL) == NULL);
+    return 0;
+}
+
+int main(void) {
+    int failed = test_assemble_and_run() || test_budgets() || test_memory() ||
+                 test_assembler_error();
+
+    if (!failed) {
+        printf("C API tests passed\n");
+    }
+
+    return failed;
+}
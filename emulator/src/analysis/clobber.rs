@@ -0,0 +1,271 @@
+use crate::{
+    provenance::Location,
+    registers::{Register, Registers},
+    Debugger,
+};
+use std::{collections::VecDeque, fmt};
+
+/// A location changed by an interrupt handler.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClobberedValue {
+    pub location: Location,
+    /// Value on interrupt entry.
+    pub entry: u8,
+    /// Value at the `RTI`.
+    pub exit: u8,
+}
+
+/// An interrupt handler that returned with changed registers or zero page locations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clobber {
+    /// Entry address of the handler.
+    pub handler: u16,
+    /// Address of the `RTI`.
+    pub pc: u16,
+    /// Address of the interrupted code.
+    pub return_address: u16,
+    pub values: Vec<ClobberedValue>,
+}
+
+impl fmt::Display for Clobber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|value| {
+                format!(
+                    "{} 0x{:02X} -> 0x{:02X}",
+                    value.location, value.entry, value.exit
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(
+            f,
+            "Interrupt handler 0x{:04X} returned at 0x{:04X} to 0x{:04X} with {values}",
+            self.handler, self.pc, self.return_address
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    handler: u16,
+    return_address: u16,
+    values: Vec<(Location, u8)>,
+}
+
+/// # Clobber Checker
+///
+/// Snapshots A, X, Y and the chosen zero page locations on interrupt entry,
+/// and reports handlers whose `RTI` leaves any of them changed.
+///
+/// Only the last `limit` clobbers are kept.
+#[derive(Clone, Debug)]
+pub struct ClobberChecker {
+    /// Zero page locations the handlers must preserve, besides A, X and Y.
+    pub zero_page: Vec<u8>,
+    limit: usize,
+    snapshots: Vec<Snapshot>,
+    clobbers: VecDeque<Clobber>,
+}
+
+impl Default for ClobberChecker {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl ClobberChecker {
+    pub const DEFAULT_LIMIT: usize = 1_000;
+
+    pub fn new(limit: usize) -> ClobberChecker {
+        ClobberChecker {
+            zero_page: Vec::new(),
+            limit: limit.max(1),
+            snapshots: Vec::new(),
+            clobbers: VecDeque::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.clobbers.clear();
+    }
+
+    fn values<R: Debugger>(
+        &self,
+        registers: &Registers<R>,
+        peek: impl Fn(u16) -> u8,
+    ) -> Vec<(Location, u8)> {
+        let mut values = vec![
+            (Location::Register(Register::A), registers.a),
+            (Location::Register(Register::X), registers.x),
+            (Location::Register(Register::Y), registers.y),
+        ];
+
+        values.extend(
+            self.zero_page
+                .iter()
+                .map(|address| (Location::Memory(*address as u16), peek(*address as u16))),
+        );
+
+        values
+    }
+
+    /// Records the entry into an interrupt handler. `peek` reads memory without side effects.
+    pub fn enter<R: Debugger>(
+        &mut self,
+        handler: u16,
+        return_address: u16,
+        registers: &Registers<R>,
+        peek: impl Fn(u16) -> u8,
+    ) {
+        let values = self.values(registers, peek);
+
+        self.snapshots.push(Snapshot {
+            handler,
+            return_address,
+            values,
+        });
+    }
+
+    /// Checks an `RTI` at `pc` against the innermost interrupt entry.
+    ///
+    /// `RTI`s outside an interrupt are ignored.
+    pub fn exit<R: Debugger>(
+        &mut self,
+        pc: u16,
+        registers: &Registers<R>,
+        peek: impl Fn(u16) -> u8,
+    ) -> Option<Clobber> {
+        let snapshot = self.snapshots.pop()?;
+        let exit = self.values(registers, peek);
+
+        let values = snapshot
+            .values
+            .iter()
+            .filter_map(|(location, entry)| {
+                let (_, exit) = exit.iter().find(|(l, _)| l == location)?;
+
+                (entry != exit).then_some(ClobberedValue {
+                    location: *location,
+                    entry: *entry,
+                    exit: *exit,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            return None;
+        }
+
+        let clobber = Clobber {
+            handler: snapshot.handler,
+            pc,
+            return_address: snapshot.return_address,
+            values,
+        };
+
+        if self.clobbers.len() >= self.limit {
+            self.clobbers.pop_front();
+        }
+
+        self.clobbers.push_back(clobber.clone());
+
+        Some(clobber)
+    }
+
+    /// Recorded clobbers, oldest first.
+    pub fn clobbers(&self) -> impl DoubleEndedIterator<Item = &Clobber> {
+        self.clobbers.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::{Memory, IRQ_VECTOR},
+        Cpu6502, CpuDebugger, NoneDebugger,
+    };
+
+    fn setup(handler: &[u8]) -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA9, 0x01, // $8000 LDA #$01
+            0xA2, 0x02, // $8002 LDX #$02
+            0xEA, // $8004 NOP
+            0x00, // $8005 BRK
+        ]);
+        cpu.memory.mem[0x9000..0x9000 + handler.len()].copy_from_slice(handler);
+        cpu.memory.mem[IRQ_VECTOR as usize] = 0x00;
+        cpu.memory.mem[IRQ_VECTOR as usize + 1] = 0x90;
+        cpu.registers.sp = 0xFF;
+
+        let mut checker = ClobberChecker::default();
+        checker.zero_page.push(0x10);
+        cpu.clobber_checker = Some(checker);
+        cpu
+    }
+
+    fn run(cpu: &mut Cpu6502<NoneDebugger>) {
+        cpu.step();
+        cpu.irq();
+        cpu.run();
+    }
+
+    #[test]
+    fn test_clobber() {
+        let mut cpu = setup(&[
+            0xA9, 0x05, // $9000 LDA #$05
+            0xE6, 0x10, // $9002 INC $10
+            0x40, // $9004 RTI
+        ]);
+        run(&mut cpu);
+
+        let clobbers = cpu
+            .clobber_checker
+            .as_ref()
+            .unwrap()
+            .clobbers()
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            *clobbers[0],
+            Clobber {
+                handler: 0x9000,
+                pc: 0x9004,
+                return_address: 0x8004,
+                values: vec![
+                    ClobberedValue {
+                        location: Location::Register(Register::A),
+                        entry: 0x01,
+                        exit: 0x05
+                    },
+                    ClobberedValue {
+                        location: Location::Memory(0x0010),
+                        entry: 0x00,
+                        exit: 0x01
+                    }
+                ]
+            }
+        );
+        assert_eq!(cpu.registers.x, 0x02);
+    }
+
+    #[test]
+    fn test_preserved() {
+        let mut cpu = setup(&[
+            0x48, // $9000 PHA
+            0xA9, 0x05, // $9001 LDA #$05
+            0x68, // $9003 PLA
+            0x40, // $9004 RTI
+        ]);
+        run(&mut cpu);
+
+        assert_eq!(cpu.clobber_checker.as_ref().unwrap().clobbers().count(), 0);
+    }
+}
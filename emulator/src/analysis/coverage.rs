@@ -0,0 +1,324 @@
+use crate::{cpu::Cpu, memory::MemoryBus, Debugger};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+/// Outcomes of a conditional branch.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BranchCoverage {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+impl BranchCoverage {
+    /// Whether the branch went both ways.
+    pub fn is_covered(&self) -> bool {
+        self.taken > 0 && self.not_taken > 0
+    }
+}
+
+/// # Coverage
+///
+/// Counts executed instructions and the outcomes of conditional branches, by address.
+///
+/// Reports are mapped to source lines with [`Symbols::lines`](crate::symbols::Symbols::lines),
+/// see [`Cpu::coverage_lcov`] and [`Cpu::coverage_report`].
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    executed: HashMap<u16, u64>,
+    branches: HashMap<u16, BranchCoverage>,
+}
+
+impl Coverage {
+    pub fn clear(&mut self) {
+        self.executed.clear();
+        self.branches.clear();
+    }
+
+    /// Records an executed instruction, and whether it branched if it is a conditional branch.
+    pub fn record(&mut self, pc: u16, taken: Option<bool>) {
+        *self.executed.entry(pc).or_default() += 1;
+
+        if let Some(taken) = taken {
+            let branch = self.branches.entry(pc).or_default();
+
+            if taken {
+                branch.taken += 1;
+            } else {
+                branch.not_taken += 1;
+            }
+        }
+    }
+
+    /// Number of times the instruction at `pc` was executed.
+    pub fn count(&self, pc: u16) -> u64 {
+        self.executed.get(&pc).copied().unwrap_or(0)
+    }
+
+    pub fn branch(&self, pc: u16) -> Option<&BranchCoverage> {
+        self.branches.get(&pc)
+    }
+
+    /// Every executed instruction address and its count, sorted by address.
+    pub fn executed(&self) -> Vec<(u16, u64)> {
+        let mut executed = self
+            .executed
+            .iter()
+            .map(|(pc, count)| (*pc, *count))
+            .collect::<Vec<_>>();
+        executed.sort();
+        executed
+    }
+
+    /// Every executed branch, sorted by address.
+    pub fn branches(&self) -> Vec<(u16, &BranchCoverage)> {
+        let mut branches = self
+            .branches
+            .iter()
+            .map(|(pc, branch)| (*pc, branch))
+            .collect::<Vec<_>>();
+        branches.sort_by_key(|(pc, _)| *pc);
+        branches
+    }
+}
+
+/// Coverage of a source line.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct LineCoverage {
+    /// Executions of the line's most executed instruction.
+    count: u64,
+    /// The line's conditional branches, `None` if never reached.
+    branches: Vec<Option<BranchCoverage>>,
+}
+
+/// Whether `opcode` is a conditional branch (`BPL`, `BMI`, `BVC`, `BVS`, `BCC`, `BCS`, `BNE`, `BEQ`).
+///
+/// Branch opcodes are `xxy10000`, `xx` selects the flag and `y` the value the branch is taken on.
+pub(crate) fn is_branch(opcode: u8) -> bool {
+    opcode & 0x1F == 0x10
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    fn line_coverage(&self, coverage: &Coverage) -> BTreeMap<usize, LineCoverage> {
+        let mut lines = BTreeMap::<usize, LineCoverage>::new();
+
+        for (address, line) in self.symbols.lines() {
+            let entry = lines.entry(line).or_default();
+            entry.count = entry.count.max(coverage.count(address));
+
+            if is_branch(self.memory.peek(address)) {
+                entry.branches.push(coverage.branch(address).copied());
+            }
+        }
+
+        lines
+    }
+
+    /// Coverage of the loaded program's source lines in LCOV tracefile format, `None` if coverage is not tracked.
+    ///
+    /// `path` is the source file name written in the `SF:` record.
+    pub fn coverage_lcov(&self, path: &str) -> Option<String> {
+        let lines = self.line_coverage(self.coverage.as_ref()?);
+        let mut lcov = format!("TN:\nSF:{path}\n");
+        let (mut branches_found, mut branches_hit) = (0, 0);
+
+        for (line, coverage) in &lines {
+            for (block, branch) in coverage.branches.iter().enumerate() {
+                let counts = match branch {
+                    Some(branch) => [branch.taken.to_string(), branch.not_taken.to_string()],
+                    None => ["-".to_string(), "-".to_string()],
+                };
+
+                for (i, count) in counts.iter().enumerate() {
+                    let _ = writeln!(lcov, "BRDA:{line},{block},{i},{count}");
+                }
+
+                branches_found += 2;
+
+                if let Some(branch) = branch {
+                    branches_hit += (branch.taken > 0) as usize + (branch.not_taken > 0) as usize;
+                }
+            }
+        }
+
+        let _ = writeln!(lcov, "BRF:{branches_found}\nBRH:{branches_hit}");
+
+        for (line, coverage) in &lines {
+            let _ = writeln!(lcov, "DA:{line},{}", coverage.count);
+        }
+
+        let hit = lines.values().filter(|line| line.count > 0).count();
+        let _ = writeln!(lcov, "LF:{}\nLH:{hit}\nend_of_record", lines.len());
+
+        Some(lcov)
+    }
+
+    /// `source` annotated with the execution count of each line, `None` if coverage is not tracked.
+    ///
+    /// Lines without instructions are marked `-`, lines never executed `#####`.
+    /// Branches that did not go both ways are listed below their line.
+    pub fn coverage_report(&self, source: &str) -> Option<String> {
+        let lines = self.line_coverage(self.coverage.as_ref()?);
+        let hit = lines.values().filter(|line| line.count > 0).count();
+        let branches = lines
+            .values()
+            .flat_map(|line| &line.branches)
+            .collect::<Vec<_>>();
+        let covered = branches
+            .iter()
+            .filter(|branch| branch.is_some_and(|branch| branch.is_covered()))
+            .count();
+        let percent = |hit: usize, found: usize| hit as f64 * 100.0 / found.max(1) as f64;
+
+        let mut report = format!(
+            "Lines: {hit}/{} ({:.2}%), branches: {covered}/{} ({:.2}%)\n\n",
+            lines.len(),
+            percent(hit, lines.len()),
+            branches.len(),
+            percent(covered, branches.len())
+        );
+
+        for (i, text) in source.lines().enumerate() {
+            let line = i + 1;
+
+            let coverage = lines.get(&line);
+
+            let count = match coverage {
+                Some(coverage) if coverage.count > 0 => coverage.count.to_string(),
+                Some(_) => "#####".to_string(),
+                None => "-".to_string(),
+            };
+
+            let _ = writeln!(report, "{count:>9}:{line:>5}: {text}");
+
+            for branch in coverage.iter().flat_map(|coverage| &coverage.branches) {
+                match branch {
+                    Some(branch) if branch.is_covered() => {}
+                    Some(branch) => {
+                        let _ = writeln!(
+                            report,
+                            "{:>16} branch taken {}, not taken {}",
+                            "", branch.taken, branch.not_taken
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(report, "{:>16} branch never executed", "");
+                    }
+                }
+            }
+        }
+
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, symbols::Symbols, Cpu6502, NoneDebugger};
+
+    const SOURCE: &str = "LDX #$02\nLOOP:\nDEX\nBNE LOOP\nBEQ END\nNOP\nEND:\nBRK";
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x02, // $8000 LDX #$02
+            0xCA, // $8002 LOOP: DEX
+            0xD0, 0xFD, // $8003 BNE LOOP
+            0xF0, 0x01, // $8005 BEQ END
+            0xEA, // $8007 NOP
+            0x00, // $8008 END: BRK
+        ]);
+
+        let mut symbols = Symbols::new([("LOOP", 0x8002), ("END", 0x8008)]);
+
+        for (address, line) in [
+            (0x8000, 1),
+            (0x8002, 3),
+            (0x8003, 4),
+            (0x8005, 5),
+            (0x8007, 6),
+            (0x8008, 8),
+        ] {
+            symbols.insert_line(address, line);
+        }
+
+        cpu.symbols = symbols;
+        cpu.coverage = Some(Coverage::default());
+        cpu.execute();
+        cpu
+    }
+
+    #[test]
+    fn test_coverage() {
+        let cpu = setup();
+        let coverage = cpu.coverage.as_ref().unwrap();
+
+        assert_eq!(coverage.count(0x8002), 2);
+        assert_eq!(coverage.count(0x8007), 0);
+        assert_eq!(
+            coverage.branch(0x8003),
+            Some(&BranchCoverage {
+                taken: 1,
+                not_taken: 1
+            })
+        );
+        assert_eq!(
+            coverage.branch(0x8005),
+            Some(&BranchCoverage {
+                taken: 1,
+                not_taken: 0
+            })
+        );
+        assert_eq!(coverage.executed().len(), 5);
+    }
+
+    #[test]
+    fn test_lcov() {
+        let cpu = setup();
+
+        assert_eq!(
+            cpu.coverage_lcov("test.asm").unwrap(),
+            [
+                "TN:",
+                "SF:test.asm",
+                "BRDA:4,0,0,1",
+                "BRDA:4,0,1,1",
+                "BRDA:5,0,0,1",
+                "BRDA:5,0,1,0",
+                "BRF:4",
+                "BRH:3",
+                "DA:1,1",
+                "DA:3,2",
+                "DA:4,2",
+                "DA:5,1",
+                "DA:6,0",
+                "DA:8,1",
+                "LF:6",
+                "LH:5",
+                "end_of_record\n",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let cpu = setup();
+        let report = cpu.coverage_report(SOURCE).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "Lines: 5/6 (83.33%), branches: 1/2 (50.00%)");
+        assert_eq!(lines[3], "        -:    2: LOOP:");
+        assert_eq!(lines[6], "        1:    5: BEQ END");
+        assert_eq!(lines[7], "                 branch taken 1, not taken 0");
+        assert_eq!(lines[8], "    #####:    6: NOP");
+    }
+}
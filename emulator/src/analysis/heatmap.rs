@@ -0,0 +1,149 @@
+use crate::memory::{Access, MEMORY_SIZE};
+
+/// Accesses to a single address.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AccessCounts {
+    pub reads: u32,
+    pub writes: u32,
+    pub executes: u32,
+}
+
+impl AccessCounts {
+    pub fn get(&self, access: Access) -> u32 {
+        match access {
+            Access::Read => self.reads,
+            Access::Write => self.writes,
+            Access::Execute => self.executes,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.reads
+            .saturating_add(self.writes)
+            .saturating_add(self.executes)
+    }
+}
+
+/// # Heatmap
+///
+/// Counts reads, writes and executes of every address.
+///
+/// Executes are instruction fetches: the opcode and the operand bytes read by the addressing mode.
+/// Reads and writes are the accesses made by the instructions themselves, including the stack and vectors.
+#[derive(Clone, Debug)]
+pub struct Heatmap {
+    counts: Vec<AccessCounts>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self {
+            counts: vec![AccessCounts::default(); MEMORY_SIZE],
+        }
+    }
+}
+
+impl Heatmap {
+    pub fn clear(&mut self) {
+        self.counts.fill(AccessCounts::default());
+    }
+
+    pub fn record(&mut self, address: u16, access: Access) {
+        let counts = &mut self.counts[address as usize];
+
+        let count = match access {
+            Access::Read => &mut counts.reads,
+            Access::Write => &mut counts.writes,
+            Access::Execute => &mut counts.executes,
+        };
+
+        *count = count.saturating_add(1);
+    }
+
+    /// Scales every count by `factor` (e.g. `0.5` halves them), so old accesses fade out.
+    pub fn decay(&mut self, factor: f32) {
+        let factor = factor.clamp(0.0, 1.0);
+        let scale = |count: &mut u32| *count = (*count as f32 * factor) as u32;
+
+        for counts in &mut self.counts {
+            scale(&mut counts.reads);
+            scale(&mut counts.writes);
+            scale(&mut counts.executes);
+        }
+    }
+
+    pub fn get(&self, address: u16) -> AccessCounts {
+        self.counts[address as usize]
+    }
+
+    /// The highest count of each kind of access, over all addresses.
+    pub fn max(&self) -> AccessCounts {
+        self.counts
+            .iter()
+            .fold(AccessCounts::default(), |max, counts| AccessCounts {
+                reads: max.reads.max(counts.reads),
+                writes: max.writes.max(counts.writes),
+                executes: max.executes.max(counts.executes),
+            })
+    }
+
+    /// Total accesses of each 256-byte page, by page number.
+    pub fn pages(&self) -> Vec<AccessCounts> {
+        self.counts
+            .chunks(0x100)
+            .map(|page| {
+                page.iter()
+                    .fold(AccessCounts::default(), |sum, counts| AccessCounts {
+                        reads: sum.reads.saturating_add(counts.reads),
+                        writes: sum.writes.saturating_add(counts.writes),
+                        executes: sum.executes.saturating_add(counts.executes),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, NoneDebugger};
+
+    #[test]
+    fn test_heatmap() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA9, 0x05, // LDA #$05
+            0x8D, 0x00, 0x02, // STA $0200
+            0xEE, 0x00, 0x02, // INC $0200
+            0x00, // BRK
+        ]);
+        cpu.heatmap = Some(Heatmap::default());
+        cpu.execute();
+
+        let heatmap = cpu.heatmap.as_mut().unwrap();
+
+        assert_eq!(
+            heatmap.get(0x0200),
+            AccessCounts {
+                reads: 1,
+                writes: 2,
+                executes: 0
+            }
+        );
+        assert_eq!(heatmap.get(0x8000).executes, 1);
+        assert_eq!(heatmap.get(0x8003).executes, 1);
+        // Operand bytes are fetched with the instruction
+        assert_eq!(heatmap.get(0x8001).executes, 1);
+        assert_eq!(heatmap.get(0x8001).reads, 0);
+        assert_eq!(heatmap.pages()[0x02].writes, 2);
+        assert_eq!(heatmap.max().writes, 2);
+
+        heatmap.decay(0.5);
+        assert_eq!(heatmap.get(0x0200).writes, 1);
+        assert_eq!(heatmap.get(0x0200).reads, 0);
+
+        heatmap.clear();
+        assert_eq!(heatmap.max(), AccessCounts::default());
+    }
+}
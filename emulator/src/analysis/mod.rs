@@ -0,0 +1,25 @@
+#[cfg(test)]
+mod buffer;
+pub mod clobber;
+pub mod coverage;
+pub mod heatmap;
+pub mod profiler;
+pub mod provenance;
+pub mod sanitizer;
+pub mod self_modifying;
+pub mod stack_sanitizer;
+pub mod trace;
+pub mod trace_diff;
+pub mod vcd;
+
+pub use clobber::*;
+pub use coverage::*;
+pub use heatmap::*;
+pub use profiler::*;
+pub use provenance::*;
+pub use sanitizer::*;
+pub use self_modifying::*;
+pub use stack_sanitizer::*;
+pub use trace::*;
+pub use trace_diff::*;
+pub use vcd::*;
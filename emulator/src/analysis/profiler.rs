@@ -0,0 +1,315 @@
+use crate::symbols::Symbols;
+use std::{collections::HashMap, fmt::Write};
+
+/// Instructions and cycles spent at a single address.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PcProfile {
+    pub instructions: u64,
+    pub cycles: u64,
+}
+
+/// Instructions and cycles spent in a subroutine.
+///
+/// * Exclusive totals only count the routine's own instructions.
+/// * Inclusive totals also count the routines it called. Recursive calls are counted once.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoutineProfile {
+    /// Entry address of the routine.
+    pub address: u16,
+    pub calls: u64,
+    pub instructions: u64,
+    pub cycles: u64,
+    pub inclusive_instructions: u64,
+    pub inclusive_cycles: u64,
+}
+
+/// # Profiler
+///
+/// Attributes instruction counts and cycles to addresses and to subroutines.
+///
+/// Routines are delimited by `JSR`/`RTS` and interrupt entry/`RTI`.
+/// The first executed address is the root routine, it is never returned from.
+/// Stack manipulation that skips an `RTS` is not detected, see [`CallStack`](crate::call_stack::CallStack) for that.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    pcs: HashMap<u16, PcProfile>,
+    routines: HashMap<u16, RoutineProfile>,
+    /// Cycles by routine stack, outermost first.
+    stacks: HashMap<Vec<u16>, u64>,
+    stack: Vec<u16>,
+    instructions: u64,
+    cycles: u64,
+}
+
+impl Profiler {
+    pub fn clear(&mut self) {
+        *self = Profiler::default();
+    }
+
+    /// Records an executed instruction.
+    ///
+    /// `next_pc` is the program counter after the instruction, which is the called routine for `JSR`.
+    pub fn record(&mut self, pc: u16, opcode: u8, cycles: u64, next_pc: u16) {
+        if self.stack.is_empty() {
+            self.enter(pc);
+        }
+
+        let profile = self.pcs.entry(pc).or_default();
+        profile.instructions += 1;
+        profile.cycles += cycles;
+
+        self.attribute(1, cycles);
+
+        match opcode {
+            // JSR
+            0x20 => self.enter(next_pc),
+            // RTS, RTI
+            0x60 | 0x40 if self.stack.len() > 1 => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Records the entry into an interrupt handler, and the cycles it took.
+    pub fn interrupt(&mut self, handler: u16, cycles: u64) {
+        self.enter(handler);
+        self.attribute(0, cycles);
+    }
+
+    fn enter(&mut self, routine: u16) {
+        self.stack.push(routine);
+        self.routine(routine).calls += 1;
+    }
+
+    fn routine(&mut self, address: u16) -> &mut RoutineProfile {
+        self.routines
+            .entry(address)
+            .or_insert_with(|| RoutineProfile {
+                address,
+                ..Default::default()
+            })
+    }
+
+    fn attribute(&mut self, instructions: u64, cycles: u64) {
+        self.instructions += instructions;
+        self.cycles += cycles;
+
+        let stack = std::mem::take(&mut self.stack);
+
+        for (i, address) in stack.iter().enumerate() {
+            if stack[..i].contains(address) {
+                continue;
+            }
+
+            let routine = self.routine(*address);
+            routine.inclusive_instructions += instructions;
+            routine.inclusive_cycles += cycles;
+        }
+
+        if let Some(address) = stack.last() {
+            let routine = self.routine(*address);
+            routine.instructions += instructions;
+            routine.cycles += cycles;
+        }
+
+        match self.stacks.get_mut(stack.as_slice()) {
+            Some(total) => *total += cycles,
+            None => {
+                self.stacks.insert(stack.clone(), cycles);
+            }
+        }
+
+        self.stack = stack;
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn pc(&self, pc: u16) -> Option<&PcProfile> {
+        self.pcs.get(&pc)
+    }
+
+    pub fn routine_profile(&self, address: u16) -> Option<&RoutineProfile> {
+        self.routines.get(&address)
+    }
+
+    /// Every routine, by inclusive cycles, highest first.
+    pub fn routines(&self) -> Vec<&RoutineProfile> {
+        let mut routines = self.routines.values().collect::<Vec<_>>();
+        routines
+            .sort_by_key(|routine| (std::cmp::Reverse(routine.inclusive_cycles), routine.address));
+        routines
+    }
+
+    /// Every executed address, by cycles, highest first.
+    pub fn pcs(&self) -> Vec<(u16, &PcProfile)> {
+        let mut pcs = self
+            .pcs
+            .iter()
+            .map(|(pc, profile)| (*pc, profile))
+            .collect::<Vec<_>>();
+        pcs.sort_by_key(|(pc, profile)| (std::cmp::Reverse(profile.cycles), *pc));
+        pcs
+    }
+
+    /// Cycles in the folded stack format read by flame graph tools (`main;outer;inner 42`), one stack per line.
+    pub fn folded(&self, symbols: &Symbols) -> String {
+        let mut lines = self
+            .stacks
+            .iter()
+            .filter(|(_, cycles)| **cycles > 0)
+            .map(|(stack, cycles)| {
+                let names = stack
+                    .iter()
+                    .map(|address| routine_name(symbols, *address))
+                    .collect::<Vec<_>>()
+                    .join(";");
+
+                format!("{names} {cycles}")
+            })
+            .collect::<Vec<_>>();
+
+        lines.sort();
+        lines.join("\n")
+    }
+
+    /// A text report of the routines and the `limit` hottest addresses, sorted by cycles.
+    pub fn report(&self, symbols: &Symbols, limit: usize) -> String {
+        let mut report = String::new();
+        let percent = |cycles: u64| cycles as f64 * 100.0 / self.cycles.max(1) as f64;
+
+        let _ = writeln!(
+            report,
+            "{} instructions, {} cycles\n",
+            self.instructions, self.cycles
+        );
+        let _ = writeln!(
+            report,
+            "{:<20} {:>8} {:>12} {:>7} {:>12} {:>7} {:>12}",
+            "Routine", "Calls", "Incl. cycles", "%", "Excl. cycles", "%", "Instructions"
+        );
+
+        for routine in self.routines() {
+            let _ = writeln!(
+                report,
+                "{:<20} {:>8} {:>12} {:>6.2}% {:>12} {:>6.2}% {:>12}",
+                routine_name(symbols, routine.address),
+                routine.calls,
+                routine.inclusive_cycles,
+                percent(routine.inclusive_cycles),
+                routine.cycles,
+                percent(routine.cycles),
+                routine.instructions
+            );
+        }
+
+        let _ = writeln!(
+            report,
+            "\n{:<20} {:>8} {:>12} {:>7}",
+            "Address", "Count", "Cycles", "%"
+        );
+
+        for (pc, profile) in self.pcs().into_iter().take(limit) {
+            let name = if symbols.is_empty() {
+                format!("${pc:04X}")
+            } else {
+                format!("${pc:04X} {}", symbols.describe(pc))
+            };
+
+            let _ = writeln!(
+                report,
+                "{:<20} {:>8} {:>12} {:>6.2}%",
+                name,
+                profile.instructions,
+                profile.cycles,
+                percent(profile.cycles)
+            );
+        }
+
+        report
+    }
+}
+
+fn routine_name(symbols: &Symbols, address: u16) -> String {
+    match symbols.label(address) {
+        Some(label) => label.to_string(),
+        None => format!("${address:04X}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, NoneDebugger};
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0x20, 0x07, 0x80, // $8000 main: JSR outer
+            0x20, 0x0B, 0x80, // JSR inner
+            0x00, // BRK
+            0x20, 0x0B, 0x80, // $8007 outer: JSR inner
+            0x60, // RTS
+            0xEA, // $800B inner: NOP
+            0x60, // RTS
+        ]);
+        cpu.symbols = Symbols::new([("main", 0x8000), ("outer", 0x8007), ("inner", 0x800B)]);
+        cpu.profiler = Some(Profiler::default());
+        cpu
+    }
+
+    #[test]
+    fn test_routines() {
+        let mut cpu = setup();
+        cpu.execute();
+
+        let profiler = cpu.profiler.as_ref().unwrap();
+        let main = profiler.routine_profile(0x8000).unwrap();
+        let outer = profiler.routine_profile(0x8007).unwrap();
+        let inner = profiler.routine_profile(0x800B).unwrap();
+
+        // JSR 6, RTS 6, NOP 2, BRK 7
+        assert_eq!(profiler.cycles(), cpu.cycles);
+        assert_eq!(profiler.instructions(), 9);
+        assert_eq!(
+            (inner.calls, inner.cycles, inner.inclusive_cycles),
+            (2, 16, 16)
+        );
+        assert_eq!(
+            (outer.calls, outer.cycles, outer.inclusive_cycles),
+            (1, 12, 20)
+        );
+        assert_eq!(
+            (main.cycles, main.inclusive_cycles),
+            (19, profiler.cycles())
+        );
+        assert_eq!(profiler.pc(0x800B).unwrap().instructions, 2);
+        assert_eq!(profiler.routines()[0].address, 0x8000);
+    }
+
+    #[test]
+    fn test_folded() {
+        let mut cpu = setup();
+        cpu.execute();
+
+        let folded = cpu.profiler.as_ref().unwrap().folded(&cpu.symbols);
+
+        assert_eq!(
+            folded,
+            [
+                "main 19",
+                "main;inner 8",
+                "main;outer 12",
+                "main;outer;inner 8"
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    registers::{Register, Registers},
+    Debugger,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+pub type OriginId = usize;
+
+/// A register or a memory byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Location {
+    Register(Register),
+    Memory(u16),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Register(register) => write!(f, "{register}"),
+            Location::Memory(address) => write!(f, "${address:04X}"),
+        }
+    }
+}
+
+/// An operand of an instruction, and the origin of its value at that time.
+///
+/// `origin` is `None` if the value was never produced by an instruction (e.g. loaded program bytes).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Source {
+    pub location: Location,
+    pub origin: Option<OriginId>,
+}
+
+/// An executed instruction that produced one or more values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    pub id: OriginId,
+    pub pc: u16,
+    pub opcode: u8,
+    pub instruction: u64,
+    pub sources: Vec<Source>,
+    /// Locations written by the instruction, and the values written.
+    pub outputs: Vec<(Location, u8)>,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|(location, value)| format!("{location}=${value:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let sources = self
+            .sources
+            .iter()
+            .map(|source| source.location.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(f, "{outputs} at ${:04X}", self.pc)?;
+        if !sources.is_empty() {
+            write!(f, " <- {sources}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// # Provenance
+///
+/// Tags every register and memory byte with the instruction that produced its current value ([`Origin`]),
+/// and the operands that instruction used ([`Source`]).
+/// Following the sources gives the chain of instructions a value came from.
+///
+/// Only the last `limit` origins are kept, older ones are forgotten.
+pub struct Provenance {
+    limit: usize,
+    origins: VecDeque<Origin>,
+    next_id: OriginId,
+    registers: HashMap<Register, OriginId>,
+    memory: HashMap<u16, OriginId>,
+    reads: Vec<Location>,
+    writes: Vec<(u16, u8)>,
+}
+
+impl Provenance {
+    pub const DEFAULT_LIMIT: usize = 100_000;
+
+    pub fn new(limit: usize) -> Provenance {
+        Provenance {
+            limit: limit.max(1),
+            origins: VecDeque::new(),
+            next_id: 0,
+            registers: HashMap::new(),
+            memory: HashMap::new(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Provenance::new(self.limit);
+    }
+
+    /// Records a register used by the current instruction (e.g. the index register of an addressing mode).
+    pub fn record_register(&mut self, register: Register) {
+        let location = Location::Register(register);
+
+        if !self.reads.contains(&location) {
+            self.reads.push(location);
+        }
+    }
+
+    /// Records a memory read by the current instruction.
+    pub fn record_read(&mut self, address: u16) {
+        let location = Location::Memory(address);
+
+        if !self.reads.contains(&location) {
+            self.reads.push(location);
+        }
+    }
+
+    /// Records a memory write by the current instruction.
+    pub fn record_write(&mut self, address: u16, data: u8) {
+        self.writes.push((address, data));
+    }
+
+    /// Creates the [`Origin`] of the executed instruction, and tags its outputs with it.
+    pub fn commit<R: Debugger>(
+        &mut self,
+        pc: u16,
+        opcode: u8,
+        instruction: u64,
+        registers: &Registers<R>,
+    ) {
+        let (inputs, outputs) = register_flow(opcode);
+
+        for register in inputs {
+            self.record_register(*register);
+        }
+
+        let mut reads = std::mem::take(&mut self.reads);
+        let writes = std::mem::take(&mut self.writes);
+
+        if outputs.is_empty() && writes.is_empty() {
+            return;
+        }
+
+        // Memory operands first, then registers
+        reads.sort_by_key(|location| matches!(location, Location::Register(_)));
+
+        let sources = reads
+            .into_iter()
+            .map(|location| Source {
+                location,
+                origin: self.origin_of(location).map(|origin| origin.id),
+            })
+            .collect::<Vec<_>>();
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let outputs = outputs
+            .iter()
+            .map(|register| {
+                self.registers.insert(*register, id);
+                (
+                    Location::Register(*register),
+                    registers.get(*register) as u8,
+                )
+            })
+            .chain(writes.into_iter().map(|(address, data)| {
+                self.memory.insert(address, id);
+                (Location::Memory(address), data)
+            }))
+            .collect();
+
+        if self.origins.len() >= self.limit {
+            self.origins.pop_front();
+        }
+
+        self.origins.push_back(Origin {
+            id,
+            pc,
+            opcode,
+            instruction,
+            sources,
+            outputs,
+        });
+    }
+
+    pub fn origin(&self, id: OriginId) -> Option<&Origin> {
+        let first = self.origins.front()?.id;
+
+        if id < first {
+            return None;
+        }
+
+        self.origins.get(id - first)
+    }
+
+    /// The instruction that produced the current value of `location`.
+    pub fn origin_of(&self, location: Location) -> Option<&Origin> {
+        let id = match location {
+            Location::Register(register) => self.registers.get(&register),
+            Location::Memory(address) => self.memory.get(&address),
+        }?;
+
+        self.origin(*id)
+    }
+
+    /// The chain of instructions the current value of `location` came from, newest first.
+    ///
+    /// At each step, the first source with a known origin is followed.
+    /// Memory operands come first, so `LDA $10` follows `$10` rather than the index register.
+    pub fn chain(&self, location: Location) -> Vec<&Origin> {
+        let mut chain = Vec::new();
+        let mut current = self.origin_of(location);
+
+        while let Some(origin) = current {
+            chain.push(origin);
+
+            current = origin
+                .sources
+                .iter()
+                .find_map(|source| source.origin.and_then(|id| self.origin(id)));
+        }
+
+        chain
+    }
+}
+
+impl Default for Provenance {
+    fn default() -> Provenance {
+        Provenance::new(Provenance::DEFAULT_LIMIT)
+    }
+}
+
+/// Registers read and written by an opcode, not counting the index registers of the addressing mode.
+fn register_flow(opcode: u8) -> (&'static [Register], &'static [Register]) {
+    use Register::*;
+
+    match opcode {
+        /* ADC */ 0x69 | 0x65 | 0x75 | 0x6D | 0x7D | 0x79 | 0x61 | 0x71
+        | /* SBC */ 0xE9 | 0xE5 | 0xF5 | 0xED | 0xFD | 0xF9 | 0xE1 | 0xF1 => (&[A, P], &[A, P]),
+        /* AND */ 0x29 | 0x25 | 0x35 | 0x2D | 0x3D | 0x39 | 0x21 | 0x31
+        | /* EOR */ 0x49 | 0x45 | 0x55 | 0x4D | 0x5D | 0x59 | 0x41 | 0x51
+        | /* ORA */ 0x09 | 0x05 | 0x15 | 0x0D | 0x1D | 0x19 | 0x01 | 0x11
+        | /* ASL, LSR */ 0x0A | 0x4A => (&[A], &[A, P]),
+        /* ROL, ROR */ 0x2A | 0x6A => (&[A, P], &[A, P]),
+        /* ASL, LSR */ 0x06 | 0x16 | 0x0E | 0x1E | 0x46 | 0x56 | 0x4E | 0x5E
+        | /* DEC, INC */ 0xC6 | 0xD6 | 0xCE | 0xDE | 0xE6 | 0xF6 | 0xEE | 0xFE
+        | /* PLP, RTI */ 0x28 | 0x40 => (&[], &[P]),
+        /* ROL, ROR */ 0x26 | 0x36 | 0x2E | 0x3E | 0x66 | 0x76 | 0x6E | 0x7E
+        | /* CLC, CLD, CLI, CLV */ 0x18 | 0xD8 | 0x58 | 0xB8
+        | /* SEC, SED, SEI */ 0x38 | 0xF8 | 0x78 => (&[P], &[P]),
+        /* BIT */ 0x24 | 0x2C
+        | /* CMP */ 0xC9 | 0xC5 | 0xD5 | 0xCD | 0xDD | 0xD9 | 0xC1 | 0xD1 => (&[A], &[P]),
+        /* CPX */ 0xE0 | 0xE4 | 0xEC => (&[X], &[P]),
+        /* CPY */ 0xC0 | 0xC4 | 0xCC => (&[Y], &[P]),
+        /* DEX, INX */ 0xCA | 0xE8 => (&[X], &[X, P]),
+        /* DEY, INY */ 0x88 | 0xC8 => (&[Y], &[Y, P]),
+        /* LDA */ 0xA9 | 0xA5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1
+        | /* PLA */ 0x68 => (&[], &[A, P]),
+        /* LDX */ 0xA2 | 0xA6 | 0xB6 | 0xAE | 0xBE => (&[], &[X, P]),
+        /* LDY */ 0xA0 | 0xA4 | 0xB4 | 0xAC | 0xBC => (&[], &[Y, P]),
+        /* STA */ 0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91
+        | /* PHA */ 0x48 => (&[A], &[]),
+        /* STX */ 0x86 | 0x96 | 0x8E => (&[X], &[]),
+        /* STY */ 0x84 | 0x94 | 0x8C => (&[Y], &[]),
+        /* PHP */ 0x08 => (&[P], &[]),
+        /* TAX */ 0xAA => (&[A], &[X, P]),
+        /* TAY */ 0xA8 => (&[A], &[Y, P]),
+        /* TSX */ 0xBA => (&[SP], &[X, P]),
+        /* TXA */ 0x8A => (&[X], &[A, P]),
+        /* TXS */ 0x9A => (&[X], &[SP]),
+        /* TYA */ 0x98 => (&[Y], &[A, P]),
+        _ => (&[], &[]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, NoneDebugger};
+
+    #[test]
+    fn test_chain() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA9, 0x05, // LDA #$05
+            0x85, 0x10, // STA $10
+            0xA9, 0x00, // LDA #$00
+            0xA6, 0x10, // LDX $10
+            0x8A, // TXA
+            0x00, // BRK
+        ]);
+        cpu.provenance = Some(Provenance::default());
+        cpu.execute();
+
+        let provenance = cpu.provenance.as_ref().unwrap();
+        let chain = provenance.chain(Location::Register(Register::A));
+
+        assert_eq!(
+            chain.iter().map(|origin| origin.pc).collect::<Vec<_>>(),
+            vec![0x8008, 0x8006, 0x8002, 0x8000]
+        );
+        assert_eq!(chain[0].outputs[0], (Location::Register(Register::A), 0x05));
+        assert_eq!(chain[1].sources[0].location, Location::Memory(0x0010));
+        assert_eq!(chain[2].outputs, vec![(Location::Memory(0x0010), 0x05)]);
+        // `LDA #$05` loads a constant from the program, not a value from memory
+        assert!(chain[3].sources.is_empty());
+    }
+
+    #[test]
+    fn test_index_register_source() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x02, // LDX #$02
+            0xB5, 0x10, // LDA $10,X
+            0x00, // BRK
+        ]);
+        cpu.provenance = Some(Provenance::default());
+        cpu.execute();
+
+        let provenance = cpu.provenance.as_ref().unwrap();
+        let origin = provenance
+            .origin_of(Location::Register(Register::A))
+            .unwrap();
+
+        assert_eq!(origin.pc, 0x8002);
+        assert_eq!(origin.sources[0].location, Location::Memory(0x0012));
+        assert_eq!(origin.sources[1].location, Location::Register(Register::X));
+        assert_eq!(origin.to_string(), "A=$00 P=$02 at $8002 <- $0012, X");
+    }
+}
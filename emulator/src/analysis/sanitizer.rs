@@ -0,0 +1,350 @@
+use crate::memory::MEMORY_SIZE;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    ops::RangeInclusive,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// Read of RAM never written since reset.
+    UninitializedRead,
+    /// Write into a read-only region.
+    RomWrite,
+    /// Instruction fetched from a data region.
+    DataExecute,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::UninitializedRead => write!(f, "Uninitialized read"),
+            ViolationKind::RomWrite => write!(f, "ROM write"),
+            ViolationKind::DataExecute => write!(f, "Data execute"),
+        }
+    }
+}
+
+/// A memory access flagged by the [`Sanitizer`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// Address of the accessing instruction.
+    pub pc: u16,
+    /// Accessed address.
+    pub address: u16,
+    /// Cycle count at the start of the accessing instruction.
+    pub cycle: u64,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of 0x{:04X} at 0x{:04X}",
+            self.kind, self.address, self.pc
+        )
+    }
+}
+
+/// # Sanitizer
+///
+/// Flags reads of uninitialized RAM, writes into read-only regions and execution from data regions.
+///
+/// By default, `0x8000` ~ `0xFFFF` (ROM) is read-only and everything below it is data,
+/// see [`Memory`](crate::memory::Memory) for the memory map.
+/// Read-only regions count as initialized, so only RAM reads are checked.
+/// Memory loaded since the last reset ([`Cpu::load_at`](crate::cpu::Cpu::load_at)) counts as initialized.
+/// The code of the program ([`Cpu::load`](crate::cpu::Cpu::load), [`Cpu::load_program`](crate::cpu::Cpu::load_program))
+/// is executable outside read-only memory too, unless it is in a declared [`Sanitizer::data`] region.
+///
+/// Each kind of violation is reported once per instruction and address, only the last `limit` are kept.
+#[derive(Clone, Debug)]
+pub struct Sanitizer {
+    pub read_only: Vec<RangeInclusive<u16>>,
+    /// Regions declared as data. Writable memory outside the loaded program is data as well.
+    pub data: Vec<RangeInclusive<u16>>,
+    /// Whether to stop execution at new violations.
+    pub stop: bool,
+    limit: usize,
+    initialized: Vec<bool>,
+    loaded: Vec<bool>,
+    reported: HashSet<(ViolationKind, u16, u16)>,
+    violations: VecDeque<Violation>,
+    stopped: Option<Violation>,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl Sanitizer {
+    pub const DEFAULT_LIMIT: usize = 1_000;
+
+    pub fn new(limit: usize) -> Sanitizer {
+        Sanitizer {
+            read_only: vec![0x8000..=0xFFFF],
+            data: Vec::new(),
+            stop: false,
+            limit: limit.max(1),
+            initialized: vec![false; MEMORY_SIZE],
+            loaded: vec![false; MEMORY_SIZE],
+            reported: HashSet::new(),
+            violations: VecDeque::new(),
+            stopped: None,
+        }
+    }
+
+    /// Forgets initialized memory and violations. Regions and the loaded program are kept.
+    pub fn clear(&mut self) {
+        self.initialized.fill(false);
+        self.reported.clear();
+        self.violations.clear();
+        self.stopped = None;
+    }
+
+    /// Forgets everything since the last reset, including the loaded program.
+    pub fn reset(&mut self) {
+        self.clear();
+        self.loaded.fill(false);
+    }
+
+    /// Marks `range` as initialized, e.g. for memory written by devices rather than the CPU.
+    pub fn initialize(&mut self, range: RangeInclusive<u16>) {
+        self.initialized[*range.start() as usize..=*range.end() as usize].fill(true);
+    }
+
+    /// Marks `range` as code of the loaded program, initialized and executable.
+    pub fn mark_loaded(&mut self, range: RangeInclusive<u16>) {
+        self.initialize(range.clone());
+        self.loaded[*range.start() as usize..=*range.end() as usize].fill(true);
+    }
+
+    pub fn is_initialized(&self, address: u16) -> bool {
+        self.initialized[address as usize] || self.is_read_only(address) || self.is_loaded(address)
+    }
+
+    pub fn is_loaded(&self, address: u16) -> bool {
+        self.loaded[address as usize]
+    }
+
+    pub fn is_read_only(&self, address: u16) -> bool {
+        self.read_only.iter().any(|range| range.contains(&address))
+    }
+
+    pub fn is_data(&self, address: u16) -> bool {
+        self.data.iter().any(|range| range.contains(&address))
+            || !(self.is_read_only(address) || self.is_loaded(address))
+    }
+
+    pub fn record_read(&mut self, pc: u16, address: u16, cycle: u64) -> Option<Violation> {
+        if self.is_initialized(address) {
+            return None;
+        }
+
+        self.report(ViolationKind::UninitializedRead, pc, address, cycle)
+    }
+
+    pub fn record_write(&mut self, pc: u16, address: u16, cycle: u64) -> Option<Violation> {
+        self.initialized[address as usize] = true;
+
+        if !self.is_read_only(address) {
+            return None;
+        }
+
+        self.report(ViolationKind::RomWrite, pc, address, cycle)
+    }
+
+    /// Checks the opcode fetch of the instruction at `pc`.
+    pub fn record_execute(&mut self, pc: u16, cycle: u64) -> Option<Violation> {
+        if !self.is_data(pc) {
+            return None;
+        }
+
+        self.report(ViolationKind::DataExecute, pc, pc, cycle)
+    }
+
+    fn report(
+        &mut self,
+        kind: ViolationKind,
+        pc: u16,
+        address: u16,
+        cycle: u64,
+    ) -> Option<Violation> {
+        if !self.reported.insert((kind, pc, address)) {
+            return None;
+        }
+
+        let violation = Violation {
+            kind,
+            pc,
+            address,
+            cycle,
+        };
+
+        if self.violations.len() >= self.limit {
+            self.violations.pop_front();
+        }
+
+        self.violations.push_back(violation);
+
+        if self.stop && self.stopped.is_none() {
+            self.stopped = Some(violation);
+        }
+
+        Some(violation)
+    }
+
+    /// The first new violation since the last call, if execution should stop.
+    pub fn take_stop(&mut self) -> Option<Violation> {
+        self.stopped.take()
+    }
+
+    /// Recorded violations, oldest first.
+    pub fn violations(&self) -> impl DoubleEndedIterator<Item = &Violation> {
+        self.violations.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::Memory, program::Program, replay::InputEvent, Cpu6502, CpuDebugger, NoneDebugger,
+        StopReason,
+    };
+
+    fn setup(program: &[u8]) -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(program);
+        cpu.sanitizer = Some(Sanitizer::default());
+        cpu
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut cpu = setup(&[
+            0xA9, 0x01, // $8000 LDA #$01
+            0x85, 0x10, // $8002 STA $10
+            0xA5, 0x10, // $8004 LDA $10
+            0xA5, 0x11, // $8006 LDA $11
+            0xA5, 0x11, // $8008 LDA $11
+            0x8D, 0x00, 0x90, // $800A STA $9000
+            0x4C, 0x00, 0x02, // $800D JMP $0200
+        ]);
+        cpu.memory.mem[0x0200] = 0x00; // BRK
+        cpu.execute();
+
+        let violations = cpu
+            .sanitizer
+            .as_ref()
+            .unwrap()
+            .violations()
+            .map(|violation| (violation.kind, violation.pc, violation.address))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            violations,
+            vec![
+                (ViolationKind::UninitializedRead, 0x8006, 0x0011),
+                (ViolationKind::UninitializedRead, 0x8008, 0x0011),
+                (ViolationKind::RomWrite, 0x800A, 0x9000),
+                (ViolationKind::DataExecute, 0x0200, 0x0200),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut cpu = setup(&[
+            0xA5, 0x10, // $8000 LDA $10
+            0xE8, // $8002 INX
+            0x00, // $8003 BRK
+        ]);
+        cpu.sanitizer.as_mut().unwrap().stop = true;
+
+        let reason = cpu.run();
+
+        assert_eq!(
+            reason,
+            StopReason::Sanitizer(Violation {
+                kind: ViolationKind::UninitializedRead,
+                pc: 0x8000,
+                address: 0x0010,
+                cycle: 0,
+            })
+        );
+        assert_eq!(cpu.registers.pc, 0x8002);
+        assert_eq!(cpu.run(), StopReason::Brk);
+    }
+
+    #[test]
+    fn test_input() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.sanitizer = Some(Sanitizer::default());
+        cpu.reset();
+        cpu.load(&[
+            0xA5, 0xFF, // $8000 LDA $FF
+            0x00, // $8002 BRK
+        ]);
+        cpu.input(InputEvent::Poke {
+            address: 0x00FF,
+            data: 0x41,
+        });
+        cpu.execute();
+
+        assert_eq!(cpu.registers.a, 0x41);
+        assert_eq!(cpu.sanitizer.as_ref().unwrap().violations().count(), 0);
+    }
+
+    #[test]
+    fn test_loaded_program() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.sanitizer = Some(Sanitizer::default());
+        cpu.reset();
+        cpu.load_program(
+            &Program::new()
+                .with_segment(
+                    0x0600,
+                    &[
+                        0xA9, 0x01, // $0600 LDA #$01
+                        0xAD, 0x00, 0x07, // $0602 LDA $0700
+                        0x00, // $0605 BRK
+                    ],
+                )
+                .with_segment(0x0700, &[0x42])
+                .with_entry(0x0600),
+        );
+        // Clearing the violations keeps the loaded program
+        cpu.sanitizer.as_mut().unwrap().clear();
+        cpu.execute();
+
+        let sanitizer = cpu.sanitizer.as_ref().unwrap();
+        assert_eq!(cpu.registers.a, 0x42);
+        assert_eq!(sanitizer.violations().count(), 0);
+        assert!(sanitizer.is_data(0x0800));
+        assert!(!sanitizer.is_initialized(0x0800));
+
+        // Other loaded data is initialized but not executable
+        cpu.set_reset_vector(0x0600);
+        cpu.load_at(0x0800, &[0x00]);
+        let sanitizer = cpu.sanitizer.as_mut().unwrap();
+        assert!(sanitizer.is_initialized(0x0800));
+        assert!(sanitizer.is_data(0x0800));
+        assert!(!sanitizer.is_loaded(0xFFFC));
+
+        // Declared data is not executable, even in the program
+        sanitizer.data.push(0x0700..=0x07FF);
+        assert!(sanitizer.is_data(0x0700));
+        assert!(!sanitizer.is_data(0x0600));
+
+        // A reset forgets the program
+        cpu.reset();
+        let sanitizer = cpu.sanitizer.as_ref().unwrap();
+        assert!(!sanitizer.is_initialized(0x0600));
+        assert!(sanitizer.is_data(0x0600));
+    }
+}
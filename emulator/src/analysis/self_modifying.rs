@@ -0,0 +1,175 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+/// A write into the opcode or operand bytes of a previously executed instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelfModification {
+    /// Address of the writing instruction.
+    pub pc: u16,
+    /// Written address.
+    pub address: u16,
+    /// Address of the modified instruction.
+    pub instruction: u16,
+    /// Opcode of the modified instruction when it was last executed.
+    pub opcode: u8,
+    pub old: u8,
+    pub new: u8,
+    /// Cycle count at the start of the writing instruction.
+    pub cycle: u64,
+}
+
+impl SelfModification {
+    /// Whether the opcode, rather than an operand byte, was written.
+    pub fn is_opcode(&self) -> bool {
+        self.address == self.instruction
+    }
+}
+
+impl fmt::Display for SelfModification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Self-modifying write at 0x{:04X}: 0x{:04X} ({} of instruction 0x{:02X} at 0x{:04X}) 0x{:02X} -> 0x{:02X}",
+            self.pc,
+            self.address,
+            if self.is_opcode() { "opcode" } else { "operand" },
+            self.opcode,
+            self.instruction,
+            self.old,
+            self.new
+        )
+    }
+}
+
+/// # Self-Modifying Code
+///
+/// Remembers which instruction each executed byte belongs to, and reports writes to those bytes.
+///
+/// Only the last `limit` modifications are kept.
+#[derive(Clone, Debug)]
+pub struct SelfModifyingCode {
+    limit: usize,
+    /// Executed byte -> (address, opcode) of its instruction.
+    executed: HashMap<u16, (u16, u8)>,
+    modifications: VecDeque<SelfModification>,
+}
+
+impl Default for SelfModifyingCode {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl SelfModifyingCode {
+    pub const DEFAULT_LIMIT: usize = 1_000;
+
+    pub fn new(limit: usize) -> SelfModifyingCode {
+        SelfModifyingCode {
+            limit: limit.max(1),
+            executed: HashMap::new(),
+            modifications: VecDeque::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.executed.clear();
+        self.modifications.clear();
+    }
+
+    /// Records the bytes of an executed instruction.
+    pub fn record_execute(&mut self, pc: u16, opcode: u8, length: u8) {
+        for offset in 0..length as u16 {
+            self.executed.insert(pc.wrapping_add(offset), (pc, opcode));
+        }
+    }
+
+    /// Checks a write made by the instruction at `pc`, and records it if it modifies executed code.
+    pub fn record_write(
+        &mut self,
+        pc: u16,
+        address: u16,
+        old: u8,
+        new: u8,
+        cycle: u64,
+    ) -> Option<SelfModification> {
+        let (instruction, opcode) = *self.executed.get(&address)?;
+
+        let modification = SelfModification {
+            pc,
+            address,
+            instruction,
+            opcode,
+            old,
+            new,
+            cycle,
+        };
+
+        if self.modifications.len() >= self.limit {
+            self.modifications.pop_front();
+        }
+
+        self.modifications.push_back(modification);
+
+        Some(modification)
+    }
+
+    /// Whether `address` was executed as part of an instruction.
+    pub fn is_executed(&self, address: u16) -> bool {
+        self.executed.contains_key(&address)
+    }
+
+    /// Recorded modifications, oldest first.
+    pub fn modifications(&self) -> impl DoubleEndedIterator<Item = &SelfModification> {
+        self.modifications.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, NoneDebugger};
+
+    #[test]
+    fn test_self_modification() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x02, // $8000 LDX #$02
+            0xA9, 0x01, // $8002 LOOP: LDA #$01
+            0xEE, 0x03, 0x80, // $8004 INC $8003
+            0x8D, 0x0F, 0x80, // $8007 STA $800F
+            0xCA, // $800A DEX
+            0xD0, 0xF5, // $800B BNE LOOP
+            0x00, // $800D BRK
+        ]);
+        cpu.self_modifying = Some(SelfModifyingCode::default());
+        cpu.execute();
+
+        let modifications = cpu
+            .self_modifying
+            .as_ref()
+            .unwrap()
+            .modifications()
+            .collect::<Vec<_>>();
+
+        // `STA $800F` writes data that is never executed
+        assert_eq!(modifications.len(), 2);
+        assert_eq!(
+            *modifications[0],
+            SelfModification {
+                pc: 0x8004,
+                address: 0x8003,
+                instruction: 0x8002,
+                opcode: 0xA9,
+                old: 0x01,
+                new: 0x02,
+                cycle: 2 + 2,
+            }
+        );
+        assert!(!modifications[0].is_opcode());
+        assert_eq!(modifications[1].new, 0x03);
+        assert_eq!(cpu.registers.a, 0x02);
+    }
+}
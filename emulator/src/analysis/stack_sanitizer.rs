@@ -0,0 +1,398 @@
+use crate::{memory::STACK_BASE, DebugKind};
+use std::{collections::VecDeque, fmt};
+
+/// A stack misuse flagged by the [`StackSanitizer`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StackIssue {
+    /// A push wrapped the stack pointer below `$0100`.
+    Overflow { pc: u16 },
+    /// A pop wrapped the stack pointer past `$01FF`.
+    Underflow { pc: u16 },
+    /// `RTS` without a matching `JSR`.
+    ReturnWithoutCall { pc: u16 },
+    /// `RTI` outside an interrupt handler.
+    RtiOutsideInterrupt { pc: u16 },
+    /// A subroutine or interrupt handler returned with a different stack pointer than it was entered with.
+    UnbalancedReturn {
+        pc: u16,
+        routine: u16,
+        entry_sp: u8,
+        return_sp: u8,
+    },
+}
+
+impl StackIssue {
+    /// Address of the offending instruction.
+    pub fn pc(&self) -> u16 {
+        match self {
+            StackIssue::Overflow { pc }
+            | StackIssue::Underflow { pc }
+            | StackIssue::ReturnWithoutCall { pc }
+            | StackIssue::RtiOutsideInterrupt { pc }
+            | StackIssue::UnbalancedReturn { pc, .. } => *pc,
+        }
+    }
+
+    /// Severity reported to the debugger. Wraparounds corrupt the stack, so they are errors.
+    pub fn kind(&self) -> DebugKind {
+        match self {
+            StackIssue::Overflow { .. } | StackIssue::Underflow { .. } => DebugKind::Error,
+            _ => DebugKind::Warn,
+        }
+    }
+}
+
+impl fmt::Display for StackIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackIssue::Overflow { pc } => {
+                write!(f, "Stack overflow at 0x{pc:04X} (wrapped below $0100)")
+            }
+            StackIssue::Underflow { pc } => {
+                write!(f, "Stack underflow at 0x{pc:04X} (wrapped past $01FF)")
+            }
+            StackIssue::ReturnWithoutCall { pc } => {
+                write!(f, "RTS at 0x{pc:04X} without a matching JSR")
+            }
+            StackIssue::RtiOutsideInterrupt { pc } => {
+                write!(f, "RTI at 0x{pc:04X} outside an interrupt handler")
+            }
+            StackIssue::UnbalancedReturn {
+                pc,
+                routine,
+                entry_sp,
+                return_sp,
+            } => write!(
+                f,
+                "Return at 0x{pc:04X} from 0x{routine:04X} left SP at 0x{return_sp:02X}, entered with 0x{entry_sp:02X}"
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct StackFrame {
+    routine: u16,
+    /// Stack pointer before the call or interrupt pushed anything.
+    sp: u8,
+    interrupt: bool,
+}
+
+/// # Stack Sanitizer
+///
+/// Flags stack pointer wraparound, unmatched `RTS`/`RTI` and routines that return with an unbalanced stack,
+/// and tracks the deepest stack address used.
+///
+/// Returns through pushed addresses (e.g. a `PHA`/`PHA`/`RTS` jump table) are reported as [`StackIssue::ReturnWithoutCall`].
+/// Only the last `limit` issues are kept.
+///
+/// The stack pointer is `0x00` after a reset, so wraparound and the deepest address are only checked
+/// once the program set up the stack with `TXS`.
+#[derive(Clone, Debug)]
+pub struct StackSanitizer {
+    /// Whether to stop execution at new issues.
+    pub stop: bool,
+    limit: usize,
+    frames: Vec<StackFrame>,
+    issues: VecDeque<StackIssue>,
+    lowest_sp: Option<u8>,
+    stopped: Option<StackIssue>,
+    stack_set: bool,
+}
+
+impl Default for StackSanitizer {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl StackSanitizer {
+    pub const DEFAULT_LIMIT: usize = 1_000;
+
+    pub fn new(limit: usize) -> StackSanitizer {
+        StackSanitizer {
+            stop: false,
+            limit: limit.max(1),
+            frames: Vec::new(),
+            issues: VecDeque::new(),
+            lowest_sp: None,
+            stopped: None,
+            stack_set: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.issues.clear();
+        self.lowest_sp = None;
+        self.stopped = None;
+        self.stack_set = false;
+    }
+
+    /// Checks a push made by the instruction at `pc`, with the stack pointer before the push.
+    pub fn record_push(&mut self, pc: u16, sp: u8) -> Option<StackIssue> {
+        if !self.stack_set {
+            return None;
+        }
+
+        self.lowest_sp = Some(self.lowest_sp.map_or(sp, |lowest| lowest.min(sp)));
+
+        (sp == 0x00).then(|| self.report(StackIssue::Overflow { pc }))
+    }
+
+    /// Checks a pop made by the instruction at `pc`, with the stack pointer before the pop.
+    pub fn record_pop(&mut self, pc: u16, sp: u8) -> Option<StackIssue> {
+        if !self.stack_set {
+            return None;
+        }
+
+        (sp == 0xFF).then(|| self.report(StackIssue::Underflow { pc }))
+    }
+
+    /// Records an executed instruction.
+    ///
+    /// `sp` is the stack pointer before the instruction, `next_pc` and `next_sp` after it.
+    pub fn record(
+        &mut self,
+        pc: u16,
+        opcode: u8,
+        sp: u8,
+        next_pc: u16,
+        next_sp: u8,
+    ) -> Option<StackIssue> {
+        match opcode {
+            // TXS
+            0x9A => {
+                self.stack_set = true;
+                None
+            }
+            // JSR
+            0x20 => {
+                self.frames.push(StackFrame {
+                    routine: next_pc,
+                    sp,
+                    interrupt: false,
+                });
+                None
+            }
+            // RTS, RTI
+            0x60 | 0x40 => {
+                let interrupt = opcode == 0x40;
+
+                match self.frames.last() {
+                    Some(frame) if frame.interrupt == interrupt => {
+                        let frame = self.frames.pop()?;
+
+                        (frame.sp != next_sp).then(|| {
+                            self.report(StackIssue::UnbalancedReturn {
+                                pc,
+                                routine: frame.routine,
+                                entry_sp: frame.sp,
+                                return_sp: next_sp,
+                            })
+                        })
+                    }
+                    _ if interrupt => Some(self.report(StackIssue::RtiOutsideInterrupt { pc })),
+                    _ => Some(self.report(StackIssue::ReturnWithoutCall { pc })),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Records the entry into an interrupt handler, with the stack pointer before the interrupt.
+    pub fn interrupt(&mut self, handler: u16, sp: u8) {
+        self.frames.push(StackFrame {
+            routine: handler,
+            sp,
+            interrupt: true,
+        });
+    }
+
+    fn report(&mut self, issue: StackIssue) -> StackIssue {
+        if self.issues.len() >= self.limit {
+            self.issues.pop_front();
+        }
+
+        self.issues.push_back(issue);
+
+        if self.stop && self.stopped.is_none() {
+            self.stopped = Some(issue);
+        }
+
+        issue
+    }
+
+    /// The first new issue since the last call, if execution should stop.
+    pub fn take_stop(&mut self) -> Option<StackIssue> {
+        self.stopped.take()
+    }
+
+    /// The lowest stack address pushed to since the last clear.
+    pub fn high_water_mark(&self) -> Option<u16> {
+        self.lowest_sp.map(|sp| STACK_BASE + sp as u16)
+    }
+
+    /// Recorded issues, oldest first.
+    pub fn issues(&self) -> impl DoubleEndedIterator<Item = &StackIssue> {
+        self.issues.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, CpuDebugger, NoneDebugger, StopReason};
+
+    /// Runs `program` after setting up the stack pointer with `TXS`.
+    fn run(program: &[u8], sp: u8) -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(program);
+        cpu.memory.mem[0x9000..0x9004].copy_from_slice(&[
+            0x9A, // $9000 TXS
+            0x4C, 0x00, 0x80, // $9001 JMP $8000
+        ]);
+        cpu.registers.x = sp;
+        cpu.registers.pc = 0x9000;
+        cpu.stack_sanitizer = Some(StackSanitizer::default());
+        cpu.execute();
+        cpu
+    }
+
+    fn issues(cpu: &Cpu6502<NoneDebugger>) -> Vec<StackIssue> {
+        cpu.stack_sanitizer
+            .as_ref()
+            .unwrap()
+            .issues()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_balanced() {
+        let cpu = run(
+            &[
+                0x20, 0x04, 0x80, // $8000 JSR $8004
+                0x00, // $8003 BRK
+                0x48, // $8004 PHA
+                0x68, // $8005 PLA
+                0x60, // $8006 RTS
+            ],
+            0xFF,
+        );
+
+        assert_eq!(issues(&cpu), vec![]);
+        assert_eq!(
+            cpu.stack_sanitizer.as_ref().unwrap().high_water_mark(),
+            Some(0x01FD)
+        );
+    }
+
+    #[test]
+    fn test_reset_stack() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.stack_sanitizer = Some(StackSanitizer {
+            stop: true,
+            ..Default::default()
+        });
+        cpu.reset();
+        cpu.load(&[
+            0x20, 0x04, 0x80, // $8000 JSR $8004
+            0x00, // $8003 BRK
+            0x48, // $8004 PHA
+            0x68, // $8005 PLA
+            0x60, // $8006 RTS
+        ]);
+
+        // Without `TXS`, pushing at SP 0x00 is not an overflow
+        assert_eq!(cpu.run(), StopReason::Brk);
+        assert_eq!(issues(&cpu), vec![]);
+        assert_eq!(
+            cpu.stack_sanitizer.as_ref().unwrap().high_water_mark(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        let cpu = run(
+            &[
+                0x60, // $8000 RTS
+                0x00, // $8001 BRK
+            ],
+            0xFE,
+        );
+
+        assert_eq!(
+            issues(&cpu),
+            vec![
+                StackIssue::Underflow { pc: 0x8000 },
+                StackIssue::ReturnWithoutCall { pc: 0x8000 }
+            ]
+        );
+
+        let cpu = run(
+            &[
+                0x20, 0x04, 0x80, // $8000 JSR $8004
+                0x00, // $8003 BRK
+                0x48, // $8004 PHA
+                0x60, // $8005 RTS
+            ],
+            0xFF,
+        );
+
+        assert_eq!(
+            issues(&cpu)[0],
+            StackIssue::UnbalancedReturn {
+                pc: 0x8005,
+                routine: 0x8004,
+                entry_sp: 0xFF,
+                return_sp: 0xFE
+            }
+        );
+    }
+
+    #[test]
+    fn test_overflow_and_rti() {
+        let cpu = run(
+            &[
+                0x48, // $8000 PHA
+                0x40, // $8001 RTI
+                0x00, // $8002 BRK
+            ],
+            0x00,
+        );
+
+        assert_eq!(issues(&cpu)[0], StackIssue::Overflow { pc: 0x8000 });
+        assert_eq!(issues(&cpu)[1], StackIssue::Underflow { pc: 0x8001 });
+        assert!(issues(&cpu).contains(&StackIssue::RtiOutsideInterrupt { pc: 0x8001 }));
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xE8, // $8000 INX
+            0x60, // $8001 RTS
+            0xE8, // $8002 INX
+            0x00, // $8003 BRK
+        ]);
+        // Returns to $8002
+        cpu.memory.mem[0x01FE] = 0x01;
+        cpu.memory.mem[0x01FF] = 0x80;
+        cpu.registers.sp = 0xFD;
+        cpu.stack_sanitizer = Some(StackSanitizer {
+            stop: true,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            cpu.run(),
+            StopReason::StackSanitizer(StackIssue::ReturnWithoutCall { pc: 0x8001 })
+        );
+        assert_eq!(cpu.registers.pc, 0x8002);
+        assert_eq!(cpu.run(), StopReason::Brk);
+    }
+}
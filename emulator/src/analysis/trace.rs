@@ -0,0 +1,342 @@
+use crate::{
+    cpu::Cpu,
+    memory::MemoryBus,
+    opcode::{decode, instruction_length, Operand},
+    Debugger,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    path::Path,
+};
+
+/// A condition that starts or stops a [`Tracer`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trigger {
+    /// An instruction in the address range is about to execute.
+    Pc(RangeInclusive<u16>),
+    /// The cycle count reached the value.
+    Cycle(u64),
+}
+
+impl Trigger {
+    pub fn matches(&self, pc: u16, cycles: u64) -> bool {
+        match self {
+            Trigger::Pc(range) => range.contains(&pc),
+            Trigger::Cycle(cycle) => cycles >= *cycle,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceState {
+    /// Waiting for the start trigger.
+    Waiting,
+    Tracing,
+    /// The stop trigger matched, or writing failed.
+    Done,
+}
+
+/// # Tracer
+///
+/// Writes a line per executed instruction, in the column layout of nestest logs:
+///
+/// ```text
+/// 8002  BD 00 02  LDA $0200,X @ 0201 = 05         A:00 X:01 Y:00 P:00 SP:FF CYC:2
+/// ```
+///
+/// Registers and cycles are the state before the instruction executes.
+/// Tracing begins at the first instruction matching `start` (immediately without one),
+/// and ends before the first instruction matching `stop`.
+pub struct Tracer {
+    writer: Box<dyn Write + Send>,
+    pub start: Option<Trigger>,
+    pub stop: Option<Trigger>,
+    state: TraceState,
+    lines: u64,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    pub fn new(writer: impl Write + Send + 'static) -> Tracer {
+        Tracer {
+            writer: Box::new(writer),
+            start: None,
+            stop: None,
+            state: TraceState::Waiting,
+            lines: 0,
+            error: None,
+        }
+    }
+
+    /// Creates a tracer writing to a new file at `path`.
+    pub fn file(path: impl AsRef<Path>) -> io::Result<Tracer> {
+        Ok(Tracer::new(BufWriter::new(File::create(path)?)))
+    }
+
+    pub fn with_start(mut self, trigger: Trigger) -> Tracer {
+        self.start = Some(trigger);
+        self
+    }
+
+    pub fn with_stop(mut self, trigger: Trigger) -> Tracer {
+        self.stop = Some(trigger);
+        self
+    }
+
+    pub fn state(&self) -> TraceState {
+        self.state
+    }
+
+    /// Number of lines written.
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    /// The write error that ended tracing, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Updates the state for the instruction at `pc`, and returns whether it should be traced.
+    pub fn update(&mut self, pc: u16, cycles: u64) -> bool {
+        if self.state == TraceState::Waiting
+            && self
+                .start
+                .as_ref()
+                .is_none_or(|start| start.matches(pc, cycles))
+        {
+            self.state = TraceState::Tracing;
+        }
+
+        if self.state == TraceState::Tracing
+            && self
+                .stop
+                .as_ref()
+                .is_some_and(|stop| stop.matches(pc, cycles))
+        {
+            self.state = TraceState::Done;
+            self.flush();
+        }
+
+        self.state == TraceState::Tracing
+    }
+
+    pub fn write_line(&mut self, line: &str) {
+        if let Err(e) = writeln!(self.writer, "{line}") {
+            self.state = TraceState::Done;
+            self.error = Some(e);
+            return;
+        }
+
+        self.lines += 1;
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.state = TraceState::Done;
+            self.error = Some(e);
+        }
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// The instruction at `pc` with its operand resolved against the current state,
+    /// e.g. `LDA ($80),Y = 0300 @ 0305 = 05`.
+    ///
+    /// Undocumented opcodes are shown as `NOP`.
+    pub fn disassemble(&self, pc: u16) -> String {
+        let peek = |address: u16| self.memory.peek(address);
+        let peek_addr =
+            |address: u16| u16::from_le_bytes([peek(address), peek(address.wrapping_add(1))]);
+
+        let opcode = match decode(peek(pc)) {
+            Some(opcode) => opcode,
+            None => return "NOP".to_string(),
+        };
+
+        let byte = peek(pc.wrapping_add(1));
+        let word = peek_addr(pc.wrapping_add(1));
+        let (x, y) = (self.registers.x, self.registers.y);
+
+        let operand = match opcode.operand {
+            Operand::Implied => String::new(),
+            Operand::Accumulator => "A".to_string(),
+            Operand::Immediate => format!("#${byte:02X}"),
+            Operand::ZeroPage => format!("${byte:02X} = {:02X}", peek(byte as u16)),
+            Operand::ZeroPageX => {
+                let address = byte.wrapping_add(x) as u16;
+                format!("${byte:02X},X @ {address:02X} = {:02X}", peek(address))
+            }
+            Operand::ZeroPageY => {
+                let address = byte.wrapping_add(y) as u16;
+                format!("${byte:02X},Y @ {address:02X} = {:02X}", peek(address))
+            }
+            Operand::Absolute if matches!(opcode.mnemonic, "JMP" | "JSR") => {
+                format!("${word:04X}")
+            }
+            Operand::Absolute => format!("${word:04X} = {:02X}", peek(word)),
+            Operand::AbsoluteX => {
+                let address = word.wrapping_add(x as u16);
+                format!("${word:04X},X @ {address:04X} = {:02X}", peek(address))
+            }
+            Operand::AbsoluteY => {
+                let address = word.wrapping_add(y as u16);
+                format!("${word:04X},Y @ {address:04X} = {:02X}", peek(address))
+            }
+            Operand::Indirect => format!("(${word:04X}) = {:04X}", peek_addr(word)),
+            Operand::IndirectX => {
+                let pointer = byte.wrapping_add(x) as u16;
+                let address = peek_addr(pointer);
+                format!(
+                    "(${byte:02X},X) @ {pointer:02X} = {address:04X} = {:02X}",
+                    peek(address)
+                )
+            }
+            Operand::IndirectY => {
+                let base = peek_addr(byte as u16);
+                let address = base.wrapping_add(y as u16);
+                format!(
+                    "(${byte:02X}),Y = {base:04X} @ {address:04X} = {:02X}",
+                    peek(address)
+                )
+            }
+            Operand::Relative => {
+                let target = pc.wrapping_add(2).wrapping_add(byte as i8 as u16);
+                format!("${target:04X}")
+            }
+        };
+
+        if operand.is_empty() {
+            opcode.mnemonic.to_string()
+        } else {
+            format!("{} {operand}", opcode.mnemonic)
+        }
+    }
+
+    /// A nestest-style trace line for the instruction at the program counter, see [`Tracer`].
+    pub fn trace_line(&self) -> String {
+        let pc = self.registers.pc;
+        let opcode = self.memory.peek(pc);
+
+        let bytes = (0..instruction_length(opcode) as u16)
+            .map(|offset| format!("{:02X}", self.memory.peek(pc.wrapping_add(offset))))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Undocumented opcodes are marked with `*`, like in nestest logs
+        let marker = if decode(opcode).is_some() { ' ' } else { '*' };
+
+        format!(
+            "{pc:04X}  {bytes:<8} {marker}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+            self.disassemble(pc),
+            self.registers.a,
+            self.registers.x,
+            self.registers.y,
+            self.registers.p,
+            self.registers.sp,
+            self.cycles
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::buffer::Buffer, memory::Memory, Cpu6502, NoneDebugger};
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x01, // $8000 LDX #$01
+            0xBD, 0x00, 0x02, // $8002 LDA $0200,X
+            0x85, 0x10, // $8005 STA $10
+            0xB5, 0x0F, // $8007 LDA $0F,X
+            0xD0, 0x00, // $8009 BNE $800B
+            0x00, // $800B BRK
+        ]);
+        cpu.memory.mem[0x0201] = 0x05;
+        cpu.registers.sp = 0xFF;
+        cpu
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut cpu = setup();
+        let buffer = Buffer::default();
+        cpu.tracer = Some(Tracer::new(buffer.clone()));
+        cpu.execute();
+
+        assert_eq!(
+            buffer.lines(),
+            [
+                "8000  A2 01     LDX #$01                        A:00 X:00 Y:00 P:00 SP:FF CYC:0",
+                "8002  BD 00 02  LDA $0200,X @ 0201 = 05         A:00 X:01 Y:00 P:00 SP:FF CYC:2",
+                "8005  85 10     STA $10 = 00                    A:05 X:01 Y:00 P:00 SP:FF CYC:6",
+                "8007  B5 0F     LDA $0F,X @ 10 = 05             A:05 X:01 Y:00 P:00 SP:FF CYC:9",
+                "8009  D0 00     BNE $800B                       A:05 X:01 Y:00 P:00 SP:FF CYC:13",
+                "800B  00        BRK                             A:05 X:01 Y:00 P:00 SP:FF CYC:16",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut cpu = setup();
+        cpu.registers.x = 0x05;
+        cpu.registers.y = 0x10;
+        cpu.load(&[
+            0xA1, 0x80, // $8000 LDA ($80,X)
+            0xB1, 0x80, // $8002 LDA ($80),Y
+            0x6C, 0x00, 0x02, // $8004 JMP ($0200)
+            0x20, 0x00, 0x90, // $8007 JSR $9000
+            0x4A, // $800A LSR A
+            0x02, // $800B (undocumented)
+        ]);
+        cpu.memory.mem[0x0080..0x0082].copy_from_slice(&[0x00, 0x03]);
+        cpu.memory.mem[0x0085..0x0087].copy_from_slice(&[0x10, 0x03]);
+        cpu.memory.mem[0x0200..0x0202].copy_from_slice(&[0x34, 0x12]);
+        cpu.memory.mem[0x0310] = 0xAB;
+
+        assert_eq!(cpu.disassemble(0x8000), "LDA ($80,X) @ 85 = 0310 = AB");
+        assert_eq!(cpu.disassemble(0x8002), "LDA ($80),Y = 0300 @ 0310 = AB");
+        assert_eq!(cpu.disassemble(0x8004), "JMP ($0200) = 1234");
+        assert_eq!(cpu.disassemble(0x8007), "JSR $9000");
+        assert_eq!(cpu.disassemble(0x800A), "LSR A");
+
+        cpu.registers.pc = 0x800B;
+        assert!(cpu.trace_line().starts_with("800B  02       *NOP   "));
+    }
+
+    #[test]
+    fn test_triggers() {
+        let mut cpu = setup();
+        let buffer = Buffer::default();
+        cpu.tracer = Some(
+            Tracer::new(buffer.clone())
+                .with_start(Trigger::Pc(0x8002..=0x8002))
+                .with_stop(Trigger::Cycle(9)),
+        );
+        cpu.execute();
+
+        let lines = buffer.lines();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("8002"));
+        assert!(lines[1].starts_with("8005"));
+        assert_eq!(cpu.tracer.as_ref().unwrap().state(), TraceState::Done);
+    }
+}
@@ -0,0 +1,501 @@
+use crate::{
+    cpu::Cpu,
+    memory::MemoryBus,
+    registers::{Register, RegisterState},
+    Debugger,
+};
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+/// A difference between two runs at the diverging instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difference {
+    Register {
+        register: Register,
+        left: u16,
+        right: u16,
+    },
+    Memory {
+        address: u16,
+        left: u8,
+        right: u8,
+    },
+    /// Trace lines with equal registers but different text, e.g. a different operand value.
+    Line,
+    /// One trace ended before the other.
+    Length,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Register {
+                register: register @ Register::PC,
+                left,
+                right,
+            } => write!(f, "{register} 0x{left:04X} != 0x{right:04X}"),
+            Difference::Register {
+                register,
+                left,
+                right,
+            } => write!(f, "{register} 0x{left:02X} != 0x{right:02X}"),
+            Difference::Memory {
+                address,
+                left,
+                right,
+            } => write!(f, "0x{address:04X} 0x{left:02X} != 0x{right:02X}"),
+            Difference::Line => write!(f, "trace line"),
+            Difference::Length => write!(f, "trace length"),
+        }
+    }
+}
+
+/// The first instruction where two runs differ.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// Number of instructions executed before the states differ, the last of them diverged.
+    /// This is also the index of the first differing trace line.
+    pub instruction: u64,
+    pub differences: Vec<Difference>,
+    /// Trace lines of the left run around the divergence, see [`Divergence::index`].
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    /// Index of the diverging line in `left` and `right`.
+    pub index: usize,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let differences = self
+            .differences
+            .iter()
+            .map(|difference| difference.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(
+            f,
+            "First divergence after {} instructions: {differences}",
+            self.instruction
+        )?;
+
+        for (name, lines) in [("left", &self.left), ("right", &self.right)] {
+            writeln!(f, "{name}:")?;
+
+            for (i, line) in lines.iter().enumerate() {
+                let marker = if i == self.index { '>' } else { ' ' };
+                writeln!(f, "{marker} {line}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Trace Diff
+///
+/// Finds the first instruction where two runs differ, either by stepping two [`Cpu`]s in lockstep
+/// or by comparing two trace files written by a [`Tracer`](crate::trace::Tracer).
+///
+/// Cycle counts are not compared, so runs with different timing but the same behavior match.
+/// Up to `context` trace lines before and after the divergence are kept.
+#[derive(Clone, Debug)]
+pub struct TraceDiff {
+    pub context: usize,
+}
+
+impl Default for TraceDiff {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CONTEXT)
+    }
+}
+
+impl TraceDiff {
+    pub const DEFAULT_CONTEXT: usize = 5;
+    /// Differing memory locations reported per divergence.
+    pub const MEMORY_LIMIT: usize = 16;
+
+    pub fn new(context: usize) -> TraceDiff {
+        TraceDiff { context }
+    }
+
+    /// Steps both CPUs until their registers or memory differ, both execute `BRK`,
+    /// or `limit` instructions were executed.
+    ///
+    /// Memory that already differs at the start, e.g. two versions of the program, is not compared.
+    /// After that, only the addresses written by each instruction are compared.
+    pub fn run<T, D, R, U, E, S>(
+        &self,
+        left: &mut Cpu<T, D, R>,
+        right: &mut Cpu<U, E, S>,
+        limit: u64,
+    ) -> Option<Divergence>
+    where
+        T: MemoryBus<Data = u8, Addr = u16> + Default,
+        D: Debugger,
+        R: Debugger,
+        U: MemoryBus<Data = u8, Addr = u16> + Default,
+        E: Debugger,
+        S: Debugger,
+    {
+        let mut before = VecDeque::new();
+
+        let differing = left
+            .memory
+            .slice(..)
+            .iter()
+            .zip(right.memory.slice(..))
+            .map(|(left, right)| left != right)
+            .collect::<Vec<_>>();
+
+        left.step_writes = Some(Vec::new());
+        right.step_writes = Some(Vec::new());
+
+        let mut divergence = None;
+
+        for instruction in 0..limit {
+            let mut differences =
+                register_differences(&left.registers.state(), &right.registers.state());
+
+            let mut written = left
+                .step_writes
+                .iter()
+                .chain(&right.step_writes)
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+            written.sort_unstable();
+            written.dedup();
+
+            differences.extend(
+                written
+                    .into_iter()
+                    .filter(|address| !differing[*address as usize])
+                    .map(|address| {
+                        (
+                            address,
+                            left.memory.peek(address),
+                            right.memory.peek(address),
+                        )
+                    })
+                    .filter(|(_, left, right)| left != right)
+                    .take(Self::MEMORY_LIMIT)
+                    .map(|(address, left, right)| Difference::Memory {
+                        address,
+                        left,
+                        right,
+                    }),
+            );
+
+            self.push_context(&mut before, (left.trace_line(), right.trace_line()));
+
+            if !differences.is_empty() {
+                let mut after = Vec::new();
+
+                // The context ends at the first `BRK`, nothing meaningful is executed after it
+                for _ in 0..self.context {
+                    if left.memory.peek(left.registers.pc) == 0x00
+                        || right.memory.peek(right.registers.pc) == 0x00
+                    {
+                        break;
+                    }
+
+                    left.execute_next();
+                    right.execute_next();
+                    after.push((left.trace_line(), right.trace_line()));
+                }
+
+                divergence = Some(self.divergence(instruction, differences, before, after));
+                break;
+            }
+
+            let left_opcode = left.execute_next();
+            let right_opcode = right.execute_next();
+
+            if left_opcode == 0x00 && right_opcode == 0x00 {
+                break;
+            }
+        }
+
+        left.step_writes = None;
+        right.step_writes = None;
+
+        divergence
+    }
+
+    /// Compares two traces line by line.
+    pub fn compare(
+        &self,
+        left: impl BufRead,
+        right: impl BufRead,
+    ) -> io::Result<Option<Divergence>> {
+        let mut left = left.lines();
+        let mut right = right.lines();
+        let mut before = VecDeque::new();
+
+        for instruction in 0.. {
+            let (left_line, right_line) =
+                match (left.next().transpose()?, right.next().transpose()?) {
+                    (None, None) => return Ok(None),
+                    (left_line, right_line) => (left_line, right_line),
+                };
+
+            let differences = match (&left_line, &right_line) {
+                (Some(left_line), Some(right_line)) => line_differences(left_line, right_line),
+                _ => vec![Difference::Length],
+            };
+
+            self.push_context(
+                &mut before,
+                (
+                    left_line.unwrap_or_default(),
+                    right_line.unwrap_or_default(),
+                ),
+            );
+
+            if !differences.is_empty() {
+                let mut after = Vec::new();
+
+                for _ in 0..self.context {
+                    let left_line = left.next().transpose()?;
+                    let right_line = right.next().transpose()?;
+
+                    if left_line.is_none() && right_line.is_none() {
+                        break;
+                    }
+
+                    after.push((
+                        left_line.unwrap_or_default(),
+                        right_line.unwrap_or_default(),
+                    ));
+                }
+
+                return Ok(Some(self.divergence(
+                    instruction,
+                    differences,
+                    before,
+                    after,
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn compare_files(
+        &self,
+        left: impl AsRef<Path>,
+        right: impl AsRef<Path>,
+    ) -> io::Result<Option<Divergence>> {
+        self.compare(
+            BufReader::new(File::open(left)?),
+            BufReader::new(File::open(right)?),
+        )
+    }
+
+    fn push_context(&self, before: &mut VecDeque<(String, String)>, lines: (String, String)) {
+        if before.len() > self.context {
+            before.pop_front();
+        }
+
+        before.push_back(lines);
+    }
+
+    fn divergence(
+        &self,
+        instruction: u64,
+        differences: Vec<Difference>,
+        before: VecDeque<(String, String)>,
+        after: Vec<(String, String)>,
+    ) -> Divergence {
+        let index = before.len() - 1;
+        let (left, right) = before.into_iter().chain(after).unzip();
+
+        Divergence {
+            instruction,
+            differences,
+            left,
+            right,
+            index,
+        }
+    }
+}
+
+fn register_differences(left: &RegisterState, right: &RegisterState) -> Vec<Difference> {
+    [
+        (Register::PC, left.pc, right.pc),
+        (Register::A, left.a as u16, right.a as u16),
+        (Register::X, left.x as u16, right.x as u16),
+        (Register::Y, left.y as u16, right.y as u16),
+        (Register::P, left.p as u16, right.p as u16),
+        (Register::SP, left.sp as u16, right.sp as u16),
+    ]
+    .into_iter()
+    .filter(|(_, left, right)| left != right)
+    .map(|(register, left, right)| Difference::Register {
+        register,
+        left,
+        right,
+    })
+    .collect()
+}
+
+/// Parses the registers of a trace line, see [`Tracer`](crate::trace::Tracer) for the format.
+fn parse_registers(line: &str) -> Option<RegisterState> {
+    let field = |name: &str| {
+        line.split_whitespace()
+            .find_map(|token| token.strip_prefix(name))
+            .and_then(|value| u8::from_str_radix(value, 16).ok())
+    };
+
+    Some(RegisterState {
+        a: field("A:")?,
+        x: field("X:")?,
+        y: field("Y:")?,
+        p: field("P:")?,
+        sp: field("SP:")?,
+        pc: u16::from_str_radix(line.get(..4)?, 16).ok()?,
+    })
+}
+
+fn line_differences(left: &str, right: &str) -> Vec<Difference> {
+    let without_cycles = |line: &str| line.split(" CYC:").next().unwrap_or_default().to_string();
+
+    if without_cycles(left) == without_cycles(right) {
+        return Vec::new();
+    }
+
+    let differences = match (parse_registers(left), parse_registers(right)) {
+        (Some(left), Some(right)) => register_differences(&left, &right),
+        _ => Vec::new(),
+    };
+
+    if differences.is_empty() {
+        vec![Difference::Line]
+    } else {
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, NoneDebugger};
+
+    fn setup(value: u8) -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA9, 0x01, // $8000 LDA #$01
+            0x85, 0x10, // $8002 STA $10
+            0x69, value, // $8004 ADC #value
+            0xAA,  // $8006 TAX
+            0xE8,  // $8007 INX
+            0x00,  // $8008 BRK
+        ]);
+        cpu
+    }
+
+    #[test]
+    fn test_run() {
+        let diff = TraceDiff::new(2);
+
+        assert_eq!(diff.run(&mut setup(0x02), &mut setup(0x02), 100), None);
+
+        let divergence = diff.run(&mut setup(0x02), &mut setup(0x03), 100).unwrap();
+
+        assert_eq!(divergence.instruction, 3);
+        assert_eq!(
+            divergence.differences,
+            vec![Difference::Register {
+                register: Register::A,
+                left: 0x03,
+                right: 0x04
+            }]
+        );
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.left.len(), 5);
+        assert!(divergence.left[2].starts_with("8006"));
+        assert!(divergence.right[2].contains("A:04"));
+    }
+
+    #[test]
+    fn test_context_ends_at_brk() {
+        let divergence = TraceDiff::new(10)
+            .run(&mut setup(0x02), &mut setup(0x03), 100)
+            .unwrap();
+
+        assert_eq!(divergence.index, 3);
+        assert_eq!(divergence.left.len(), 6);
+        assert!(divergence.left[5].starts_with("8008"));
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut left = setup(0x02);
+        let mut right = setup(0x02);
+        right.memory.mem[0x8003] = 0x11; // STA $11
+
+        let divergence = TraceDiff::default()
+            .run(&mut left, &mut right, 100)
+            .unwrap();
+
+        assert_eq!(divergence.instruction, 2);
+        assert_eq!(
+            divergence.differences,
+            vec![
+                Difference::Memory {
+                    address: 0x0010,
+                    left: 0x01,
+                    right: 0x00
+                },
+                Difference::Memory {
+                    address: 0x0011,
+                    left: 0x00,
+                    right: 0x01
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let left = "\
+8000  A9 01     LDA #$01                        A:00 X:00 Y:00 P:00 SP:00 CYC:0
+8002  85 10     STA $10 = 00                    A:01 X:00 Y:00 P:00 SP:00 CYC:2
+8004  A5 10     LDA $10 = 01                    A:01 X:00 Y:00 P:00 SP:00 CYC:5
+";
+        let right = "\
+8000  A9 01     LDA #$01                        A:00 X:00 Y:00 P:00 SP:00 CYC:0
+8002  85 10     STA $10 = 00                    A:01 X:00 Y:00 P:00 SP:00 CYC:3
+8004  A5 10     LDA $10 = 02                    A:01 X:00 Y:00 P:00 SP:00 CYC:6
+";
+        let diff = TraceDiff::default();
+
+        assert_eq!(
+            diff.compare(left.as_bytes(), left.as_bytes()).unwrap(),
+            None
+        );
+
+        let divergence = diff
+            .compare(left.as_bytes(), right.as_bytes())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(divergence.instruction, 2);
+        assert_eq!(divergence.differences, vec![Difference::Line]);
+
+        let divergence = diff
+            .compare(left.as_bytes(), &right.as_bytes()[..80])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(divergence.instruction, 1);
+        assert_eq!(divergence.differences, vec![Difference::Length]);
+    }
+}
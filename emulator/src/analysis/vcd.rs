@@ -0,0 +1,255 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    path::Path,
+};
+
+/// A single bus access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BusCycle {
+    pub address: u16,
+    pub data: u8,
+    pub write: bool,
+    /// Opcode fetch.
+    pub sync: bool,
+}
+
+/// # VCD Writer
+///
+/// Writes bus activity as a Value Change Dump, for waveform viewers such as GTKWave.
+///
+/// Signals are `addr[15:0]`, `data[7:0]`, `rw` (high on reads), `sync` (high on opcode fetches),
+/// the active-low `irq_n` and `nmi_n` lines, and a `cs_<name>` signal per chip select,
+/// high while the address bus is in its range.
+///
+/// The emulator is not cycle-accurate on the bus: each access takes one cycle, starting at the first cycle
+/// of its instruction. Dummy accesses are not modeled, so the bus holds its last value during internal cycles.
+///
+/// The header is written on the first access, so chip selects must be added before that.
+pub struct VcdWriter {
+    writer: Box<dyn Write + Send>,
+    /// Chip-select signals by name and address range.
+    pub chip_selects: Vec<(String, RangeInclusive<u16>)>,
+    /// Length of a cycle in nanoseconds.
+    pub period: u64,
+    started: bool,
+    next_cycle: u64,
+    values: Vec<u64>,
+    error: Option<io::Error>,
+}
+
+impl VcdWriter {
+    /// Cycle length of a 1 MHz clock.
+    pub const DEFAULT_PERIOD: u64 = 1_000;
+
+    pub fn new(writer: impl Write + Send + 'static) -> VcdWriter {
+        VcdWriter {
+            writer: Box::new(writer),
+            chip_selects: Vec::new(),
+            period: Self::DEFAULT_PERIOD,
+            started: false,
+            next_cycle: 0,
+            values: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Creates a writer to a new file at `path`.
+    pub fn file(path: impl AsRef<Path>) -> io::Result<VcdWriter> {
+        Ok(VcdWriter::new(BufWriter::new(File::create(path)?)))
+    }
+
+    pub fn with_chip_select(mut self, name: &str, range: RangeInclusive<u16>) -> VcdWriter {
+        self.chip_selects.push((name.to_string(), range));
+        self
+    }
+
+    /// The write error that stopped recording, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Width and name of each signal.
+    fn signals(&self) -> Vec<(u8, String)> {
+        let mut signals = vec![
+            (16, "addr [15:0]".to_string()),
+            (8, "data [7:0]".to_string()),
+            (1, "rw".to_string()),
+            (1, "sync".to_string()),
+            (1, "irq_n".to_string()),
+            (1, "nmi_n".to_string()),
+        ];
+
+        signals.extend(
+            self.chip_selects
+                .iter()
+                .map(|(name, _)| (1, format!("cs_{name}"))),
+        );
+
+        signals
+    }
+
+    /// Identifier of the signal at `index`, from the printable ASCII characters.
+    fn id(index: usize) -> String {
+        let mut index = index;
+        let mut id = String::new();
+
+        loop {
+            id.push((b'!' + (index % 94) as u8) as char);
+            index /= 94;
+
+            if index == 0 {
+                return id;
+            }
+        }
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        writeln!(self.writer, "$version 6502 emulator $end")?;
+        writeln!(self.writer, "$timescale 1 ns $end")?;
+        writeln!(self.writer, "$scope module cpu $end")?;
+
+        for (i, (width, name)) in self.signals().iter().enumerate() {
+            writeln!(self.writer, "$var wire {width} {} {name} $end", Self::id(i))?;
+        }
+
+        writeln!(self.writer, "$upscope $end")?;
+        writeln!(self.writer, "$enddefinitions $end")
+    }
+
+    /// Records a bus access during `cycle`, with the state of the interrupt lines.
+    pub fn record(&mut self, cycle: u64, access: BusCycle, irq: bool, nmi: bool) {
+        if self.error.is_some() {
+            return;
+        }
+
+        if let Err(e) = self.write(cycle, access, irq, nmi) {
+            self.error = Some(e);
+        }
+    }
+
+    fn write(&mut self, cycle: u64, access: BusCycle, irq: bool, nmi: bool) -> io::Result<()> {
+        // Accesses of an instruction take consecutive cycles
+        let cycle = cycle.max(self.next_cycle);
+        self.next_cycle = cycle + 1;
+
+        let mut values = vec![
+            access.address as u64,
+            access.data as u64,
+            !access.write as u64,
+            access.sync as u64,
+            !irq as u64,
+            !nmi as u64,
+        ];
+
+        values.extend(
+            self.chip_selects
+                .iter()
+                .map(|(_, range)| range.contains(&access.address) as u64),
+        );
+
+        let signals = self.signals();
+        let changes = values
+            .iter()
+            .enumerate()
+            .filter(|(i, value)| self.values.get(*i) != Some(value))
+            .map(|(i, value)| match signals[i].0 {
+                1 => format!("{value}{}", Self::id(i)),
+                width => format!("b{value:0width$b} {}", Self::id(i), width = width as usize),
+            })
+            .collect::<Vec<_>>();
+
+        if !self.started {
+            self.header()?;
+            writeln!(self.writer, "#{}", cycle * self.period)?;
+            writeln!(self.writer, "$dumpvars")?;
+
+            for change in changes {
+                writeln!(self.writer, "{change}")?;
+            }
+
+            writeln!(self.writer, "$end")?;
+            self.started = true;
+        } else if !changes.is_empty() {
+            writeln!(self.writer, "#{}", cycle * self.period)?;
+
+            for change in changes {
+                writeln!(self.writer, "{change}")?;
+            }
+        }
+
+        self.values = values;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.error = Some(e);
+        }
+    }
+}
+
+impl Drop for VcdWriter {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::buffer::Buffer, memory::Memory, Cpu6502, NoneDebugger};
+
+    #[test]
+    fn test_vcd() {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA9, 0x05, // $8000 LDA #$05
+            0x85, 0x10, // $8002 STA $10
+            0x00, // $8004 BRK
+        ]);
+
+        let buffer = Buffer::default();
+        cpu.vcd = Some(VcdWriter::new(buffer.clone()).with_chip_select("ram", 0x0000..=0x7FFF));
+        cpu.execute();
+        cpu.vcd = None;
+
+        let lines = buffer.lines();
+
+        assert!(lines.contains(&"$var wire 16 ! addr [15:0] $end".to_string()));
+        assert!(lines.contains(&"$var wire 1 ' cs_ram $end".to_string()));
+        assert_eq!(
+            &lines[lines.iter().position(|line| *line == "#0").unwrap()..][..11],
+            [
+                "#0",
+                "$dumpvars",
+                "b1000000000000000 !",
+                "b10101001 \"",
+                "1#",
+                "1$",
+                "1%",
+                "1&",
+                "0'",
+                "$end",
+                "#1000",
+            ]
+        );
+
+        // STA $10: opcode fetch at cycle 2, operand at 3, write at 4
+        let write = lines.iter().position(|line| *line == "#4000").unwrap();
+        assert_eq!(
+            &lines[write..][..6],
+            [
+                "#4000",
+                "b0000000000010000 !",
+                "b00000101 \"",
+                "0#",
+                "1'",
+                "#5000"
+            ]
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use crate::{
+    cpu::Cpu,
+    expression::Expression,
+    memory::{Access, BankAddress, MemoryBus},
+    DebugKind, Debugger,
+};
+use std::{fmt, ops::RangeInclusive};
+
+pub type BreakpointId = usize;
+
+/// Which memory accesses trigger a watchpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    /// Read or write
+    Access,
+}
+
+impl WatchKind {
+    pub fn matches(&self, access: Access) -> bool {
+        matches!(
+            (self, access),
+            (WatchKind::Read, Access::Read)
+                | (WatchKind::Write, Access::Write)
+                | (WatchKind::Access, Access::Read | Access::Write)
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BreakpointKind {
+    /// Stops before the instruction at the address is executed.
+    Execute(u16),
+    /// Stops after an instruction accesses an address in the range.
+    /// If `value` is set, only accesses of that value trigger the watchpoint.
+    Watch {
+        range: RangeInclusive<u16>,
+        kind: WatchKind,
+        value: Option<u8>,
+    },
+}
+
+/// # Breakpoint
+///
+/// An execution breakpoint or a memory watchpoint.
+///
+/// * `hits` counts every time the breakpoint matched, including ignored ones.
+/// * The first `ignore` hits do not stop execution.
+/// * A `temporary` breakpoint is removed after it stops execution once.
+/// * If `condition` is set, the breakpoint only matches when it evaluates to non-zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    pub id: BreakpointId,
+    pub kind: BreakpointKind,
+    pub condition: Option<Expression>,
+    pub enabled: bool,
+    pub temporary: bool,
+    pub ignore: u64,
+    pub hits: u64,
+}
+
+impl Breakpoint {
+    pub fn execute(pc: u16) -> Breakpoint {
+        Breakpoint::new(BreakpointKind::Execute(pc))
+    }
+
+    pub fn watch(range: RangeInclusive<u16>, kind: WatchKind) -> Breakpoint {
+        Breakpoint::new(BreakpointKind::Watch {
+            range,
+            kind,
+            value: None,
+        })
+    }
+
+    fn new(kind: BreakpointKind) -> Breakpoint {
+        Breakpoint {
+            id: 0,
+            kind,
+            condition: None,
+            enabled: true,
+            temporary: false,
+            ignore: 0,
+            hits: 0,
+        }
+    }
+
+    /// Only trigger the watchpoint when `value` is read or written.
+    pub fn with_value(mut self, value: u8) -> Breakpoint {
+        if let BreakpointKind::Watch { value: v, .. } = &mut self.kind {
+            *v = Some(value);
+        }
+        self
+    }
+
+    /// Only match when `condition` evaluates to non-zero, e.g. `A == $10 && X > 3`.
+    pub fn with_condition(mut self, condition: Expression) -> Breakpoint {
+        self.condition = Some(condition);
+        self
+    }
+
+    pub fn temporary(mut self) -> Breakpoint {
+        self.temporary = true;
+        self
+    }
+
+    /// Do not stop for the first `count` hits.
+    pub fn ignore(mut self, count: u64) -> Breakpoint {
+        self.ignore = count;
+        self
+    }
+
+    fn matches_access(&self, address: u16, data: u8, access: Access) -> bool {
+        match &self.kind {
+            BreakpointKind::Watch { range, kind, value } => {
+                kind.matches(access)
+                    && range.contains(&address)
+                    && value.is_none_or(|value| value == data)
+            }
+            BreakpointKind::Execute(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            BreakpointKind::Execute(pc) => write!(f, "#{} execute 0x{:04X}", self.id, pc)?,
+            BreakpointKind::Watch { range, kind, value } => {
+                let kind = match kind {
+                    WatchKind::Read => "read",
+                    WatchKind::Write => "write",
+                    WatchKind::Access => "access",
+                };
+
+                write!(f, "#{} {kind} 0x{:04X}", self.id, range.start())?;
+                if range.start() != range.end() {
+                    write!(f, "~0x{:04X}", range.end())?;
+                }
+                if let Some(value) = value {
+                    write!(f, " = 0x{value:02X}")?;
+                }
+            }
+        }
+
+        if let Some(condition) = &self.condition {
+            write!(f, " if {condition}")?;
+        }
+
+        if self.temporary {
+            write!(f, " (temporary)")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The memory access that triggered a watchpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WatchAccess {
+    pub address: u16,
+    pub value: u8,
+    pub access: Access,
+}
+
+/// Which breakpoint stopped execution, and where.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BreakpointHit {
+    pub id: BreakpointId,
+    /// For execution breakpoints, the address of the next instruction.
+    /// For watchpoints, the address of the instruction that made the access.
+    pub pc: u16,
+    /// Bank mapped at `pc` before the instruction there was executed, in banked memory.
+    pub bank: Option<usize>,
+    pub access: Option<WatchAccess>,
+}
+
+impl fmt::Display for BreakpointHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pc = BankAddress {
+            bank: self.bank,
+            address: self.pc,
+        };
+
+        match self.access {
+            Some(WatchAccess {
+                address,
+                value,
+                access,
+            }) => write!(
+                f,
+                "Watchpoint #{} ({} 0x{:04X} = 0x{:02X}) at {}",
+                self.id,
+                match access {
+                    Access::Read => "read",
+                    Access::Write => "write",
+                    Access::Execute => "execute",
+                },
+                address,
+                value,
+                pc
+            ),
+            None => write!(f, "Breakpoint #{} at {}", self.id, pc),
+        }
+    }
+}
+
+/// # Breakpoints
+///
+/// Manages the breakpoints and watchpoints of a CPU.
+/// Memory accesses are collected as they happen. After each instruction, the CPU evaluates the conditions
+/// of the matching watchpoints, then of the execution breakpoints at the new program counter.
+#[derive(Default)]
+pub struct Breakpoints {
+    breakpoints: Vec<Breakpoint>,
+    next_id: BreakpointId,
+    accesses: Vec<BreakpointHit>,
+}
+
+impl Breakpoints {
+    /// Adds a breakpoint and returns its id.
+    pub fn add(&mut self, mut breakpoint: Breakpoint) -> BreakpointId {
+        self.next_id += 1;
+        breakpoint.id = self.next_id;
+        self.breakpoints.push(breakpoint);
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: BreakpointId) -> Option<Breakpoint> {
+        let index = self.breakpoints.iter().position(|b| b.id == id)?;
+        Some(self.breakpoints.remove(index))
+    }
+
+    pub fn get(&self, id: BreakpointId) -> Option<&Breakpoint> {
+        self.breakpoints.iter().find(|b| b.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: BreakpointId) -> Option<&mut Breakpoint> {
+        self.breakpoints.iter_mut().find(|b| b.id == id)
+    }
+
+    pub fn set_enabled(&mut self, id: BreakpointId, enabled: bool) -> bool {
+        match self.get_mut(id) {
+            Some(breakpoint) => {
+                breakpoint.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.accesses.clear();
+    }
+
+    /// Collects the watchpoints matching a memory access made by the instruction at `pc`.
+    /// Only the first matching access of each watchpoint is kept per instruction.
+    pub fn check_access(&mut self, pc: u16, address: u16, data: u8, access: Access) {
+        for breakpoint in &self.breakpoints {
+            if !breakpoint.enabled
+                || !breakpoint.matches_access(address, data, access)
+                || self.accesses.iter().any(|hit| hit.id == breakpoint.id)
+            {
+                continue;
+            }
+
+            self.accesses.push(BreakpointHit {
+                id: breakpoint.id,
+                pc,
+                bank: None,
+                access: Some(WatchAccess {
+                    address,
+                    value: data,
+                    access,
+                }),
+            });
+        }
+    }
+
+    /// Takes the watchpoint matches of the last instruction.
+    pub fn take_accesses(&mut self) -> Vec<BreakpointHit> {
+        std::mem::take(&mut self.accesses)
+    }
+
+    /// The enabled execution breakpoints at `pc`.
+    pub fn executes_at(&self, pc: u16) -> Vec<BreakpointHit> {
+        self.breakpoints
+            .iter()
+            .filter(|b| b.enabled && b.kind == BreakpointKind::Execute(pc))
+            .map(|b| BreakpointHit {
+                id: b.id,
+                pc,
+                bank: None,
+                access: None,
+            })
+            .collect()
+    }
+
+    /// Counts a hit, and returns `true` if execution should stop.
+    /// A temporary breakpoint is removed when it stops execution.
+    pub fn hit(&mut self, id: BreakpointId) -> bool {
+        let index = match self.breakpoints.iter().position(|b| b.id == id) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let breakpoint = &mut self.breakpoints[index];
+        breakpoint.hits += 1;
+
+        if breakpoint.hits <= breakpoint.ignore {
+            return false;
+        }
+
+        if breakpoint.temporary {
+            self.breakpoints.remove(index);
+        }
+
+        true
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// Counts the hits of the `candidates` whose condition holds, and returns the first that stops execution.
+    ///
+    /// A condition that fails to evaluate stops execution, so that it can be fixed.
+    pub(crate) fn check_breakpoints(
+        &mut self,
+        candidates: Vec<BreakpointHit>,
+    ) -> Option<BreakpointHit> {
+        let mut stop = None;
+
+        for candidate in candidates {
+            let condition = match self.breakpoints.get(candidate.id) {
+                Some(breakpoint) => match &breakpoint.condition {
+                    Some(condition) => self.evaluate(condition).map(|value| value != 0),
+                    None => Ok(true),
+                },
+                None => continue,
+            };
+
+            let holds = match condition {
+                Ok(holds) => holds,
+                Err(e) => {
+                    self.debugger.debug(
+                        &format!("Breakpoint #{} condition: {e}", candidate.id),
+                        DebugKind::Warn,
+                    );
+                    true
+                }
+            };
+
+            if holds && self.breakpoints.hit(candidate.id) && stop.is_none() {
+                // Watchpoints stop after the instruction, which may have switched banks
+                let bank = match candidate.access {
+                    Some(_) => self.instruction_bank,
+                    None => self.memory.bank(candidate.pc),
+                };
+
+                stop = Some(BreakpointHit { bank, ..candidate });
+            }
+        }
+
+        stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, CpuDebugger, NoneDebugger, StopReason};
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x00, // LDX #$00
+            0xE8, // INX
+            0x8A, // TXA
+            0x9D, 0x00, 0x02, // STA $0200,X
+            0xE0, 0x08, // CPX #$08
+            0xD0, 0xF7, // BNE $8002
+            0x00, // BRK
+        ]);
+        cpu
+    }
+
+    #[test]
+    fn test_execute_breakpoint() {
+        let mut cpu = setup();
+        let id = cpu.breakpoints.add(Breakpoint::execute(0x8004).ignore(2));
+
+        assert_eq!(
+            cpu.run(),
+            StopReason::Breakpoint(BreakpointHit {
+                id,
+                pc: 0x8004,
+                bank: None,
+                access: None
+            })
+        );
+        assert_eq!(cpu.registers.x, 0x03);
+        assert_eq!(cpu.breakpoints.get(id).unwrap().hits, 3);
+
+        // Continuing executes the instruction at the breakpoint
+        assert_eq!(
+            cpu.run(),
+            StopReason::Breakpoint(BreakpointHit {
+                id,
+                pc: 0x8004,
+                bank: None,
+                access: None
+            })
+        );
+        assert_eq!(cpu.registers.x, 0x04);
+
+        cpu.breakpoints.set_enabled(id, false);
+        assert_eq!(cpu.run(), StopReason::Brk);
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        let mut cpu = setup();
+        let condition = Expression::parse("X == 5 && [$0204] == 4").unwrap();
+        let id = cpu
+            .breakpoints
+            .add(Breakpoint::execute(0x8002).with_condition(condition));
+
+        assert!(matches!(cpu.run(), StopReason::Breakpoint(hit) if hit.id == id));
+        assert_eq!(cpu.registers.x, 0x05);
+        // Hits are only counted when the condition holds
+        assert_eq!(cpu.breakpoints.get(id).unwrap().hits, 1);
+        assert_eq!(cpu.run(), StopReason::Brk);
+    }
+
+    #[test]
+    fn test_temporary_breakpoint() {
+        let mut cpu = setup();
+        let id = cpu.breakpoints.add(Breakpoint::execute(0x8007).temporary());
+
+        assert!(matches!(cpu.run(), StopReason::Breakpoint(hit) if hit.id == id));
+        assert!(cpu.breakpoints.get(id).is_none());
+        assert_eq!(cpu.run(), StopReason::Brk);
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut cpu = setup();
+        let id = cpu
+            .breakpoints
+            .add(Breakpoint::watch(0x0200..=0x02FF, WatchKind::Write).with_value(0x05));
+
+        assert_eq!(
+            cpu.run(),
+            StopReason::Breakpoint(BreakpointHit {
+                id,
+                pc: 0x8004,
+                bank: None,
+                access: Some(WatchAccess {
+                    address: 0x0205,
+                    value: 0x05,
+                    access: Access::Write
+                })
+            })
+        );
+        assert_eq!(cpu.registers.pc, 0x8007);
+
+        cpu.breakpoints.remove(id);
+        cpu.breakpoints
+            .add(Breakpoint::watch(0x0000..=0xFFFF, WatchKind::Read));
+
+        // `CPX #$08` fetches its operand with the instruction, it does not read memory
+        assert_eq!(cpu.step(), None);
+        assert_eq!(cpu.registers.pc, 0x8009);
+    }
+}
@@ -0,0 +1,433 @@
+use crate::{cpu::Cpu, memory::MemoryBus, Debugger};
+use std::{collections::VecDeque, fmt};
+
+/// How a frame was entered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameKind {
+    /// `JSR`
+    Subroutine,
+    Irq,
+    Nmi,
+}
+
+impl fmt::Display for FrameKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameKind::Subroutine => write!(f, "JSR"),
+            FrameKind::Irq => write!(f, "IRQ"),
+            FrameKind::Nmi => write!(f, "NMI"),
+        }
+    }
+}
+
+/// A call or interrupt that has not returned yet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    /// Address of the `JSR`, or of the instruction the interrupt returns to.
+    pub caller: u16,
+    /// Entry address of the called routine or handler.
+    pub routine: u16,
+    /// Address execution continues at after the return.
+    pub return_address: u16,
+    /// Stack pointer right after the return address (and status) were pushed.
+    pub sp: u8,
+    /// Index of the instruction that entered the frame (see [`Cpu::instructions`]).
+    pub instruction: u64,
+}
+
+impl Frame {
+    /// Bytes the matching return instruction pops.
+    fn size(&self) -> i8 {
+        match self.kind {
+            FrameKind::Subroutine => 2,
+            FrameKind::Irq | FrameKind::Nmi => 3,
+        }
+    }
+}
+
+/// A mismatch between the shadow call stack and the real stack.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Desync {
+    /// `RTS`/`RTI` returned somewhere other than the return address pushed by the call.
+    ReturnAddressChanged { pc: u16, frame: Frame, actual: u16 },
+    /// `RTS`/`RTI` while no call or interrupt is active.
+    ReturnWithoutCall { pc: u16, opcode: u8 },
+    /// `RTS` returning from an interrupt, or `RTI` returning from a subroutine.
+    WrongReturn { pc: u16, opcode: u8, frame: Frame },
+    /// The return address of a frame was popped by something other than its return instruction
+    /// (e.g. `PLA`, `TXS`), so the frame was dropped.
+    FrameDiscarded { pc: u16, frame: Frame },
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Desync::ReturnAddressChanged { pc, frame, actual } => write!(
+                f,
+                "Return at 0x{pc:04X} went to 0x{actual:04X}, expected 0x{:04X} ({} from 0x{:04X})",
+                frame.return_address, frame.kind, frame.caller
+            ),
+            Desync::ReturnWithoutCall { pc, opcode } => write!(
+                f,
+                "{} at 0x{pc:04X} without a matching call",
+                if *opcode == 0x40 { "RTI" } else { "RTS" }
+            ),
+            Desync::WrongReturn { pc, opcode, frame } => write!(
+                f,
+                "{} at 0x{pc:04X} returned from a {} frame (from 0x{:04X})",
+                if *opcode == 0x40 { "RTI" } else { "RTS" },
+                frame.kind,
+                frame.caller
+            ),
+            Desync::FrameDiscarded { pc, frame } => write!(
+                f,
+                "Instruction at 0x{pc:04X} discarded the {} frame from 0x{:04X}",
+                frame.kind, frame.caller
+            ),
+        }
+    }
+}
+
+/// Frames pushed and popped by one instruction, so that it can be undone.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallStackChange {
+    pub pushed: usize,
+    /// Popped frames, innermost first.
+    pub popped: Vec<Frame>,
+}
+
+impl CallStackChange {
+    pub fn is_empty(&self) -> bool {
+        self.pushed == 0 && self.popped.is_empty()
+    }
+}
+
+/// # Shadow Call Stack
+///
+/// Tracks `JSR`/`RTS` and interrupt entry/`RTI` next to the real stack.
+///
+/// After every instruction, frames whose return address was popped are removed.
+/// When that happens through anything other than the matching return instruction, a [`Desync`] is reported.
+/// Returns that don't consume a frame (e.g. a `PHA`/`PHA`/`RTS` jump table) are not treated as returns.
+#[derive(Clone, Debug, Default)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    desyncs: VecDeque<Desync>,
+}
+
+impl CallStack {
+    /// Number of desyncs kept.
+    pub const DESYNC_LIMIT: usize = 1_000;
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.desyncs.clear();
+    }
+
+    /// Recent desyncs, oldest first.
+    pub fn desyncs(&self) -> impl DoubleEndedIterator<Item = &Desync> {
+        self.desyncs.iter()
+    }
+
+    /// Active frames, outermost first.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn push(&mut self, frame: Frame, change: &mut CallStackChange) {
+        self.frames.push(frame);
+        change.pushed += 1;
+    }
+
+    fn pop(&mut self, change: &mut CallStackChange) -> Option<Frame> {
+        let frame = self.frames.pop()?;
+
+        if change.pushed > 0 {
+            change.pushed -= 1;
+        } else {
+            change.popped.push(frame);
+        }
+
+        Some(frame)
+    }
+
+    /// Updates the frames after the instruction at `pc` was executed.
+    ///
+    /// `sp` and `next_pc` are the stack pointer and program counter after the instruction.
+    pub fn update(
+        &mut self,
+        pc: u16,
+        opcode: u8,
+        sp: u8,
+        next_pc: u16,
+        instruction: u64,
+        change: &mut CallStackChange,
+    ) -> Vec<Desync> {
+        let mut desyncs = Vec::new();
+
+        // Distance the stack shrank past the frame's return address
+        let unwound = |frame: &Frame| sp.wrapping_sub(frame.sp) as i8;
+
+        if let /* RTS, RTI */ 0x60 | 0x40 = opcode {
+            match self.frames.last().copied() {
+                Some(frame) if unwound(&frame) == frame.size() => {
+                    self.pop(change);
+
+                    let returns_from_interrupt = frame.kind != FrameKind::Subroutine;
+                    if returns_from_interrupt != (opcode == 0x40) {
+                        desyncs.push(Desync::WrongReturn { pc, opcode, frame });
+                    } else if next_pc != frame.return_address {
+                        desyncs.push(Desync::ReturnAddressChanged {
+                            pc,
+                            frame,
+                            actual: next_pc,
+                        });
+                    }
+                }
+                None => desyncs.push(Desync::ReturnWithoutCall { pc, opcode }),
+                _ => {}
+            }
+        }
+
+        while let Some(frame) = self.frames.last().copied() {
+            if unwound(&frame) <= 0 {
+                break;
+            }
+
+            self.pop(change);
+            desyncs.push(Desync::FrameDiscarded { pc, frame });
+        }
+
+        if opcode == /* JSR */ 0x20 {
+            self.push(
+                Frame {
+                    kind: FrameKind::Subroutine,
+                    caller: pc,
+                    routine: next_pc,
+                    return_address: pc.wrapping_add(3),
+                    sp,
+                    instruction,
+                },
+                change,
+            );
+        }
+
+        for desync in &desyncs {
+            if self.desyncs.len() >= CallStack::DESYNC_LIMIT {
+                self.desyncs.pop_front();
+            }
+
+            self.desyncs.push_back(*desync);
+        }
+
+        desyncs
+    }
+
+    /// Undoes the changes made by one instruction.
+    pub fn undo(&mut self, change: CallStackChange) {
+        for _ in 0..change.pushed {
+            self.frames.pop();
+        }
+
+        for frame in change.popped.into_iter().rev() {
+            self.frames.push(frame);
+        }
+    }
+}
+
+/// A frame of a [`Cpu::backtrace`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BacktraceFrame {
+    /// The current program counter for the innermost frame, otherwise the address of the calling instruction.
+    pub pc: u16,
+    /// Entry address of the routine containing `pc`, if it was entered through a tracked call.
+    pub routine: Option<u16>,
+    /// `pc` as `label+offset`, if symbols are loaded.
+    pub symbol: Option<String>,
+    /// How the next inner frame was entered from this one.
+    pub call: Option<FrameKind>,
+}
+
+impl fmt::Display for BacktraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:04X}", self.pc)?;
+
+        if let Some(symbol) = &self.symbol {
+            write!(f, " in {symbol}")?;
+        } else if let Some(routine) = self.routine {
+            write!(f, " in 0x{routine:04X}")?;
+        }
+
+        if let Some(call) = self.call {
+            if call != FrameKind::Subroutine {
+                write!(f, " <{call}>")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// The active calls, innermost first.
+    ///
+    /// Empty if the call stack is not tracked.
+    pub fn backtrace(&self) -> Vec<BacktraceFrame> {
+        let frames = match &self.call_stack {
+            Some(call_stack) => call_stack.frames(),
+            None => return Vec::new(),
+        };
+
+        let symbol = |pc: u16| (!self.symbols.is_empty()).then(|| self.symbols.describe(pc));
+
+        let mut backtrace = vec![BacktraceFrame {
+            pc: self.registers.pc,
+            routine: frames.last().map(|frame| frame.routine),
+            symbol: symbol(self.registers.pc),
+            call: None,
+        }];
+
+        for (i, frame) in frames.iter().enumerate().rev() {
+            let routine = i.checked_sub(1).map(|outer| frames[outer].routine);
+
+            backtrace.push(BacktraceFrame {
+                pc: frame.caller,
+                routine,
+                symbol: symbol(frame.caller),
+                call: Some(frame.kind),
+            });
+        }
+
+        backtrace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::{Memory, IRQ_VECTOR},
+        symbols::Symbols,
+        Cpu6502, CpuDebugger, NoneDebugger,
+    };
+
+    fn setup(program: &[u8]) -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(program);
+        cpu.registers.sp = 0xFF;
+        cpu.call_stack = Some(CallStack::default());
+        cpu
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let mut cpu = setup(&[
+            0x20, 0x07, 0x80, // $8000 main: JSR outer
+            0x00, // BRK
+            0x00, 0x00, 0x00, // padding
+            0x20, 0x0B, 0x80, // $8007 outer: JSR inner
+            0x60, // RTS
+            0xEA, // $800B inner: NOP
+            0x60, // RTS
+        ]);
+        cpu.symbols = Symbols::new([("main", 0x8000), ("outer", 0x8007), ("inner", 0x800B)]);
+
+        cpu.step();
+        cpu.step();
+
+        let backtrace = cpu.backtrace();
+        assert_eq!(
+            backtrace
+                .iter()
+                .map(|frame| (frame.pc, frame.routine))
+                .collect::<Vec<_>>(),
+            vec![
+                (0x800B, Some(0x800B)),
+                (0x8007, Some(0x8007)),
+                (0x8000, None)
+            ]
+        );
+        assert_eq!(backtrace[0].to_string(), "0x800B in inner");
+        assert_eq!(backtrace[1].to_string(), "0x8007 in outer");
+
+        cpu.step();
+        cpu.step();
+        assert_eq!(cpu.backtrace().len(), 2);
+        cpu.step();
+        assert_eq!(cpu.backtrace().len(), 1);
+        assert_eq!(cpu.call_stack.as_ref().unwrap().depth(), 0);
+    }
+
+    #[test]
+    fn test_desync() {
+        let mut cpu = setup(&[
+            0x20, 0x04, 0x80, // $8000: JSR $8004
+            0x00, // BRK
+            0x68, // $8004: PLA
+            0x68, // PLA
+            0x60, // RTS
+        ]);
+
+        cpu.step();
+        assert_eq!(cpu.call_stack.as_ref().unwrap().depth(), 1);
+
+        // Popping the return address drops the frame
+        cpu.step();
+        let call_stack = cpu.call_stack.as_ref().unwrap();
+        assert_eq!(call_stack.depth(), 0);
+        assert!(matches!(
+            call_stack.desyncs().last(),
+            Some(Desync::FrameDiscarded { pc: 0x8004, frame }) if frame.caller == 0x8000
+        ));
+
+        cpu.step();
+        cpu.step();
+        assert_eq!(
+            cpu.call_stack.as_ref().unwrap().desyncs().last(),
+            Some(&Desync::ReturnWithoutCall {
+                pc: 0x8006,
+                opcode: 0x60
+            })
+        );
+    }
+
+    #[test]
+    fn test_interrupt_frame() {
+        let mut cpu = setup(&[
+            0x58, // $8000: CLI
+            0xEA, // NOP
+            0x00, // BRK
+            0xE8, // $8003 handler: INX
+            0x40, // RTI
+        ]);
+        cpu.memory.poke(IRQ_VECTOR, 0x03);
+        cpu.memory.poke(IRQ_VECTOR + 1, 0x80);
+
+        cpu.step();
+        cpu.irq();
+        cpu.step();
+
+        assert_eq!(cpu.registers.pc, 0x8003);
+        let backtrace = cpu.backtrace();
+        assert_eq!(backtrace.len(), 2);
+        assert_eq!(backtrace[1].call, Some(FrameKind::Irq));
+        assert_eq!(backtrace[1].pc, 0x8002);
+
+        cpu.step();
+        cpu.step();
+        assert_eq!(cpu.registers.pc, 0x8002);
+        assert_eq!(cpu.registers.x, 0x01);
+        assert_eq!(cpu.backtrace().len(), 1);
+    }
+}
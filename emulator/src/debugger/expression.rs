@@ -0,0 +1,584 @@
+use crate::{
+    cpu::Cpu,
+    memory::MemoryBus,
+    registers::{Flag, Register},
+    Debugger,
+};
+use std::{fmt, iter::Peekable, str::Chars};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpressionError {
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnknownSymbol(String),
+    DivisionByZero,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::UnexpectedCharacter(c) => write!(f, "Unexpected character: {c:?}"),
+            ExpressionError::UnexpectedToken(token) => write!(f, "Unexpected token: {token:?}"),
+            ExpressionError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            ExpressionError::InvalidNumber(number) => write!(f, "Invalid number: {number:?}"),
+            ExpressionError::UnknownSymbol(symbol) => write!(f, "Unknown symbol: {symbol:?}"),
+            ExpressionError::DivisionByZero => write!(f, "Division by zero"),
+        }
+    }
+}
+
+pub type ExpressionResult<T> = Result<T, ExpressionError>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+    Complement,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl BinaryOperator {
+    /// Binding power of the operator, higher binds tighter.
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::BitOr => 3,
+            BinaryOperator::BitXor => 4,
+            BinaryOperator::BitAnd => 5,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 6,
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Remainder => 10,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Number(i64),
+    /// An assembler symbol, register or flag, resolved in that order when evaluated.
+    Identifier(String),
+    /// `[address]` reads a byte, `word[address]` reads a little-endian word.
+    Deref {
+        address: Box<Node>,
+        word: bool,
+    },
+    Unary(UnaryOperator, Box<Node>),
+    Binary(BinaryOperator, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Operator(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{number}"),
+            Token::Ident(ident) => write!(f, "{ident}"),
+            Token::Operator(operator) => write!(f, "{operator}"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+        }
+    }
+}
+
+const OPERATORS: [&str; 21] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "|", "^", "&", "<", ">", "+", "-", "*", "/",
+    "%", "!", "~", "=",
+];
+
+fn tokenize(source: &str) -> ExpressionResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        // `%` is a binary literal where an operand is expected, otherwise the remainder operator
+        let operand_expected = !matches!(
+            tokens.last(),
+            Some(Token::Number(_) | Token::Ident(_) | Token::RParen | Token::RBracket)
+        );
+
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                chars.next();
+            }
+            '(' | ')' | '[' | ']' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    _ => Token::RBracket,
+                });
+            }
+            '$' => {
+                chars.next();
+                tokens.push(Token::Number(number(&mut chars, 16, "$")?));
+            }
+            '%' if operand_expected => {
+                chars.next();
+                tokens.push(Token::Number(number(&mut chars, 2, "%")?));
+            }
+            '0'..='9' => {
+                chars.next();
+
+                if c == '0' && matches!(chars.peek(), Some('x' | 'X')) {
+                    chars.next();
+                    tokens.push(Token::Number(number(&mut chars, 16, "0x")?));
+                } else {
+                    let rest = take_while(&mut chars, |c| c.is_ascii_alphanumeric());
+                    let digits = format!("{c}{rest}");
+
+                    tokens.push(Token::Number(
+                        digits
+                            .parse()
+                            .map_err(|_| ExpressionError::InvalidNumber(digits))?,
+                    ));
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == '.' => {
+                tokens.push(Token::Ident(take_while(&mut chars, |c| {
+                    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+                })));
+            }
+            _ => {
+                let rest = chars.clone().collect::<String>();
+                let operator = OPERATORS
+                    .iter()
+                    .find(|operator| rest.starts_with(**operator))
+                    .ok_or(ExpressionError::UnexpectedCharacter(c))?;
+
+                for _ in 0..operator.len() {
+                    chars.next();
+                }
+
+                // `=` is accepted as `==`
+                tokens.push(Token::Operator(if *operator == "=" {
+                    "=="
+                } else {
+                    operator
+                }));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+    let mut result = String::new();
+
+    while let Some(&c) = chars.peek() {
+        if !predicate(c) {
+            break;
+        }
+
+        result.push(c);
+        chars.next();
+    }
+
+    result
+}
+
+fn number(chars: &mut Peekable<Chars>, radix: u32, prefix: &str) -> ExpressionResult<i64> {
+    let digits = take_while(chars, |c| c.is_ascii_alphanumeric());
+
+    i64::from_str_radix(&digits, radix)
+        .map_err(|_| ExpressionError::InvalidNumber(format!("{prefix}{digits}")))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> ExpressionResult<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(ExpressionError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> ExpressionResult<()> {
+        let token = self.next()?;
+
+        if token != expected {
+            return Err(ExpressionError::UnexpectedToken(token.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn binary_operator(&self) -> Option<BinaryOperator> {
+        let operator = match self.peek()? {
+            Token::Operator(operator) => *operator,
+            _ => return None,
+        };
+
+        Some(match operator {
+            "||" => BinaryOperator::Or,
+            "&&" => BinaryOperator::And,
+            "|" => BinaryOperator::BitOr,
+            "^" => BinaryOperator::BitXor,
+            "&" => BinaryOperator::BitAnd,
+            "==" => BinaryOperator::Equal,
+            "!=" => BinaryOperator::NotEqual,
+            "<" => BinaryOperator::Less,
+            "<=" => BinaryOperator::LessEqual,
+            ">" => BinaryOperator::Greater,
+            ">=" => BinaryOperator::GreaterEqual,
+            "<<" => BinaryOperator::ShiftLeft,
+            ">>" => BinaryOperator::ShiftRight,
+            "+" => BinaryOperator::Add,
+            "-" => BinaryOperator::Subtract,
+            "*" => BinaryOperator::Multiply,
+            "/" => BinaryOperator::Divide,
+            "%" => BinaryOperator::Remainder,
+            _ => return None,
+        })
+    }
+
+    /// Precedence climbing, operators bind tighter than `min_precedence`.
+    fn expression(&mut self, min_precedence: u8) -> ExpressionResult<Node> {
+        let mut left = self.unary()?;
+
+        while let Some(operator) = self.binary_operator() {
+            let precedence = operator.precedence();
+
+            if precedence <= min_precedence {
+                break;
+            }
+
+            self.position += 1;
+            let right = self.expression(precedence)?;
+            left = Node::Binary(operator, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> ExpressionResult<Node> {
+        let operator = match self.peek() {
+            Some(Token::Operator("-")) => UnaryOperator::Negate,
+            Some(Token::Operator("!")) => UnaryOperator::Not,
+            Some(Token::Operator("~")) => UnaryOperator::Complement,
+            _ => return self.primary(),
+        };
+
+        self.position += 1;
+        Ok(Node::Unary(operator, Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> ExpressionResult<Node> {
+        match self.next()? {
+            Token::Number(number) => Ok(Node::Number(number)),
+            Token::LParen => {
+                let node = self.expression(0)?;
+                self.expect(Token::RParen)?;
+                Ok(node)
+            }
+            Token::LBracket => self.deref(false),
+            Token::Ident(ident) => {
+                let size = ident.to_lowercase();
+
+                if (size == "byte" || size == "word") && self.peek() == Some(&Token::LBracket) {
+                    self.position += 1;
+                    return self.deref(size == "word");
+                }
+
+                Ok(Node::Identifier(ident))
+            }
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn deref(&mut self, word: bool) -> ExpressionResult<Node> {
+        let address = self.expression(0)?;
+        self.expect(Token::RBracket)?;
+
+        Ok(Node::Deref {
+            address: Box::new(address),
+            word,
+        })
+    }
+}
+
+/// Registers are matched case-insensitively.
+fn register(name: &str) -> Option<Register> {
+    Some(match name.to_uppercase().as_str() {
+        "A" => Register::A,
+        "X" => Register::X,
+        "Y" => Register::Y,
+        "P" => Register::P,
+        "SP" => Register::SP,
+        "PC" => Register::PC,
+        _ => return None,
+    })
+}
+
+/// Flags are matched case-insensitively.
+fn flag(name: &str) -> Option<Flag> {
+    Some(match name.to_uppercase().as_str() {
+        "N" => Flag::Negative,
+        "V" => Flag::Overflow,
+        "B" => Flag::Break,
+        "D" => Flag::Decimal,
+        "I" => Flag::InterruptDisable,
+        "Z" => Flag::Zero,
+        "C" => Flag::Carry,
+        _ => return None,
+    })
+}
+
+/// # Expression
+///
+/// A debugger expression, evaluated against the live CPU.
+///
+/// * Numbers: `16`, `$10`, `0x10`, `%00010000`
+/// * Registers `A`, `X`, `Y`, `P`, `SP`, `PC` and flags `N`, `V`, `B`, `D`, `I`, `Z`, `C` (`0` or `1`)
+/// * Memory: `[$0200]` reads a byte, `word[$FB]` reads a little-endian word
+/// * Assembler symbols: `loop`, `loop+2`. A symbol named like a register or flag, e.g. a label `C`, takes precedence over it
+/// * Operators, as in C: `+ - * / % << >> & | ^ ~ ! == != < <= > >= && ||`
+///
+/// Comparisons and logical operators evaluate to `0` or `1`.
+/// Memory is read without side effects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    pub source: String,
+    pub root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> ExpressionResult<Expression> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+        };
+
+        let root = parser.expression(0)?;
+
+        if let Some(token) = parser.peek() {
+            return Err(ExpressionError::UnexpectedToken(token.to_string()));
+        }
+
+        Ok(Expression {
+            source: source.trim().to_string(),
+            root,
+        })
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    pub fn evaluate(&self, expression: &Expression) -> ExpressionResult<i64> {
+        self.evaluate_node(&expression.root)
+    }
+
+    /// Parses and evaluates `source`.
+    pub fn evaluate_str(&self, source: &str) -> ExpressionResult<i64> {
+        self.evaluate(&Expression::parse(source)?)
+    }
+
+    fn evaluate_node(&self, node: &Node) -> ExpressionResult<i64> {
+        Ok(match node {
+            Node::Number(number) => *number,
+            Node::Identifier(name) => {
+                if let Some(address) = self.symbols.address(name) {
+                    address as i64
+                } else if let Some(register) = register(name) {
+                    self.registers.get(register) as i64
+                } else if let Some(flag) = flag(name) {
+                    self.registers.get_flag(flag) as i64
+                } else {
+                    return Err(ExpressionError::UnknownSymbol(name.clone()));
+                }
+            }
+            Node::Deref { address, word } => {
+                let address = self.evaluate_node(address)? as u16;
+                let lsb = self.memory.peek(address);
+
+                if *word {
+                    u16::from_le_bytes([lsb, self.memory.peek(address.wrapping_add(1))]) as i64
+                } else {
+                    lsb as i64
+                }
+            }
+            Node::Unary(operator, operand) => {
+                let value = self.evaluate_node(operand)?;
+
+                match operator {
+                    UnaryOperator::Negate => value.wrapping_neg(),
+                    UnaryOperator::Not => (value == 0) as i64,
+                    UnaryOperator::Complement => !value,
+                }
+            }
+            Node::Binary(BinaryOperator::And, left, right) => {
+                (self.evaluate_node(left)? != 0 && self.evaluate_node(right)? != 0) as i64
+            }
+            Node::Binary(BinaryOperator::Or, left, right) => {
+                (self.evaluate_node(left)? != 0 || self.evaluate_node(right)? != 0) as i64
+            }
+            Node::Binary(operator, left, right) => {
+                let left = self.evaluate_node(left)?;
+                let right = self.evaluate_node(right)?;
+
+                match operator {
+                    BinaryOperator::BitOr => left | right,
+                    BinaryOperator::BitXor => left ^ right,
+                    BinaryOperator::BitAnd => left & right,
+                    BinaryOperator::Equal => (left == right) as i64,
+                    BinaryOperator::NotEqual => (left != right) as i64,
+                    BinaryOperator::Less => (left < right) as i64,
+                    BinaryOperator::LessEqual => (left <= right) as i64,
+                    BinaryOperator::Greater => (left > right) as i64,
+                    BinaryOperator::GreaterEqual => (left >= right) as i64,
+                    BinaryOperator::ShiftLeft => left.wrapping_shl(right as u32),
+                    BinaryOperator::ShiftRight => left.wrapping_shr(right as u32),
+                    BinaryOperator::Add => left.wrapping_add(right),
+                    BinaryOperator::Subtract => left.wrapping_sub(right),
+                    BinaryOperator::Multiply => left.wrapping_mul(right),
+                    BinaryOperator::Divide | BinaryOperator::Remainder if right == 0 => {
+                        return Err(ExpressionError::DivisionByZero)
+                    }
+                    BinaryOperator::Divide => left.wrapping_div(right),
+                    BinaryOperator::Remainder => left.wrapping_rem(right),
+                    BinaryOperator::And | BinaryOperator::Or => unreachable!(),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, symbols::Symbols, Cpu6502, NoneDebugger};
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.registers.a = 0x10;
+        cpu.registers.x = 0x05;
+        cpu.registers.set_flag_carry(true);
+        cpu.memory.poke(0x0200, 0x42);
+        cpu.memory.poke(0x00FB, 0x34);
+        cpu.memory.poke(0x00FC, 0xC0);
+        cpu.symbols = Symbols::new([("loop", 0x8004), ("data", 0x0200)]);
+        cpu
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let cpu = setup();
+
+        assert_eq!(cpu.evaluate_str("A == $10 && X > 3"), Ok(1));
+        assert_eq!(cpu.evaluate_str("[$0200] != 0"), Ok(1));
+        assert_eq!(cpu.evaluate_str("word[$FB] >= $C000"), Ok(1));
+        assert_eq!(cpu.evaluate_str("word[$FB]"), Ok(0xC034));
+        assert_eq!(cpu.evaluate_str("loop+2"), Ok(0x8006));
+        assert_eq!(cpu.evaluate_str("[data] - 2"), Ok(0x40));
+        assert_eq!(cpu.evaluate_str("c && !z"), Ok(1));
+        assert_eq!(cpu.evaluate_str("1 + 2 * 3 == 7"), Ok(1));
+        assert_eq!(cpu.evaluate_str("(1 + 2) * 3"), Ok(9));
+        assert_eq!(cpu.evaluate_str("%1010 % 4"), Ok(2));
+        assert_eq!(cpu.evaluate_str("0x10 << 1 | 1"), Ok(0x21));
+        assert_eq!(cpu.evaluate_str("PC = $8000"), Ok(1));
+    }
+
+    #[test]
+    fn test_symbol_named_like_register() {
+        let mut cpu = setup();
+        cpu.symbols = Symbols::new([("C", 0x9000), ("x", 0x0300)]);
+
+        assert_eq!(cpu.evaluate_str("C"), Ok(0x9000));
+        assert_eq!(cpu.evaluate_str("x"), Ok(0x0300));
+        assert_eq!(cpu.evaluate_str("X"), Ok(0x05));
+        assert_eq!(cpu.evaluate_str("c"), Ok(1));
+    }
+
+    #[test]
+    fn test_errors() {
+        let cpu = setup();
+
+        assert_eq!(
+            cpu.evaluate_str("missing + 1"),
+            Err(ExpressionError::UnknownSymbol("missing".to_string()))
+        );
+        assert_eq!(
+            cpu.evaluate_str("A / (X - 5)"),
+            Err(ExpressionError::DivisionByZero)
+        );
+        assert_eq!(
+            Expression::parse("[$10"),
+            Err(ExpressionError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Expression::parse("A == 1 )"),
+            Err(ExpressionError::UnexpectedToken(")".to_string()))
+        );
+        assert_eq!(
+            Expression::parse("$XYZ"),
+            Err(ExpressionError::InvalidNumber("$XYZ".to_string()))
+        );
+        assert_eq!(
+            Expression::parse("A # 1"),
+            Err(ExpressionError::UnexpectedCharacter('#'))
+        );
+    }
+}
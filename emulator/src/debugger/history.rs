@@ -0,0 +1,538 @@
+use crate::{
+    breakpoint::Breakpoints,
+    call_stack::{CallStack, CallStackChange},
+    clobber::ClobberChecker,
+    coverage::Coverage,
+    cpu::Cpu,
+    heatmap::Heatmap,
+    memory::MemoryBus,
+    profiler::Profiler,
+    provenance::Provenance,
+    registers::RegisterState,
+    sanitizer::Sanitizer,
+    self_modifying::SelfModifyingCode,
+    stack_sanitizer::StackSanitizer,
+    trace::Tracer,
+    vcd::VcdWriter,
+    write_log::WriteLog,
+    Debugger,
+};
+use std::collections::VecDeque;
+
+/// The changes made by a single instruction.
+///
+/// `registers`, `cycles`, the pending interrupts and the device state hold the values *before* the instruction was executed,
+/// the device state only if a device was written,
+/// `writes` holds `(address, old value)` pairs in the order they were written,
+/// including inputs applied right after the instruction, and `call_stack` the frames pushed and popped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Delta {
+    pub registers: RegisterState,
+    pub cycles: u64,
+    pub irq_pending: bool,
+    pub nmi_pending: bool,
+    /// See [`MemoryBus::take_devices_before`].
+    pub devices: Option<Vec<Vec<u8>>>,
+    pub writes: Vec<(u16, u8)>,
+    pub call_stack: CallStackChange,
+}
+
+/// A full copy of the machine state, taken before the instruction `instruction` is executed.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub instruction: u64,
+    pub registers: RegisterState,
+    pub cycles: u64,
+    pub irq_pending: bool,
+    pub nmi_pending: bool,
+    pub memory: Vec<u8>,
+    pub devices: Vec<Vec<u8>>,
+    pub call_stack: Option<CallStack>,
+}
+
+/// # Execution History
+///
+/// Records the changes made by every executed instruction, so that the CPU can be stepped backwards.
+///
+/// * The last `capacity` instructions are kept as [`Delta`]s in a ring buffer. Undoing one of them is cheap.
+/// * Every `checkpoint_interval` instructions, a full [`Checkpoint`] is taken.
+///   States older than the ring buffer are restored from the nearest checkpoint, then re-executed forward.
+pub struct History {
+    capacity: usize,
+    checkpoint_interval: u64,
+    max_checkpoints: usize,
+    deltas: VecDeque<Delta>,
+    checkpoints: VecDeque<Checkpoint>,
+    current: Option<Delta>,
+}
+
+impl History {
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+    pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 1_000;
+    pub const DEFAULT_MAX_CHECKPOINTS: usize = 64;
+
+    pub fn new(capacity: usize, checkpoint_interval: u64) -> History {
+        History {
+            capacity,
+            checkpoint_interval: checkpoint_interval.max(1),
+            max_checkpoints: History::DEFAULT_MAX_CHECKPOINTS,
+            deltas: VecDeque::with_capacity(capacity),
+            checkpoints: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    /// Sets the maximum number of checkpoints kept. The oldest checkpoint is dropped first.
+    pub fn with_max_checkpoints(mut self, max_checkpoints: usize) -> History {
+        self.max_checkpoints = max_checkpoints.max(1);
+        self
+    }
+
+    /// Number of instructions that can be undone without re-execution.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    pub fn checkpoints(&self) -> impl Iterator<Item = &Checkpoint> {
+        self.checkpoints.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+        self.checkpoints.clear();
+        self.current = None;
+    }
+
+    pub fn is_checkpoint(&self, instruction: u64) -> bool {
+        instruction.is_multiple_of(self.checkpoint_interval)
+    }
+
+    pub fn push_checkpoint(&mut self, checkpoint: Checkpoint) {
+        if self.checkpoints.len() >= self.max_checkpoints {
+            self.checkpoints.pop_front();
+        }
+
+        self.checkpoints.push_back(checkpoint);
+    }
+
+    /// Starts recording a new instruction.
+    pub fn begin(&mut self, delta: Delta) {
+        self.current = Some(delta);
+    }
+
+    /// Records the old value of `address`, before it is overwritten.
+    pub fn record_write(&mut self, address: u16, old: u8) {
+        if let Some(current) = &mut self.current {
+            current.writes.push((address, old));
+        }
+    }
+
+    /// Records the old value of `address` before an input between instructions overwrites it.
+    /// It is undone with the last executed instruction.
+    pub fn record_input_write(&mut self, address: u16, old: u8) {
+        if let Some(last) = self.deltas.back_mut() {
+            last.writes.push((address, old));
+        }
+    }
+
+    /// Records the device state before an input between instructions wrote a device.
+    /// The state before the last executed instruction is kept if it wrote a device itself.
+    pub fn record_input_devices(&mut self, devices: Vec<Vec<u8>>) {
+        if let Some(last) = self.deltas.back_mut() {
+            last.devices.get_or_insert(devices);
+        }
+    }
+
+    /// Records the device state before the current instruction wrote a device.
+    pub fn record_devices(&mut self, devices: Vec<Vec<u8>>) {
+        if let Some(current) = &mut self.current {
+            current.devices = Some(devices);
+        }
+    }
+
+    /// Records the call stack frames pushed and popped by the current instruction.
+    pub fn record_call_stack(&mut self, change: CallStackChange) {
+        if let Some(current) = &mut self.current {
+            current.call_stack = change;
+        }
+    }
+
+    /// Finishes recording the current instruction.
+    pub fn commit(&mut self) {
+        if let Some(current) = self.current.take() {
+            if self.capacity == 0 {
+                return;
+            }
+
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+
+            self.deltas.push_back(current);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Delta> {
+        self.deltas.pop_back()
+    }
+
+    /// Returns the latest checkpoint taken at or before `instruction`.
+    pub fn checkpoint_before(&self, instruction: u64) -> Option<&Checkpoint> {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.instruction <= instruction)
+    }
+
+    /// Drops all deltas, and every checkpoint taken at or after `instruction`.
+    fn truncate(&mut self, instruction: u64) {
+        self.deltas.clear();
+        self.current = None;
+        self.checkpoints
+            .retain(|checkpoint| checkpoint.instruction < instruction);
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new(
+            History::DEFAULT_CAPACITY,
+            History::DEFAULT_CHECKPOINT_INTERVAL,
+        )
+    }
+}
+
+/// The analyses fed by the hooks, taken out of the CPU while a checkpoint is executed forward,
+/// so replayed instructions are not counted, traced or reported twice.
+struct Analyses {
+    breakpoints: Breakpoints,
+    write_log: Option<WriteLog>,
+    provenance: Option<Provenance>,
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>,
+    heatmap: Option<Heatmap>,
+    self_modifying: Option<SelfModifyingCode>,
+    sanitizer: Option<Sanitizer>,
+    stack_sanitizer: Option<StackSanitizer>,
+    clobber_checker: Option<ClobberChecker>,
+    tracer: Option<Tracer>,
+    vcd: Option<VcdWriter>,
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// Enables the execution history. See [`History`].
+    pub fn enable_history(&mut self, capacity: usize, checkpoint_interval: u64) {
+        self.history = Some(History::new(capacity, checkpoint_interval));
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Undoes the last executed instruction.
+    ///
+    /// Returns `false` if the history is disabled, or there is no earlier state to go back to.
+    pub fn step_back(&mut self) -> bool {
+        if self.instructions == 0 {
+            return false;
+        }
+
+        let restored = self.seek(self.instructions - 1);
+
+        if restored {
+            debug!(self, "Step back to 0x{:04X}", self.registers.pc);
+        }
+
+        restored
+    }
+
+    /// Steps backwards until the program counter is `pc`.
+    ///
+    /// Returns `false` if the history runs out before `pc` is reached.
+    pub fn run_back_to(&mut self, pc: u16) -> bool {
+        loop {
+            if !self.step_back() {
+                return false;
+            }
+
+            if self.registers.pc == pc {
+                return true;
+            }
+        }
+    }
+
+    /// Restores the state before the `instruction`-th instruction was executed.
+    ///
+    /// Only earlier states can be restored.
+    /// Deltas are undone when they are available, otherwise the nearest checkpoint is restored and executed forward.
+    pub fn seek(&mut self, instruction: u64) -> bool {
+        if instruction >= self.instructions {
+            return instruction == self.instructions;
+        }
+
+        let history = match &mut self.history {
+            Some(history) => history,
+            None => return false,
+        };
+
+        if self.instructions - instruction <= history.len() as u64 {
+            while self.instructions > instruction {
+                let delta = match history.pop() {
+                    Some(delta) => delta,
+                    None => return false,
+                };
+
+                // Banks are switched back first, so the old values are poked into the banks they were read from
+                if let Some(devices) = &delta.devices {
+                    self.memory.restore_devices(devices);
+                }
+
+                for (address, old) in delta.writes.into_iter().rev() {
+                    self.memory.poke(address, old);
+                }
+
+                if let Some(call_stack) = &mut self.call_stack {
+                    call_stack.undo(delta.call_stack);
+                }
+
+                self.registers.restore(delta.registers);
+                self.cycles = delta.cycles;
+                self.irq_pending = delta.irq_pending;
+                self.nmi_pending = delta.nmi_pending;
+                self.instructions -= 1;
+            }
+
+            self.rewind_inputs();
+            return true;
+        }
+
+        let checkpoint = match history.checkpoint_before(instruction) {
+            Some(checkpoint) => checkpoint.clone(),
+            None => return false,
+        };
+
+        history.truncate(checkpoint.instruction);
+
+        self.memory.restore(&checkpoint.memory);
+        self.memory.restore_devices(&checkpoint.devices);
+
+        if self.call_stack.is_some() {
+            self.call_stack = Some(checkpoint.call_stack.unwrap_or_default());
+        }
+
+        self.registers.restore(checkpoint.registers);
+        self.cycles = checkpoint.cycles;
+        self.irq_pending = checkpoint.irq_pending;
+        self.nmi_pending = checkpoint.nmi_pending;
+        self.instructions = checkpoint.instruction;
+
+        self.catch_up(instruction);
+        self.rewind_inputs();
+
+        true
+    }
+
+    /// Executes forward to `instruction` after a checkpoint was restored,
+    /// applying the recorded inputs at the instructions they arrived before.
+    ///
+    /// Only the history and the call stack follow the replayed instructions;
+    /// breakpoints and analyses already saw them the first time.
+    fn catch_up(&mut self, instruction: u64) {
+        let analyses = self.take_analyses();
+        let replay = self.replay.take();
+
+        while self.instructions < instruction {
+            self.execute_next();
+
+            if let Some(replay) = &replay {
+                for record in replay.inputs_at(self.instructions) {
+                    self.apply_input(record.event);
+                }
+            }
+        }
+
+        self.replay = replay;
+        self.put_analyses(analyses);
+    }
+
+    /// Drops the logged writes and the recorded inputs after the current instruction,
+    /// or rewinds the replay to it.
+    fn rewind_inputs(&mut self) {
+        if let Some(write_log) = &mut self.write_log {
+            write_log.truncate(self.instructions);
+        }
+
+        if let Some(replay) = &mut self.replay {
+            replay.rewind(self.instructions);
+        }
+    }
+
+    fn take_analyses(&mut self) -> Analyses {
+        Analyses {
+            breakpoints: std::mem::take(&mut self.breakpoints),
+            write_log: self.write_log.take(),
+            provenance: self.provenance.take(),
+            profiler: self.profiler.take(),
+            coverage: self.coverage.take(),
+            heatmap: self.heatmap.take(),
+            self_modifying: self.self_modifying.take(),
+            sanitizer: self.sanitizer.take(),
+            stack_sanitizer: self.stack_sanitizer.take(),
+            clobber_checker: self.clobber_checker.take(),
+            tracer: self.tracer.take(),
+            vcd: self.vcd.take(),
+        }
+    }
+
+    fn put_analyses(&mut self, analyses: Analyses) {
+        self.breakpoints = analyses.breakpoints;
+        self.write_log = analyses.write_log;
+        self.provenance = analyses.provenance;
+        self.profiler = analyses.profiler;
+        self.coverage = analyses.coverage;
+        self.heatmap = analyses.heatmap;
+        self.self_modifying = analyses.self_modifying;
+        self.sanitizer = analyses.sanitizer;
+        self.stack_sanitizer = analyses.stack_sanitizer;
+        self.clobber_checker = analyses.clobber_checker;
+        self.tracer = analyses.tracer;
+        self.vcd = analyses.vcd;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        memory::{Memory, MemoryBus},
+        profiler::Profiler,
+        replay::{InputEvent, Replay},
+        Cpu6502, CpuDebugger, NoneDebugger,
+    };
+
+    fn setup(capacity: usize, checkpoint_interval: u64) -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x00, // LDX #$00
+            0xE8, // INX
+            0x8A, // TXA
+            0x9D, 0x00, 0x02, // STA $0200,X
+            0xE0, 0x08, // CPX #$08
+            0xD0, 0xF7, // BNE $8002
+            0x00, // BRK
+        ]);
+        cpu.enable_history(capacity, checkpoint_interval);
+        cpu
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut cpu = setup(100, 10);
+
+        for _ in 0..6 {
+            cpu.step();
+        }
+
+        assert_eq!(cpu.registers.pc, 0x8002);
+        assert_eq!(cpu.memory.peek(0x0201), 0x01);
+
+        assert!(cpu.step_back());
+        assert_eq!(cpu.registers.pc, 0x8009);
+        assert!(cpu.step_back());
+        assert!(cpu.step_back());
+        assert_eq!(cpu.registers.pc, 0x8004);
+        assert_eq!(cpu.memory.peek(0x0201), 0x00);
+        assert_eq!(cpu.registers.a, 0x01);
+    }
+
+    #[test]
+    fn test_run_back_to() {
+        let mut cpu = setup(100, 10);
+        cpu.execute();
+
+        assert_eq!(cpu.memory.peek(0x0208), 0x08);
+
+        assert!(cpu.run_back_to(0x8004));
+        assert_eq!(cpu.registers.x, 0x08);
+        assert_eq!(cpu.memory.peek(0x0208), 0x00);
+
+        assert!(!cpu.run_back_to(0x1234));
+        assert_eq!(cpu.instructions, 0);
+        assert_eq!(cpu.registers.pc, 0x8000);
+    }
+
+    #[test]
+    fn test_step_back_from_checkpoint() {
+        // Only 2 deltas are kept, older states are restored from checkpoints
+        let mut cpu = setup(2, 4);
+        cpu.execute();
+
+        let instructions = cpu.instructions;
+
+        assert!(cpu.seek(5));
+        assert_eq!(cpu.instructions, 5);
+        assert_eq!(cpu.registers.pc, 0x8009);
+        assert_eq!(cpu.registers.x, 0x01);
+        assert_eq!(cpu.memory.peek(0x0201), 0x01);
+        assert_eq!(cpu.memory.peek(0x0202), 0x00);
+
+        while cpu.instructions < instructions {
+            cpu.step();
+        }
+
+        assert_eq!(cpu.memory.peek(0x0208), 0x08);
+    }
+
+    #[test]
+    fn test_seek_skips_analyses() {
+        let mut cpu = setup(2, 4);
+        cpu.profiler = Some(Profiler::default());
+        cpu.execute();
+
+        let instructions = cpu.profiler.as_ref().unwrap().instructions();
+
+        assert!(cpu.seek(5));
+        assert_eq!(cpu.profiler.as_ref().unwrap().instructions(), instructions);
+    }
+
+    #[test]
+    fn test_seek_replays_inputs() {
+        let mut cpu = setup(2, 4);
+        cpu.replay = Some(Replay::recording());
+        cpu.registers.set_flag_interrupt_disable(true);
+
+        for _ in 0..6 {
+            cpu.step();
+        }
+
+        cpu.input(InputEvent::Poke {
+            address: 0x0300,
+            data: 0x42,
+        });
+        cpu.input(InputEvent::Irq);
+
+        for _ in 0..4 {
+            cpu.step();
+        }
+
+        // Restored from the checkpoint at 4, the inputs at 6 are applied again
+        assert!(cpu.seek(7));
+        assert_eq!(cpu.memory.peek(0x0300), 0x42);
+        assert!(cpu.irq_pending);
+
+        // Undone with the instruction before them
+        assert!(cpu.seek(5));
+        assert_eq!(cpu.memory.peek(0x0300), 0x00);
+        assert!(!cpu.irq_pending);
+        assert!(cpu.replay.as_ref().unwrap().records().is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{cpu::Cpu, memory::Memory, DebugKind, Debugger};
+use core::marker::PhantomData;
+use log::Level;
+
+/// Where debug messages of a [`LogDebugger`] come from, which sets their log target.
+pub trait LogTarget: Default {
+    const TARGET: &'static str;
+}
+
+/// Instructions, stack operations and interrupts, logged to `emulator::cpu`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuTarget;
+
+/// Memory reads and writes, logged to `emulator::memory`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryTarget;
+
+/// Flag changes, logged to `emulator::registers`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RegistersTarget;
+
+impl LogTarget for CpuTarget {
+    const TARGET: &'static str = "emulator::cpu";
+}
+
+impl LogTarget for MemoryTarget {
+    const TARGET: &'static str = "emulator::memory";
+}
+
+impl LogTarget for RegistersTarget {
+    const TARGET: &'static str = "emulator::registers";
+}
+
+/// # Log Debugger
+///
+/// Forwards debug messages to the [`log`] facade, so emulator output can be filtered and routed
+/// like any other log. For `tracing` subscribers, forward `log` records with `tracing-log`.
+///
+/// [`DebugKind::Info`] messages are logged at [`Level::Trace`], since there are several for every instruction.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogDebugger<T: LogTarget> {
+    target: PhantomData<T>,
+}
+
+impl From<DebugKind> for Level {
+    fn from(kind: DebugKind) -> Level {
+        match kind {
+            DebugKind::Info => Level::Trace,
+            DebugKind::Warn => Level::Warn,
+            DebugKind::Error => Level::Error,
+        }
+    }
+}
+
+impl<T: LogTarget> Debugger for LogDebugger<T> {
+    fn debug(&mut self, message: &str, kind: DebugKind) {
+        log::log!(target: T::TARGET, Level::from(kind), "{message}");
+    }
+}
+
+/// CPU logging its messages, with a target per component.
+pub type LogCpu =
+    Cpu<Memory<LogDebugger<MemoryTarget>>, LogDebugger<CpuTarget>, LogDebugger<RegistersTarget>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Log, Metadata, Record};
+    use std::sync::Mutex;
+
+    struct TestLogger(Mutex<Vec<(String, Level, String)>>);
+
+    impl Log for TestLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.0.lock().unwrap().push((
+                record.target().to_string(),
+                record.level(),
+                record.args().to_string(),
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+
+    #[test]
+    fn test_log() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut cpu = LogCpu::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA9, 0x05, // $8000 LDA #$05
+            0x85, 0x10, // $8002 STA $10
+            0x02, // $8004 unknown opcode
+            0x00, // $8005 BRK
+        ]);
+        cpu.execute();
+
+        let records = LOGGER.0.lock().unwrap();
+        let logged = |target: &str, level, message: &str| {
+            records
+                .iter()
+                .any(|record| *record == (target.to_string(), level, message.to_string()))
+        };
+
+        assert!(logged(
+            "emulator::cpu",
+            Level::Trace,
+            "Execute 0xA9 at 0x8000"
+        ));
+        assert!(logged("emulator::cpu", Level::Warn, "Unknown opcode: 0x02"));
+        assert!(logged(
+            "emulator::memory",
+            Level::Trace,
+            "Write 0x0010 = 0x05"
+        ));
+        assert!(logged(
+            "emulator::registers",
+            Level::Trace,
+            "Set flag zero: 0 -> 0"
+        ));
+    }
+}
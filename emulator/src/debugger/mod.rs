@@ -0,0 +1,92 @@
+#[cfg(feature = "std")]
+use crate::{sanitizer::Violation, stack_sanitizer::StackIssue};
+use core::fmt;
+
+#[cfg(feature = "std")]
+pub mod breakpoint;
+#[cfg(feature = "std")]
+pub mod call_stack;
+#[cfg(feature = "std")]
+pub mod expression;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "log")]
+pub mod logger;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod symbols;
+#[cfg(feature = "std")]
+pub mod write_log;
+
+#[cfg(feature = "std")]
+pub use breakpoint::*;
+#[cfg(feature = "std")]
+pub use call_stack::*;
+#[cfg(feature = "std")]
+pub use expression::*;
+#[cfg(feature = "std")]
+pub use history::*;
+#[cfg(feature = "log")]
+pub use logger::*;
+#[cfg(feature = "std")]
+pub use replay::*;
+#[cfg(feature = "std")]
+pub use symbols::*;
+#[cfg(feature = "std")]
+pub use write_log::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DebugKind {
+    Info,
+    Warn,
+    Error,
+}
+
+pub trait Debugger: Default {
+    fn debug(&mut self, message: &str, kind: DebugKind);
+}
+
+/// Why execution stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    /// A `BRK` instruction was executed.
+    Brk,
+    #[cfg(feature = "std")]
+    Breakpoint(BreakpointHit),
+    /// The [`Sanitizer`](crate::sanitizer::Sanitizer) flagged a memory access.
+    #[cfg(feature = "std")]
+    Sanitizer(Violation),
+    /// The [`StackSanitizer`](crate::stack_sanitizer::StackSanitizer) flagged a stack misuse.
+    #[cfg(feature = "std")]
+    StackSanitizer(StackIssue),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Brk => write!(f, "Program finished"),
+            #[cfg(feature = "std")]
+            StopReason::Breakpoint(hit) => write!(f, "{hit}"),
+            #[cfg(feature = "std")]
+            StopReason::Sanitizer(violation) => write!(f, "Sanitizer: {violation}"),
+            #[cfg(feature = "std")]
+            StopReason::StackSanitizer(issue) => write!(f, "Stack sanitizer: {issue}"),
+        }
+    }
+}
+
+pub trait CpuDebugger {
+    /// Executes one instruction.
+    /// Returns why execution should stop after it, if it should.
+    fn step(&mut self) -> Option<StopReason>;
+    /// Executes instructions until a `BRK`, a breakpoint or a sanitizer issue stops it.
+    fn run(&mut self) -> StopReason;
+}
+
+#[derive(Default)]
+pub struct NoneDebugger;
+
+impl Debugger for NoneDebugger {
+    fn debug(&mut self, _: &str, _: DebugKind) {}
+}
@@ -0,0 +1,416 @@
+use crate::{cpu::Cpu, memory::MemoryBus, DebugKind, Debugger};
+use std::{fmt, fs, io, path::Path};
+
+/// An input from outside the CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    /// A device storing a byte in RAM, e.g. the key input at `0x00FF`.
+    ///
+    /// The byte is stored without side effects like [`MemoryBus::poke`], so devices mapped at the address
+    /// are not triggered. Use [`InputEvent::Write`] for those.
+    Poke {
+        address: u16,
+        data: u8,
+    },
+    /// A write on the memory bus, like a store by the CPU, e.g. to a device register.
+    Write {
+        address: u16,
+        data: u8,
+    },
+    Irq,
+    Nmi,
+}
+
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputEvent::Poke { address, data } => write!(f, "poke {address:04X} {data:02X}"),
+            InputEvent::Write { address, data } => write!(f, "write {address:04X} {data:02X}"),
+            InputEvent::Irq => write!(f, "irq"),
+            InputEvent::Nmi => write!(f, "nmi"),
+        }
+    }
+}
+
+/// An input and when it happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputRecord {
+    /// Number of instructions executed before the input (see [`Cpu::instructions`]).
+    pub instruction: u64,
+    /// Cycle count at the input, used to check that a replay did not diverge.
+    pub cycle: u64,
+    pub event: InputEvent,
+}
+
+impl fmt::Display for InputRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.instruction, self.cycle, self.event)
+    }
+}
+
+/// # Replay
+///
+/// Records external inputs with the instruction they arrived before, or replays recorded inputs
+/// at exactly the same instructions, so runs depending on input timing are reproducible.
+///
+/// Instruction counts start at the last reset, so record and replay from a reset with the same program loaded.
+/// While replaying, inputs given through [`Cpu::input`] are ignored.
+///
+/// Replays are saved as text, one input per line: `instruction cycle event`,
+/// where the event is `poke <address> <data>`, `write <address> <data>` (hex), `irq` or `nmi`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    records: Vec<InputRecord>,
+    replaying: bool,
+    next: usize,
+}
+
+impl Replay {
+    pub fn recording() -> Replay {
+        Replay::default()
+    }
+
+    pub fn replaying(records: Vec<InputRecord>) -> Replay {
+        Replay {
+            records,
+            replaying: true,
+            next: 0,
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replaying
+    }
+
+    /// Whether every recorded input was replayed.
+    pub fn is_finished(&self) -> bool {
+        self.replaying && self.next >= self.records.len()
+    }
+
+    pub fn records(&self) -> &[InputRecord] {
+        &self.records
+    }
+
+    /// Clears the recording, or rewinds the replay to the start.
+    pub fn clear(&mut self) {
+        if self.replaying {
+            self.next = 0;
+        } else {
+            self.records.clear();
+        }
+    }
+
+    pub fn record(&mut self, record: InputRecord) {
+        if !self.replaying {
+            self.records.push(record);
+        }
+    }
+
+    /// The inputs applied before the instruction with index `instruction`.
+    pub fn inputs_at(&self, instruction: u64) -> impl Iterator<Item = &InputRecord> {
+        self.records
+            .iter()
+            .filter(move |record| record.instruction == instruction)
+    }
+
+    /// Goes back to just after the inputs of the `instruction`-th instruction were applied,
+    /// after the CPU went back in its history.
+    ///
+    /// A replay continues with the following inputs. A recording drops the later inputs,
+    /// since execution continues from here without them.
+    pub fn rewind(&mut self, instruction: u64) {
+        let next = self
+            .records
+            .partition_point(|record| record.instruction <= instruction);
+
+        if self.replaying {
+            self.next = next;
+        } else {
+            self.records.truncate(next);
+        }
+    }
+
+    /// Takes the inputs to replay before the instruction with index `instruction`.
+    pub fn take_due(&mut self, instruction: u64) -> Vec<InputRecord> {
+        if !self.replaying {
+            return Vec::new();
+        }
+
+        let due = self.records[self.next..]
+            .iter()
+            .take_while(|record| record.instruction <= instruction)
+            .copied()
+            .collect::<Vec<_>>();
+
+        self.next += due.len();
+        due
+    }
+
+    /// Parses a saved replay, see [`Replay`] for the format. Empty lines and `#` comments are skipped.
+    ///
+    /// Inputs must be in the order they happened, with instructions and cycles never decreasing.
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut records = Vec::<InputRecord>::new();
+
+        for (line_number, line) in text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        {
+            let record = Self::parse_record(line)
+                .ok_or_else(|| format!("Invalid input at line {line_number}: {line}"))?;
+
+            if records.last().is_some_and(|last| {
+                record.instruction < last.instruction || record.cycle < last.cycle
+            }) {
+                return Err(format!("Input out of order at line {line_number}: {line}"));
+            }
+
+            records.push(record);
+        }
+
+        Ok(Replay::replaying(records))
+    }
+
+    fn parse_record(line: &str) -> Option<InputRecord> {
+        let mut fields = line.split_whitespace();
+
+        let instruction = fields.next()?.parse().ok()?;
+        let cycle = fields.next()?.parse().ok()?;
+        let event = match fields.next()? {
+            "poke" => InputEvent::Poke {
+                address: u16::from_str_radix(fields.next()?, 16).ok()?,
+                data: u8::from_str_radix(fields.next()?, 16).ok()?,
+            },
+            "write" => InputEvent::Write {
+                address: u16::from_str_radix(fields.next()?, 16).ok()?,
+                data: u8::from_str_radix(fields.next()?, 16).ok()?,
+            },
+            "irq" => InputEvent::Irq,
+            "nmi" => InputEvent::Nmi,
+            _ => return None,
+        };
+
+        fields.next().is_none().then_some(InputRecord {
+            instruction,
+            cycle,
+            event,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Replay> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# instruction cycle event")?;
+
+        for record in &self.records {
+            writeln!(f, "{record}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// Applies an external input before the next instruction, and records it if a [`Replay`] is recording.
+    pub fn input(&mut self, event: InputEvent) {
+        if let Some(replay) = &mut self.replay {
+            if replay.is_replaying() {
+                self.debugger
+                    .debug(&format!("Ignored {event} while replaying"), DebugKind::Warn);
+                return;
+            }
+
+            replay.record(InputRecord {
+                instruction: self.instructions,
+                cycle: self.cycles,
+                event,
+            });
+        }
+
+        self.apply_input(event);
+    }
+
+    /// Applies an input between instructions. Memory it changes is undone with the previous instruction,
+    /// device state with the device snapshot taken before it.
+    ///
+    /// Written memory counts as initialized for the [`Sanitizer`](crate::sanitizer::Sanitizer).
+    pub(crate) fn apply_input(&mut self, event: InputEvent) {
+        if let InputEvent::Poke { address, .. } | InputEvent::Write { address, .. } = event {
+            if let Some(sanitizer) = &mut self.sanitizer {
+                sanitizer.initialize(address..=address);
+            }
+        }
+
+        match event {
+            InputEvent::Poke { address, data } => {
+                if let Some(history) = &mut self.history {
+                    history.record_input_write(address, self.memory.peek(address));
+                }
+
+                self.memory.poke(address, data);
+            }
+            InputEvent::Write { address, data } => {
+                if let Some(history) = &mut self.history {
+                    history.record_input_write(address, self.memory.peek(address));
+                }
+
+                self.memory.write(address, data);
+
+                if let (Some(history), Some(devices)) =
+                    (&mut self.history, self.memory.take_devices_before())
+                {
+                    history.record_input_devices(devices);
+                }
+            }
+            InputEvent::Irq => self.irq(),
+            InputEvent::Nmi => self.nmi(),
+        }
+    }
+
+    /// Applies the replayed inputs due before the next instruction.
+    pub(crate) fn replay_inputs(&mut self) {
+        let due = match &mut self.replay {
+            Some(replay) => replay.take_due(self.instructions),
+            None => return,
+        };
+
+        for record in due {
+            if record.instruction != self.instructions || record.cycle != self.cycles {
+                self.debugger.debug(
+                    &format!(
+                        "Replay diverged: {} expected at instruction {} cycle {}, applied at instruction {} cycle {}",
+                        record.event, record.instruction, record.cycle, self.instructions, self.cycles
+                    ),
+                    DebugKind::Warn,
+                );
+            }
+
+            self.apply_input(record.event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::{Memory, IRQ_VECTOR},
+        Cpu6502, NoneDebugger,
+    };
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA5, 0xFF, // $8000 LDA $FF
+            0xF0, 0xFC, // $8002 BEQ $8000
+            0x85, 0x10, // $8004 STA $10
+            0xE8, // $8006 INX
+            0xD0, 0xFD, // $8007 BNE $8006
+            0x00, // $8009 BRK
+        ]);
+        cpu.memory.mem[0x9000] = 0xC8; // $9000 INY
+        cpu.memory.mem[0x9001] = 0x40; // $9001 RTI
+        cpu.memory.mem[IRQ_VECTOR as usize] = 0x00;
+        cpu.memory.mem[IRQ_VECTOR as usize + 1] = 0x90;
+        cpu.registers.sp = 0xFF;
+        cpu
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut cpu = setup();
+        cpu.replay = Some(Replay::recording());
+
+        for _ in 0..7 {
+            cpu.execute_next();
+        }
+
+        cpu.input(InputEvent::Poke {
+            address: 0x00FF,
+            data: 0x41,
+        });
+
+        for _ in 0..20 {
+            cpu.execute_next();
+        }
+
+        cpu.input(InputEvent::Irq);
+        cpu.execute();
+
+        let saved = cpu.replay.as_ref().unwrap().to_string();
+        let recorded = (cpu.registers.state(), cpu.cycles, cpu.memory.mem);
+
+        assert_eq!(
+            saved,
+            "# instruction cycle event\n7 21 poke 00FF 41\n27 72 irq\n"
+        );
+
+        let mut replayed = setup();
+        replayed.replay = Some(Replay::parse(&saved).unwrap());
+        replayed.input(InputEvent::Nmi);
+        replayed.execute();
+
+        assert!(replayed.replay.as_ref().unwrap().is_finished());
+        assert_eq!(
+            (
+                replayed.registers.state(),
+                replayed.cycles,
+                replayed.memory.mem
+            ),
+            recorded
+        );
+        assert_eq!(replayed.registers.y, 0x01);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Replay::parse("1 2 nmi\n\n# comment\n3 4 poke 0200 0A").unwrap(),
+            Replay::replaying(vec![
+                InputRecord {
+                    instruction: 1,
+                    cycle: 2,
+                    event: InputEvent::Nmi
+                },
+                InputRecord {
+                    instruction: 3,
+                    cycle: 4,
+                    event: InputEvent::Poke {
+                        address: 0x0200,
+                        data: 0x0A
+                    }
+                }
+            ])
+        );
+        assert_eq!(
+            Replay::parse("5 6 write 4000 01").unwrap().records()[0].event,
+            InputEvent::Write {
+                address: 0x4000,
+                data: 0x01
+            }
+        );
+        assert!(Replay::parse("1 2 jump").is_err());
+        assert_eq!(
+            Replay::parse("3 4 irq\n1 2 nmi"),
+            Err("Input out of order at line 2: 1 2 nmi".to_string())
+        );
+        assert!(Replay::parse("3 4 irq\n3 2 nmi").is_err());
+    }
+}
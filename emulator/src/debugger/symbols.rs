@@ -0,0 +1,93 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// # Symbols
+///
+/// Label addresses and source lines of the loaded program (e.g. from the assembler), for the debugger.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Symbols {
+    addresses: HashMap<String, u16>,
+    labels: BTreeMap<u16, String>,
+    lines: BTreeMap<u16, usize>,
+}
+
+impl Symbols {
+    pub fn new<I, S>(symbols: I) -> Symbols
+    where
+        I: IntoIterator<Item = (S, u16)>,
+        S: Into<String>,
+    {
+        let mut result = Symbols::default();
+
+        for (name, address) in symbols {
+            result.insert(name, address);
+        }
+
+        result
+    }
+
+    /// Adds a symbol. If several symbols share an address, the first one names the address.
+    pub fn insert(&mut self, name: impl Into<String>, address: u16) {
+        let name = name.into();
+
+        self.labels.entry(address).or_insert_with(|| name.clone());
+        self.addresses.insert(name, address);
+    }
+
+    /// Maps the instruction at `address` to a source line (1-based).
+    pub fn insert_line(&mut self, address: u16, line: usize) {
+        self.lines.insert(address, line);
+    }
+
+    pub fn clear(&mut self) {
+        self.addresses.clear();
+        self.labels.clear();
+        self.lines.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.addresses.get(name).copied()
+    }
+
+    /// The label at exactly `address`.
+    pub fn label(&self, address: u16) -> Option<&str> {
+        self.labels.get(&address).map(String::as_str)
+    }
+
+    /// The source line of the instruction at exactly `address`.
+    pub fn line(&self, address: u16) -> Option<usize> {
+        self.lines.get(&address).copied()
+    }
+
+    /// The nearest label at or before `address`, and the offset from it. (`loop+3`)
+    pub fn nearest(&self, address: u16) -> Option<(&str, u16)> {
+        self.labels
+            .range(..=address)
+            .next_back()
+            .map(|(label_address, label)| (label.as_str(), address - label_address))
+    }
+
+    /// Formats `address` as `label` or `label+offset`, or `$XXXX` if no label precedes it.
+    pub fn describe(&self, address: u16) -> String {
+        match self.nearest(address) {
+            Some((label, 0)) => label.to_string(),
+            Some((label, offset)) => format!("{label}+{offset}"),
+            None => format!("${address:04X}"),
+        }
+    }
+
+    /// Every labelled address and its label, sorted by address.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        self.labels
+            .iter()
+            .map(|(address, label)| (*address, label.as_str()))
+    }
+
+    /// Every instruction address with a source line, sorted by address.
+    pub fn lines(&self) -> impl Iterator<Item = (u16, usize)> + '_ {
+        self.lines.iter().map(|(address, line)| (*address, *line))
+    }
+}
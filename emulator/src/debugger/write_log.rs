@@ -0,0 +1,266 @@
+use crate::{cpu::Cpu, memory::MemoryBus, Debugger};
+use std::collections::{HashMap, VecDeque};
+
+/// A single write to a memory address.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WriteRecord {
+    /// Cycle count at the start of the writing instruction.
+    pub cycle: u64,
+    /// Index of the writing instruction (see [`Cpu::instructions`]).
+    pub instruction: u64,
+    /// Address of the writing instruction.
+    pub pc: u16,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// # Write Log
+///
+/// An index of memory writes, by address. (`address -> [WriteRecord]`)
+///
+/// Writes are kept for `limit` instructions: writes made before the last `limit` instructions
+/// are dropped whenever a new write is recorded, whatever address they went to.
+pub struct WriteLog {
+    limit: u64,
+    /// Every kept write in the order it was made, with the written address.
+    log: VecDeque<(u16, WriteRecord)>,
+    /// Sequence number of the first write in `log`, counted since the last clear.
+    first: u64,
+    /// Sequence numbers of the writes to each address, oldest first.
+    index: HashMap<u16, VecDeque<u64>>,
+}
+
+impl WriteLog {
+    /// Number of instructions writes are kept for.
+    pub const DEFAULT_LIMIT: u64 = 10_000;
+
+    pub fn new(limit: u64) -> WriteLog {
+        WriteLog {
+            limit: limit.max(1),
+            log: VecDeque::new(),
+            first: 0,
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+        self.first = 0;
+        self.index.clear();
+    }
+
+    pub fn record(&mut self, address: u16, record: WriteRecord) {
+        while let Some(&(address, _)) = self
+            .log
+            .front()
+            .filter(|(_, oldest)| oldest.instruction + self.limit <= record.instruction)
+        {
+            self.log.pop_front();
+            self.unindex(address, |writes| writes.pop_front());
+            self.first += 1;
+        }
+
+        let sequence = self.first + self.log.len() as u64;
+        self.log.push_back((address, record));
+        self.index.entry(address).or_default().push_back(sequence);
+    }
+
+    /// Removes a write from the index of `address`, with `pop` taking it from the address's writes.
+    fn unindex(&mut self, address: u16, pop: impl FnOnce(&mut VecDeque<u64>) -> Option<u64>) {
+        if let Some(writes) = self.index.get_mut(&address) {
+            pop(writes);
+
+            if writes.is_empty() {
+                self.index.remove(&address);
+            }
+        }
+    }
+
+    /// The last write to `address`.
+    pub fn last_write(&self, address: u16) -> Option<&WriteRecord> {
+        self.writes(address).next_back()
+    }
+
+    /// Every recorded write to `address`, oldest first.
+    pub fn writes(&self, address: u16) -> impl DoubleEndedIterator<Item = &WriteRecord> {
+        self.index
+            .get(&address)
+            .into_iter()
+            .flatten()
+            .map(move |sequence| &self.log[(sequence - self.first) as usize].1)
+    }
+
+    /// Writes to `address` made by the `instruction`-th instruction or later, oldest first.
+    pub fn writes_since(
+        &self,
+        address: u16,
+        instruction: u64,
+    ) -> impl DoubleEndedIterator<Item = &WriteRecord> {
+        self.writes(address)
+            .filter(move |record| record.instruction >= instruction)
+    }
+
+    /// Every address written by the instruction at `pc`, in ascending order.
+    pub fn written_by(&self, pc: u16) -> Vec<u16> {
+        let mut addresses = self
+            .log
+            .iter()
+            .filter(|(_, record)| record.pc == pc)
+            .map(|(address, _)| *address)
+            .collect::<Vec<_>>();
+
+        addresses.sort_unstable();
+        addresses.dedup();
+        addresses
+    }
+
+    /// Drops the writes made by the `instruction`-th instruction or later.
+    pub fn truncate(&mut self, instruction: u64) {
+        while let Some(&(address, _)) = self
+            .log
+            .back()
+            .filter(|(_, record)| record.instruction >= instruction)
+        {
+            self.log.pop_back();
+            self.unindex(address, |writes| writes.pop_back());
+        }
+    }
+}
+
+impl Default for WriteLog {
+    fn default() -> WriteLog {
+        WriteLog::new(WriteLog::DEFAULT_LIMIT)
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// Writes to `address` made within the last `instructions` instructions, oldest first.
+    pub fn recent_writes(&self, address: u16, instructions: u64) -> Vec<WriteRecord> {
+        let since = self.instructions.saturating_sub(instructions);
+
+        match &self.write_log {
+            Some(write_log) => write_log.writes_since(address, since).copied().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::Memory, Cpu6502, NoneDebugger};
+
+    fn setup() -> Cpu6502<NoneDebugger> {
+        let mut cpu = Cpu6502::<NoneDebugger>::new(Memory::new());
+        cpu.reset();
+        cpu.load(&[
+            0xA2, 0x00, // LDX #$00
+            0xE8, // INX
+            0x86, 0xFE, // STX $FE
+            0xE0, 0x04, // CPX #$04
+            0xD0, 0xF9, // BNE $8002
+            0x00, // BRK
+        ]);
+        cpu.write_log = Some(WriteLog::default());
+        cpu
+    }
+
+    #[test]
+    fn test_last_write() {
+        let mut cpu = setup();
+        cpu.execute();
+
+        let write_log = cpu.write_log.as_ref().unwrap();
+        let last = write_log.last_write(0x00FE).unwrap();
+
+        assert_eq!(last.pc, 0x8003);
+        assert_eq!(last.old, 0x03);
+        assert_eq!(last.new, 0x04);
+        assert_eq!(last.instruction, 14);
+        assert_eq!(write_log.writes(0x00FE).count(), 4);
+        assert_eq!(write_log.written_by(0x8003), vec![0x00FE]);
+        assert!(write_log.last_write(0x00FF).is_none());
+    }
+
+    #[test]
+    fn test_recent_writes() {
+        let mut cpu = setup();
+        cpu.execute();
+
+        // STX is executed every 4 instructions, BRK is the last one
+        let recent = cpu.recent_writes(0x00FE, 6);
+
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].new, 0x04);
+        assert_eq!(cpu.recent_writes(0x00FE, 10).len(), 2);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut cpu = setup();
+        // STX is executed by the instructions 2, 6, 10 and 14
+        cpu.write_log = Some(WriteLog::new(8));
+        cpu.execute();
+
+        // 6 is the last instruction before the 8 instructions up to 14
+        let write_log = cpu.write_log.as_ref().unwrap();
+        assert_eq!(
+            write_log
+                .writes(0x00FE)
+                .map(|record| record.instruction)
+                .collect::<Vec<_>>(),
+            vec![10, 14]
+        );
+
+        let mut cpu = setup();
+        cpu.write_log = Some(WriteLog::new(9));
+        cpu.execute();
+
+        assert_eq!(cpu.write_log.as_ref().unwrap().writes(0x00FE).count(), 3);
+    }
+
+    #[test]
+    fn test_limit_drops_other_addresses() {
+        let record = |instruction| WriteRecord {
+            cycle: instruction * 3,
+            instruction,
+            pc: 0x8000,
+            old: 0x00,
+            new: 0x01,
+        };
+
+        let mut write_log = WriteLog::new(4);
+        write_log.record(0x0010, record(0));
+        write_log.record(0x0020, record(3));
+        assert!(write_log.last_write(0x0010).is_some());
+
+        // 0x0010 was not written again, its write is dropped all the same
+        write_log.record(0x0020, record(4));
+        assert!(write_log.last_write(0x0010).is_none());
+        assert_eq!(write_log.writes(0x0020).count(), 2);
+        assert_eq!(write_log.written_by(0x8000), vec![0x0020]);
+
+        write_log.truncate(4);
+        assert_eq!(write_log.last_write(0x0020).unwrap().instruction, 3);
+        write_log.record(0x0030, record(9));
+        assert_eq!(write_log.written_by(0x8000), vec![0x0030]);
+    }
+
+    #[test]
+    fn test_step_back_truncates() {
+        let mut cpu = setup();
+        cpu.enable_history(100, 10);
+        cpu.execute();
+
+        assert!(cpu.run_back_to(0x8003));
+
+        let write_log = cpu.write_log.as_ref().unwrap();
+        assert_eq!(write_log.writes(0x00FE).count(), 3);
+        assert_eq!(write_log.last_write(0x00FE).unwrap().new, 0x03);
+    }
+}
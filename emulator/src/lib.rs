@@ -1,6 +1,22 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Sends a formatted debug message through `$target.debug`.
+///
+/// Formatting needs an allocator, so the message is compiled out without the `std` feature.
+macro_rules! debug {
+    ($target:expr, $($arg:tt)*) => {
+        #[cfg(feature = "std")]
+        $target.debug(&format!($($arg)*));
+    };
+}
+
+#[cfg(feature = "std")]
+pub mod analysis;
 pub mod debugger;
 pub mod processor;
 
+#[cfg(feature = "std")]
+pub use analysis::*;
 pub use debugger::*;
 pub use processor::*;
 
@@ -0,0 +1,263 @@
+use crate::{
+    memory::Memory, registers::RegisterState, Cpu6502, CpuDebugger, NoneDebugger, StopReason,
+};
+use std::{
+    any::Any,
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// CPU used by batch jobs. Debug output is discarded, analyses can be enabled with [`Job::with_setup`].
+pub type BatchCpu = Cpu6502<NoneDebugger>;
+
+type Setup = Box<dyn Fn(&mut BatchCpu) + Send>;
+type Extract<T> = Box<dyn Fn(&BatchCpu) -> T + Send>;
+
+/// A program to run in a [`BatchRunner`], and how to read its result.
+pub struct Job<T> {
+    pub name: String,
+    pub program: Vec<u8>,
+    /// Registers to start from instead of the reset state.
+    pub registers: Option<RegisterState>,
+    /// Memory written after loading the program, e.g. test inputs.
+    pub memory: Vec<(u16, Vec<u8>)>,
+    /// Cycles to run before giving up.
+    pub cycle_budget: u64,
+    setup: Option<Setup>,
+    extract: Extract<T>,
+}
+
+impl<T> Job<T> {
+    pub const DEFAULT_CYCLE_BUDGET: u64 = 1_000_000;
+
+    /// Creates a job running `program`, then reading the result with `extract`.
+    pub fn new(
+        name: &str,
+        program: &[u8],
+        extract: impl Fn(&BatchCpu) -> T + Send + 'static,
+    ) -> Job<T> {
+        Job {
+            name: name.to_string(),
+            program: program.to_vec(),
+            registers: None,
+            memory: Vec::new(),
+            cycle_budget: Self::DEFAULT_CYCLE_BUDGET,
+            setup: None,
+            extract: Box::new(extract),
+        }
+    }
+
+    pub fn with_registers(mut self, registers: RegisterState) -> Job<T> {
+        self.registers = Some(registers);
+        self
+    }
+
+    pub fn with_memory(mut self, address: u16, data: &[u8]) -> Job<T> {
+        self.memory.push((address, data.to_vec()));
+        self
+    }
+
+    pub fn with_cycle_budget(mut self, cycle_budget: u64) -> Job<T> {
+        self.cycle_budget = cycle_budget;
+        self
+    }
+
+    /// Runs `setup` on the CPU after the initial state is applied, e.g. to add breakpoints.
+    pub fn with_setup(mut self, setup: impl Fn(&mut BatchCpu) + Send + 'static) -> Job<T> {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    fn run(self) -> JobReport<T> {
+        let start = Instant::now();
+        let mut cpu = BatchCpu::new(Memory::new());
+
+        let run = panic::catch_unwind(AssertUnwindSafe(|| {
+            cpu.reset();
+            cpu.load(&self.program);
+
+            if let Some(registers) = self.registers {
+                cpu.registers.restore(registers);
+            }
+
+            for (address, data) in &self.memory {
+                cpu.load_at(*address, data);
+            }
+
+            if let Some(setup) = &self.setup {
+                setup(&mut cpu);
+            }
+
+            let outcome = loop {
+                if cpu.cycles >= self.cycle_budget {
+                    break Outcome::BudgetExceeded;
+                }
+
+                match cpu.step() {
+                    Some(StopReason::Brk) => break Outcome::Finished,
+                    Some(reason) => break Outcome::Stopped(reason),
+                    None => {}
+                }
+            };
+
+            (outcome, (self.extract)(&cpu))
+        }));
+
+        let (outcome, result) = match run {
+            Ok((outcome, result)) => (outcome, Some(result)),
+            Err(e) => (Outcome::Panicked(panic_message(e)), None),
+        };
+
+        JobReport {
+            name: self.name,
+            outcome,
+            instructions: cpu.instructions,
+            cycles: cpu.cycles,
+            elapsed: start.elapsed(),
+            result,
+        }
+    }
+}
+
+fn panic_message(e: Box<dyn Any + Send>) -> String {
+    match e.downcast::<String>() {
+        Ok(message) => *message,
+        Err(e) => e
+            .downcast_ref::<&str>()
+            .map_or("unknown panic", |message| message)
+            .to_string(),
+    }
+}
+
+/// How a job ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The program reached a `BRK`.
+    Finished,
+    /// A breakpoint or sanitizer stopped the program.
+    Stopped(StopReason),
+    BudgetExceeded,
+    /// The emulator panicked, with the panic message.
+    Panicked(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct JobReport<T> {
+    pub name: String,
+    pub outcome: Outcome,
+    pub instructions: u64,
+    pub cycles: u64,
+    /// Wall-clock time of the job.
+    pub elapsed: Duration,
+    /// Result of the job's extractor, `None` if the job panicked.
+    pub result: Option<T>,
+}
+
+/// # Batch Runner
+///
+/// Runs independent [`Job`]s on `threads` worker threads.
+/// Every job gets its own CPU and memory, so jobs cannot affect each other.
+#[derive(Clone, Debug)]
+pub struct BatchRunner {
+    pub threads: usize,
+}
+
+impl Default for BatchRunner {
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+}
+
+impl BatchRunner {
+    pub fn new(threads: usize) -> BatchRunner {
+        BatchRunner {
+            threads: threads.max(1),
+        }
+    }
+
+    /// Runs all jobs, and returns their reports in the order of `jobs`.
+    pub fn run<T: Send>(&self, jobs: Vec<Job<T>>) -> Vec<JobReport<T>> {
+        let count = jobs.len();
+        let queue = Mutex::new(jobs.into_iter().enumerate());
+        let reports = Mutex::new(Vec::with_capacity(count));
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(count) {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap().next();
+
+                    let (index, job) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+
+                    let report = job.run();
+                    reports.lock().unwrap().push((index, report));
+                });
+            }
+        });
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_by_key(|(index, _)| *index);
+        reports.into_iter().map(|(_, report)| report).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu::NoneDebuggerCpu, memory_map::MemoryMap, NoneDebugger};
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_send() {
+        assert_send::<BatchCpu>();
+        assert_send::<NoneDebuggerCpu<MemoryMap<NoneDebugger>>>();
+        assert_send::<Job<u8>>();
+    }
+
+    #[test]
+    fn test_run() {
+        let add = |n: u8| {
+            Job::new(&format!("add {n}"), &[0xA5, 0x10, 0x69, n, 0x00], |cpu| {
+                cpu.registers.a
+            })
+            .with_memory(0x0010, &[100])
+        };
+
+        let mut jobs = (0..20).map(add).collect::<Vec<_>>();
+        jobs.push(
+            Job::new("loop", &[0x4C, 0x00, 0x80], |cpu| cpu.registers.a).with_cycle_budget(300),
+        );
+        jobs.push(
+            Job::new("registers", &[0xE8, 0x00], |cpu| cpu.registers.x).with_registers(
+                RegisterState {
+                    a: 0,
+                    x: 0x41,
+                    y: 0,
+                    p: 0,
+                    sp: 0xFF,
+                    pc: 0x8000,
+                },
+            ),
+        );
+
+        let reports = BatchRunner::new(4).run(jobs);
+
+        assert_eq!(reports.len(), 22);
+
+        for (n, report) in reports[..20].iter().enumerate() {
+            assert_eq!(report.name, format!("add {n}"));
+            assert_eq!(report.outcome, Outcome::Finished);
+            assert_eq!(report.result, Some(100 + n as u8));
+        }
+
+        assert_eq!(reports[20].outcome, Outcome::BudgetExceeded);
+        assert_eq!(reports[20].cycles, 300);
+        assert_eq!(reports[21].result, Some(0x42));
+    }
+}
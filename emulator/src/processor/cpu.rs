@@ -1,10 +1,32 @@
 use crate::{
     addressing_mode::AddressingMode,
-    memory::{MemoryBus, STACK_BASE},
-    registers::Registers,
-    CpuDebugger, DebugKind, Debugger, NoneDebugger,
+    memory::{BankAddress, MemoryBus, IRQ_VECTOR, NMI_VECTOR, ORG, RESET_VECTOR, STACK_BASE},
+    opcode::base_cycles,
+    registers::{Register, Registers},
+    CpuDebugger, DebugKind, Debugger, NoneDebugger, StopReason,
 };
-use std::fmt;
+#[cfg(feature = "std")]
+use crate::{
+    breakpoint::Breakpoints,
+    call_stack::{CallStack, CallStackChange},
+    clobber::ClobberChecker,
+    coverage::Coverage,
+    heatmap::Heatmap,
+    history::History,
+    profiler::Profiler,
+    provenance::Provenance,
+    replay::Replay,
+    sanitizer::Sanitizer,
+    self_modifying::SelfModifyingCode,
+    stack_sanitizer::StackSanitizer,
+    symbols::Symbols,
+    trace::Tracer,
+    vcd::VcdWriter,
+    write_log::WriteLog,
+};
+use core::fmt;
+
+mod hooks;
 
 #[doc=include_str!("../../../README.md")]
 #[derive(Default)]
@@ -17,6 +39,58 @@ where
     pub memory: T,
     pub debugger: D,
     pub registers: Registers<R>,
+    /// Number of instructions executed since the last reset.
+    pub instructions: u64,
+    /// Number of cycles elapsed since the last reset.
+    pub cycles: u64,
+    #[cfg(feature = "std")]
+    pub breakpoints: Breakpoints,
+    /// Symbols of the loaded program, used by the debugger.
+    #[cfg(feature = "std")]
+    pub symbols: Symbols,
+    #[cfg(feature = "std")]
+    pub history: Option<History>,
+    #[cfg(feature = "std")]
+    pub write_log: Option<WriteLog>,
+    #[cfg(feature = "std")]
+    pub provenance: Option<Provenance>,
+    #[cfg(feature = "std")]
+    pub call_stack: Option<CallStack>,
+    #[cfg(feature = "std")]
+    pub profiler: Option<Profiler>,
+    #[cfg(feature = "std")]
+    pub coverage: Option<Coverage>,
+    #[cfg(feature = "std")]
+    pub heatmap: Option<Heatmap>,
+    #[cfg(feature = "std")]
+    pub self_modifying: Option<SelfModifyingCode>,
+    #[cfg(feature = "std")]
+    pub sanitizer: Option<Sanitizer>,
+    #[cfg(feature = "std")]
+    pub stack_sanitizer: Option<StackSanitizer>,
+    #[cfg(feature = "std")]
+    pub clobber_checker: Option<ClobberChecker>,
+    #[cfg(feature = "std")]
+    pub tracer: Option<Tracer>,
+    #[cfg(feature = "std")]
+    pub vcd: Option<VcdWriter>,
+    /// External inputs being recorded or replayed, see [`Cpu::input`].
+    #[cfg(feature = "std")]
+    pub replay: Option<Replay>,
+    /// Addresses written by the last instruction, collected while it is `Some`.
+    #[cfg(feature = "std")]
+    pub(crate) step_writes: Option<Vec<u16>>,
+    /// Address of the instruction being executed.
+    instruction_pc: u16,
+    /// Bank mapped at `instruction_pc` before the instruction was executed.
+    #[cfg(feature = "std")]
+    pub(crate) instruction_bank: Option<usize>,
+    page_crossed: bool,
+    pub(crate) irq_pending: bool,
+    pub(crate) nmi_pending: bool,
+    /// Frames pushed and popped by the instruction being executed, recorded in the history.
+    #[cfg(feature = "std")]
+    call_stack_change: CallStackChange,
 }
 
 pub type NoneDebuggerCpu<T> = Cpu<T, NoneDebugger, NoneDebugger>;
@@ -43,6 +117,50 @@ where
             registers: Registers::default(),
             memory,
             debugger: D::default(),
+            instructions: 0,
+            cycles: 0,
+            #[cfg(feature = "std")]
+            breakpoints: Breakpoints::default(),
+            #[cfg(feature = "std")]
+            symbols: Symbols::default(),
+            #[cfg(feature = "std")]
+            history: None,
+            #[cfg(feature = "std")]
+            write_log: None,
+            #[cfg(feature = "std")]
+            provenance: None,
+            #[cfg(feature = "std")]
+            call_stack: None,
+            #[cfg(feature = "std")]
+            profiler: None,
+            #[cfg(feature = "std")]
+            coverage: None,
+            #[cfg(feature = "std")]
+            heatmap: None,
+            #[cfg(feature = "std")]
+            self_modifying: None,
+            #[cfg(feature = "std")]
+            sanitizer: None,
+            #[cfg(feature = "std")]
+            stack_sanitizer: None,
+            #[cfg(feature = "std")]
+            clobber_checker: None,
+            #[cfg(feature = "std")]
+            tracer: None,
+            #[cfg(feature = "std")]
+            vcd: None,
+            #[cfg(feature = "std")]
+            replay: None,
+            #[cfg(feature = "std")]
+            step_writes: None,
+            instruction_pc: 0,
+            #[cfg(feature = "std")]
+            instruction_bank: None,
+            page_crossed: false,
+            irq_pending: false,
+            nmi_pending: false,
+            #[cfg(feature = "std")]
+            call_stack_change: CallStackChange::default(),
         }
     }
 
@@ -50,28 +168,81 @@ where
         self.debugger.debug(message, DebugKind::Info);
     }
 
+    /// `address` with the bank currently mapped at it, to tell apart code of different banks.
+    pub fn bank_address(&self, address: u16) -> BankAddress {
+        BankAddress {
+            bank: self.memory.bank(address),
+            address,
+        }
+    }
+
+    /// Resets the registers and memory, then starts at the reset vector,
+    /// or at `ORG` (`0x8000`) while the vector is `0x0000`, e.g. in cleared RAM.
     pub fn reset(&mut self) {
         self.registers.reset();
         self.memory.reset();
+        self.registers.pc = match self.reset_vector() {
+            0x0000 => ORG,
+            vector => vector,
+        };
+        self.instructions = 0;
+        self.cycles = 0;
+
+        self.reset_hooks();
+
+        self.irq_pending = false;
+        self.nmi_pending = false;
+
         self.debug("Reset CPU");
     }
 
+    /// Loads `program` at `ORG` (`0x8000`), see [`Cpu::load_at`] for other addresses.
     pub fn load(&mut self, program: &[T::Data]) {
         self.memory.rom(program);
+        self.on_load(ORG, program.len(), true);
     }
 
-    pub fn execute(&mut self) {
-        loop {
-            let opcode = self.memory.read(self.registers.pc);
+    /// Copies `data` to memory from `address`, wrapping around the end of the address space.
+    /// Writes go through [`MemoryBus::poke`], so ROM can be loaded too.
+    pub fn load_at(&mut self, address: u16, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            self.memory.poke(address.wrapping_add(i as u16), *byte);
+        }
+
+        self.on_load(address, data.len(), false);
+        debug!(self, "Load {} bytes at 0x{:04X}", data.len(), address);
+    }
+
+    /// Points the reset vector (`0xFFFC`) to `address`.
+    /// The program counter is not changed, the CPU starts there after the next [`Cpu::reset`] if the vector survives it, e.g. in ROM.
+    pub fn set_reset_vector(&mut self, address: u16) {
+        self.load_at(RESET_VECTOR, &address.to_le_bytes());
+    }
+
+    /// Address in the reset vector (`0xFFFC`), where [`Cpu::reset`] starts.
+    pub fn reset_vector(&self) -> u16 {
+        u16::from_le_bytes([
+            self.memory.peek(RESET_VECTOR),
+            self.memory.peek(RESET_VECTOR.wrapping_add(1)),
+        ])
+    }
 
-            self.debug(&format!(
-                "Execute 0x{:02X} at 0x{:04X}",
-                opcode, self.registers.pc
-            ));
+    /// Requests an interrupt through the IRQ vector (`0xFFFE`).
+    ///
+    /// The interrupt is taken after the current instruction, once the interrupt disable flag is clear.
+    pub fn irq(&mut self) {
+        self.irq_pending = true;
+    }
 
-            self.execute_instruction(opcode);
+    /// Requests a non-maskable interrupt through the NMI vector (`0xFFFA`), taken after the current instruction.
+    pub fn nmi(&mut self) {
+        self.nmi_pending = true;
+    }
 
-            if opcode == 0x00 {
+    /// Executes instructions until a `BRK`, ignoring breakpoints.
+    pub fn execute(&mut self) {
+        loop {
+            if self.execute_next() == 0x00 {
                 break;
             }
         }
@@ -288,26 +459,106 @@ where
 
             /* BRK */ 0x00 => {}
             /* NOP */
-            _ => self.debugger.debug(
-                &format!("Unknown opcode: 0x{:02X}", opcode),
-                DebugKind::Warn,
-            ),
+            _ => {
+                #[cfg(feature = "std")]
+                self.debugger.debug(
+                    &format!("Unknown opcode: 0x{:02X}", opcode),
+                    DebugKind::Warn,
+                );
+            }
+        }
+    }
+
+    fn read(&mut self, address: T::Addr) -> T::Data {
+        let data = self.memory.read(address);
+        self.on_read(address, data);
+
+        data
+    }
+
+    fn read_addr(&mut self, address: T::Addr) -> T::Addr {
+        let lsb = self.read(address);
+        let msb = self.read(address.wrapping_add(1));
+
+        T::Addr::from_le_bytes([lsb, msb])
+    }
+
+    /// Reads an opcode or operand byte of the instruction being executed.
+    fn fetch(&mut self, address: T::Addr) -> T::Data {
+        let data = self.memory.read(address);
+        self.on_fetch(address, data);
+
+        data
+    }
+
+    fn fetch_addr(&mut self, address: T::Addr) -> T::Addr {
+        let lsb = self.fetch(address);
+        let msb = self.fetch(address.wrapping_add(1));
+
+        T::Addr::from_le_bytes([lsb, msb])
+    }
+
+    fn write(&mut self, address: T::Addr, data: T::Data) {
+        self.on_write(address, data);
+        self.memory.write(address, data);
+    }
+
+    /// Enters the pending interrupt, if any: pushes the program counter and status, and jumps through the vector.
+    fn service_interrupt(&mut self) {
+        let (nmi, vector) = if self.nmi_pending {
+            self.nmi_pending = false;
+            (true, NMI_VECTOR)
+        } else if self.irq_pending && !self.registers.get_flag_interrupt_disable() {
+            self.irq_pending = false;
+            (false, IRQ_VECTOR)
+        } else {
+            return;
+        };
+
+        let caller = self.registers.pc;
+        let sp = self.registers.sp;
+
+        self.stack_push_addr(caller);
+        self.stack_push(self.registers.p & !0b0001_0000);
+        self.registers.set_flag_interrupt_disable(true);
+        self.registers.pc = self.read_addr(vector);
+        self.cycles += 7;
+        self.tick(7);
+
+        debug!(
+            self,
+            "{} at {}, jump to {}",
+            if nmi { "NMI" } else { "IRQ" },
+            self.bank_address(caller),
+            self.bank_address(self.registers.pc)
+        );
+
+        self.on_interrupt(nmi, caller, sp);
+    }
+
+    /// Advances the devices on the bus, and takes their interrupt requests.
+    fn tick(&mut self, cycles: u64) {
+        if self.memory.tick(cycles) {
+            self.irq_pending = true;
         }
     }
 
     fn stack_push(&mut self, data: T::Data) {
-        self.memory
-            .write(STACK_BASE + self.registers.sp as T::Addr, data);
+        self.on_stack_push();
+
+        self.write(STACK_BASE + self.registers.sp as T::Addr, data);
         self.registers.sp = self.registers.sp.wrapping_sub(1);
 
-        self.debug(&format!("Stack push 0x{:02X}", data));
+        debug!(self, "Stack push 0x{:02X}", data);
     }
 
     fn stack_pop(&mut self) -> T::Data {
+        self.on_stack_pop();
+
         self.registers.sp = self.registers.sp.wrapping_add(1);
-        let data = self.memory.read(STACK_BASE + self.registers.sp as T::Addr);
+        let data = self.read(STACK_BASE + self.registers.sp as T::Addr);
 
-        self.debug(&format!("Stack pop 0x{:02X}", data));
+        debug!(self, "Stack pop 0x{:02X}", data);
         data
     }
 
@@ -317,7 +568,7 @@ where
         self.stack_push(msb);
         self.stack_push(lsb);
 
-        self.debug(&format!("Stack push 0x{:04X}", data));
+        debug!(self, "Stack push 0x{:04X}", data);
     }
 
     fn stack_pop_addr(&mut self) -> T::Addr {
@@ -325,12 +576,23 @@ where
         let msb = self.stack_pop();
         let data = T::Addr::from_le_bytes([lsb, msb]);
 
-        self.debug(&format!("Stack pop 0x{:04X}", data));
+        debug!(self, "Stack pop 0x{:04X}", data);
         data
     }
 
+    /// Reads the index register of an addressing mode.
+    fn index_register(&mut self, register: Register) -> T::Data {
+        self.on_index_register(register);
+
+        match register {
+            Register::X => self.registers.x,
+            Register::Y => self.registers.y,
+            _ => unreachable!(),
+        }
+    }
+
     fn get_address_from_mode(&mut self, mode: AddressingMode) -> T::Addr {
-        self.debug(&format!("Addressing mode {:?}", mode));
+        debug!(self, "Addressing mode {:?}", mode);
 
         match mode {
             AddressingMode::Immediate => {
@@ -340,72 +602,86 @@ where
                 data
             }
             AddressingMode::Absolute => {
-                let data = self.memory.read_addr(self.registers.pc);
+                let data = self.fetch_addr(self.registers.pc);
                 self.registers.pc += 2;
 
                 data
             }
             AddressingMode::AbsoluteX => {
-                let base = self.memory.read_addr(self.registers.pc);
+                let base = self.fetch_addr(self.registers.pc);
                 self.registers.pc += 2;
 
-                base + self.registers.x as T::Addr
+                let data = base + self.index_register(Register::X) as T::Addr;
+                self.page_crossed = base & 0xFF00 != data & 0xFF00;
+
+                data
             }
             AddressingMode::AbsoluteY => {
-                let base = self.memory.read_addr(self.registers.pc);
+                let base = self.fetch_addr(self.registers.pc);
                 self.registers.pc += 2;
 
-                base + self.registers.y as T::Addr
+                let data = base + self.index_register(Register::Y) as T::Addr;
+                self.page_crossed = base & 0xFF00 != data & 0xFF00;
+
+                data
             }
             AddressingMode::Indirect => {
-                let ptr = self.memory.read_addr(self.registers.pc);
+                let ptr = self.fetch_addr(self.registers.pc);
                 self.registers.pc += 2;
 
-                self.memory.read_addr(ptr)
+                self.read_addr(ptr)
             }
             AddressingMode::IndirectX => {
-                let base = self.memory.read(self.registers.pc);
+                let base = self.fetch(self.registers.pc);
                 self.registers.pc += 1;
 
-                let ptr = base.wrapping_add(self.registers.x);
-                let data = self.memory.read_addr(ptr as T::Addr);
+                let ptr = base.wrapping_add(self.index_register(Register::X));
+                let data = self.read_addr(ptr as T::Addr);
                 self.registers.pc += 2;
 
                 data
             }
             AddressingMode::IndirectY => {
-                let ptr = self.memory.read(self.registers.pc);
+                let ptr = self.fetch(self.registers.pc);
                 self.registers.pc += 1;
 
-                let data = self.memory.read_addr(ptr as T::Addr);
+                let base = self.read_addr(ptr as T::Addr);
                 self.registers.pc += 2;
 
-                data + self.registers.y as T::Addr
+                let data = base + self.index_register(Register::Y) as T::Addr;
+                self.page_crossed = base & 0xFF00 != data & 0xFF00;
+
+                data
             }
             AddressingMode::ZeroPage => {
-                let data = self.memory.read(self.registers.pc);
+                let data = self.fetch(self.registers.pc);
                 self.registers.pc += 1;
 
                 data as T::Addr
             }
             AddressingMode::ZeroPageX => {
-                let data = self.memory.read(self.registers.pc);
+                let data = self.fetch(self.registers.pc);
                 self.registers.pc += 1;
 
-                data.wrapping_add(self.registers.x) as T::Addr
+                data.wrapping_add(self.index_register(Register::X)) as T::Addr
             }
             AddressingMode::ZeroPageY => {
-                let data = self.memory.read(self.registers.pc);
+                let data = self.fetch(self.registers.pc);
                 self.registers.pc += 1;
 
-                data.wrapping_add(self.registers.y) as T::Addr
+                data.wrapping_add(self.index_register(Register::Y)) as T::Addr
             }
         }
     }
 
     fn get_data_from_addressing_mode(&mut self, mode: AddressingMode) -> T::Data {
         let address = self.get_address_from_mode(mode);
-        self.memory.read(address)
+
+        // Immediate operands are part of the instruction
+        match mode {
+            AddressingMode::Immediate => self.fetch(address),
+            _ => self.read(address),
+        }
     }
 
     fn add_to_accumulator_with_carry(&mut self, data: T::Data) {
@@ -430,13 +706,19 @@ where
     }
 
     fn branch(&mut self) {
-        let offset = self.memory.read(self.registers.pc) as i8;
+        let offset = self.fetch(self.registers.pc) as i8;
         self.registers.pc += 1;
 
         let pc = self.registers.pc as T::Addr;
         self.registers.pc = pc.wrapping_add(offset as T::Addr);
 
-        self.debug(&format!("Branch to 0x{:04X}", self.registers.pc));
+        // Taken branches take one more cycle, and another one if the branch crosses a page
+        self.cycles += 1;
+        if pc & 0xFF00 != self.registers.pc & 0xFF00 {
+            self.cycles += 1;
+        }
+
+        debug!(self, "Branch to {}", self.bank_address(self.registers.pc));
     }
 
     /// ## ADC (Add with Carry)
@@ -480,7 +762,7 @@ where
 
         if let Some(mode) = mode {
             let address = self.get_address_from_mode(mode);
-            self.memory.write(address, data);
+            self.write(address, data);
         } else {
             self.registers.a = data;
         }
@@ -683,9 +965,9 @@ where
     /// `M - 1 -> M`, Flags affected: `N` `Z`
     fn dec(&mut self, mode: AddressingMode) {
         let addr = self.get_address_from_mode(mode);
-        let mut data = self.memory.read(addr);
+        let mut data = self.read(addr);
         data = data.wrapping_sub(1);
-        self.memory.write(addr, data);
+        self.write(addr, data);
         self.registers.set_zero_negative_flags(data);
     }
 
@@ -727,9 +1009,9 @@ where
     /// `M + 1 -> M`, Flags affected: `N` `Z`
     fn inc(&mut self, mode: AddressingMode) {
         let addr = self.get_address_from_mode(mode);
-        let mut data = self.memory.read(addr);
+        let mut data = self.read(addr);
         data = data.wrapping_add(1);
-        self.memory.write(addr, data);
+        self.write(addr, data);
         self.registers.set_zero_negative_flags(data);
     }
 
@@ -824,7 +1106,7 @@ where
         match mode {
             Some(mode) => {
                 let addr = self.get_address_from_mode(mode);
-                self.memory.write(addr, data);
+                self.write(addr, data);
             }
             None => self.registers.a = data,
         }
@@ -897,7 +1179,7 @@ where
         match mode {
             Some(mode) => {
                 let addr = self.get_address_from_mode(mode);
-                self.memory.write(addr, data);
+                self.write(addr, data);
             }
             None => self.registers.a = data,
         }
@@ -922,7 +1204,7 @@ where
         match mode {
             Some(mode) => {
                 let addr = self.get_address_from_mode(mode);
-                self.memory.write(addr, data);
+                self.write(addr, data);
             }
             None => self.registers.a = data,
         }
@@ -992,7 +1274,7 @@ where
     /// `A -> M`, Flags affected: None
     fn sta(&mut self, mode: AddressingMode) {
         let address = self.get_address_from_mode(mode);
-        self.memory.write(address, self.registers.a);
+        self.write(address, self.registers.a);
     }
 
     /// ## STX (Store Index X in Memory)
@@ -1002,7 +1284,7 @@ where
     /// `X -> M`, Flags affected: None
     fn stx(&mut self, mode: AddressingMode) {
         let address = self.get_address_from_mode(mode);
-        self.memory.write(address, self.registers.x);
+        self.write(address, self.registers.x);
     }
 
     /// ## STY (Store Index Y in Memory)
@@ -1012,7 +1294,7 @@ where
     /// `Y -> M`, Flags affected: None
     fn sty(&mut self, mode: AddressingMode) {
         let address = self.get_address_from_mode(mode);
-        self.memory.write(address, self.registers.y);
+        self.write(address, self.registers.y);
     }
 
     /// ## TAX (Transfer Accumulator to Index X)
@@ -1075,25 +1357,130 @@ where
     }
 }
 
-impl<T, D, R> CpuDebugger for Cpu<T, D, R>
+impl<T, D, R> Cpu<T, D, R>
 where
     T: MemoryBus<Data = u8, Addr = u16> + Default,
     D: Debugger,
     R: Debugger,
 {
-    fn step(&mut self) -> u8 {
-        let opcode = self.memory.read(self.registers.pc);
+    /// Executes the next instruction without stopping at breakpoints, and returns its opcode.
+    pub fn execute_next(&mut self) -> u8 {
+        self.on_begin();
 
-        self.debug(&format!(
-            "Execute 0x{:02X} at 0x{:04X}",
-            opcode, self.registers.pc
-        ));
+        self.instruction_pc = self.registers.pc;
+        self.page_crossed = false;
+
+        let opcode = self.fetch(self.registers.pc);
+
+        debug!(
+            self,
+            "Execute 0x{:02X} at {}",
+            opcode,
+            self.bank_address(self.registers.pc)
+        );
+
+        self.on_execute(opcode);
+
+        let start_cycles = self.cycles;
+        let start_sp = self.registers.sp;
 
         self.execute_instruction(opcode);
+
+        self.cycles += base_cycles(opcode) as u64;
+
+        // Indexed reads take one more cycle if the effective address crosses a page
+        if self.page_crossed && has_page_penalty(opcode) {
+            self.cycles += 1;
+        }
+
+        self.tick(self.cycles - start_cycles);
+
+        self.on_executed(opcode, start_cycles, start_sp);
+
+        // `BRK` halts the program, so no interrupt is taken after it
+        if opcode != 0x00 {
+            self.service_interrupt();
+        }
+
+        self.on_commit(opcode);
+        self.instructions += 1;
+
         opcode
     }
 }
 
+impl<T, D, R> CpuDebugger for Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    fn step(&mut self) -> Option<StopReason> {
+        let opcode = self.execute_next();
+
+        #[cfg(feature = "std")]
+        {
+            let accesses = self.breakpoints.take_accesses();
+            if let Some(hit) = self.check_breakpoints(accesses) {
+                return Some(StopReason::Breakpoint(hit));
+            }
+
+            if let Some(violation) = self
+                .sanitizer
+                .as_mut()
+                .and_then(|sanitizer| sanitizer.take_stop())
+            {
+                return Some(StopReason::Sanitizer(violation));
+            }
+
+            if let Some(issue) = self
+                .stack_sanitizer
+                .as_mut()
+                .and_then(|stack_sanitizer| stack_sanitizer.take_stop())
+            {
+                return Some(StopReason::StackSanitizer(issue));
+            }
+        }
+
+        if opcode == 0x00 {
+            return Some(StopReason::Brk);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            let executes = self.breakpoints.executes_at(self.registers.pc);
+            self.check_breakpoints(executes).map(StopReason::Breakpoint)
+        }
+
+        #[cfg(not(feature = "std"))]
+        None
+    }
+
+    fn run(&mut self) -> StopReason {
+        loop {
+            if let Some(reason) = self.step() {
+                return reason;
+            }
+        }
+    }
+}
+
+fn has_page_penalty(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        /* ADC */
+        0x7D | 0x79 | 0x71
+        | /* AND */ 0x3D | 0x39 | 0x31
+        | /* CMP */ 0xDD | 0xD9 | 0xD1
+        | /* EOR */ 0x5D | 0x59 | 0x51
+        | /* LDA */ 0xBD | 0xB9 | 0xB1
+        | /* LDX */ 0xBE
+        | /* LDY */ 0xBC
+        | /* ORA */ 0x1D | 0x19 | 0x11
+        | /* SBC */ 0xFD | 0xF9 | 0xF1
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1142,6 +1529,31 @@ mod tests {
         }
     }
 
+    #[cfg(test)]
+    mod cycles {
+        use super::*;
+
+        #[test]
+        fn test_cycles() {
+            let mut cpu = setup();
+            cpu.reset();
+            cpu.registers.x = 0x01;
+            cpu.load(&[
+                0xA9, 0x01, // LDA #$01 ; 2
+                0xBD, 0x00, 0x02, // LDA $0200,X ; 4
+                0xBD, 0xFF, 0x02, // LDA $02FF,X ; 4 + 1 (page crossed)
+                0x9D, 0xFF, 0x02, // STA $02FF,X ; 5
+                0xF0, 0x00, // BEQ $00 ; 2 + 1 (branch taken)
+                0x00, // BRK ; 7
+            ]);
+
+            cpu.execute();
+
+            assert_eq!(cpu.cycles, 2 + 4 + 5 + 5 + 3 + 7);
+            assert_eq!(cpu.instructions, 6);
+        }
+    }
+
     #[cfg(test)]
     mod memory_addressing_mode {
         use super::*;
@@ -0,0 +1,471 @@
+//! Bookkeeping of the debugger and the analyses around each bus access and instruction.
+//!
+//! Without the `std` feature there is nothing to record, and the hooks are empty.
+
+use super::Cpu;
+use crate::{memory::MemoryBus, registers::Register, Debugger};
+
+#[cfg(feature = "std")]
+use crate::{
+    call_stack::{Frame, FrameKind},
+    coverage::is_branch,
+    history::{Checkpoint, Delta},
+    memory::{Access, MEMORY_SIZE},
+    opcode::instruction_length,
+    registers::{Flag, Registers},
+    vcd::BusCycle,
+    write_log::WriteRecord,
+    DebugKind,
+};
+
+#[cfg(feature = "std")]
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    pub(super) fn reset_hooks(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+
+        if let Some(write_log) = &mut self.write_log {
+            write_log.clear();
+        }
+
+        if let Some(provenance) = &mut self.provenance {
+            provenance.clear();
+        }
+
+        if let Some(call_stack) = &mut self.call_stack {
+            call_stack.clear();
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.clear();
+        }
+
+        if let Some(coverage) = &mut self.coverage {
+            coverage.clear();
+        }
+
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.clear();
+        }
+
+        if let Some(self_modifying) = &mut self.self_modifying {
+            self_modifying.clear();
+        }
+
+        if let Some(sanitizer) = &mut self.sanitizer {
+            sanitizer.reset();
+        }
+
+        if let Some(stack_sanitizer) = &mut self.stack_sanitizer {
+            stack_sanitizer.clear();
+        }
+
+        if let Some(clobber_checker) = &mut self.clobber_checker {
+            clobber_checker.clear();
+        }
+
+        if let Some(replay) = &mut self.replay {
+            replay.clear();
+        }
+    }
+
+    /// Called after `len` bytes were loaded from `address`, wrapping around the address space.
+    /// `code` is set for the code of the program, as opposed to other data.
+    pub(crate) fn on_load(&mut self, address: u16, len: usize, code: bool) {
+        let Some(sanitizer) = &mut self.sanitizer else {
+            return;
+        };
+
+        if len == 0 {
+            return;
+        }
+
+        let end = address as usize + len.min(MEMORY_SIZE) - 1;
+        let mut ranges = vec![address..=end.min(u16::MAX as usize) as u16];
+        if end > u16::MAX as usize {
+            ranges.push(0..=(end - MEMORY_SIZE) as u16);
+        }
+
+        for range in ranges {
+            if code {
+                sanitizer.mark_loaded(range);
+            } else {
+                sanitizer.initialize(range);
+            }
+        }
+    }
+
+    pub(super) fn on_read(&mut self, address: u16, data: u8) {
+        if let Some(provenance) = &mut self.provenance {
+            provenance.record_read(address);
+        }
+
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(address, Access::Read);
+        }
+
+        self.record_bus(address, data, false, false);
+
+        if let Some(sanitizer) = &mut self.sanitizer {
+            if let Some(violation) =
+                sanitizer.record_read(self.instruction_pc, address, self.cycles)
+            {
+                self.debugger.debug(&violation.to_string(), DebugKind::Warn);
+            }
+        }
+
+        self.breakpoints
+            .check_access(self.instruction_pc, address, data, Access::Read);
+    }
+
+    pub(super) fn on_fetch(&mut self, address: u16, data: u8) {
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(address, Access::Execute);
+        }
+
+        // Operand bytes follow the opcode, so only the opcode fetch is at the instruction address
+        self.record_bus(address, data, false, address == self.instruction_pc);
+    }
+
+    /// Called before `data` is written, while the old value is still in memory.
+    pub(super) fn on_write(&mut self, address: u16, data: u8) {
+        let old = self.memory.peek(address);
+
+        if let Some(provenance) = &mut self.provenance {
+            provenance.record_write(address, data);
+        }
+
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(address, Access::Write);
+        }
+
+        if let Some(self_modifying) = &mut self.self_modifying {
+            if let Some(modification) =
+                self_modifying.record_write(self.instruction_pc, address, old, data, self.cycles)
+            {
+                self.debugger
+                    .debug(&modification.to_string(), DebugKind::Warn);
+            }
+        }
+
+        if let Some(sanitizer) = &mut self.sanitizer {
+            if let Some(violation) =
+                sanitizer.record_write(self.instruction_pc, address, self.cycles)
+            {
+                self.debugger.debug(&violation.to_string(), DebugKind::Warn);
+            }
+        }
+
+        if let Some(history) = &mut self.history {
+            history.record_write(address, old);
+        }
+
+        if let Some(step_writes) = &mut self.step_writes {
+            step_writes.push(address);
+        }
+
+        if let Some(write_log) = &mut self.write_log {
+            write_log.record(
+                address,
+                WriteRecord {
+                    cycle: self.cycles,
+                    instruction: self.instructions,
+                    pc: self.instruction_pc,
+                    old,
+                    new: data,
+                },
+            );
+        }
+
+        self.breakpoints
+            .check_access(self.instruction_pc, address, data, Access::Write);
+
+        self.record_bus(address, data, true, false);
+    }
+
+    fn record_bus(&mut self, address: u16, data: u8, write: bool, sync: bool) {
+        if let Some(vcd) = &mut self.vcd {
+            let access = BusCycle {
+                address,
+                data,
+                write,
+                sync,
+            };
+
+            vcd.record(self.cycles, access, self.irq_pending, self.nmi_pending);
+        }
+    }
+
+    pub(super) fn on_stack_push(&mut self) {
+        if let Some(stack_sanitizer) = &mut self.stack_sanitizer {
+            if let Some(issue) = stack_sanitizer.record_push(self.instruction_pc, self.registers.sp)
+            {
+                self.debugger.debug(&issue.to_string(), issue.kind());
+            }
+        }
+    }
+
+    pub(super) fn on_stack_pop(&mut self) {
+        if let Some(stack_sanitizer) = &mut self.stack_sanitizer {
+            if let Some(issue) = stack_sanitizer.record_pop(self.instruction_pc, self.registers.sp)
+            {
+                self.debugger.debug(&issue.to_string(), issue.kind());
+            }
+        }
+    }
+
+    pub(super) fn on_index_register(&mut self, register: Register) {
+        if let Some(provenance) = &mut self.provenance {
+            provenance.record_register(register);
+        }
+    }
+
+    /// Called after entering an interrupt from `caller`, with the stack pointer before it.
+    pub(super) fn on_interrupt(&mut self, nmi: bool, caller: u16, sp: u8) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.interrupt(self.registers.pc, 7);
+        }
+
+        if let Some(stack_sanitizer) = &mut self.stack_sanitizer {
+            stack_sanitizer.interrupt(self.registers.pc, sp);
+        }
+
+        if let Some(clobber_checker) = &mut self.clobber_checker {
+            let memory = &self.memory;
+
+            clobber_checker.enter(self.registers.pc, caller, &self.registers, |address| {
+                memory.peek(address)
+            });
+        }
+
+        if let Some(call_stack) = &mut self.call_stack {
+            call_stack.push(
+                Frame {
+                    kind: if nmi { FrameKind::Nmi } else { FrameKind::Irq },
+                    caller,
+                    routine: self.registers.pc,
+                    return_address: caller,
+                    sp: self.registers.sp,
+                    instruction: self.instructions,
+                },
+                &mut self.call_stack_change,
+            );
+        }
+    }
+
+    /// Called before an instruction starts.
+    pub(super) fn on_begin(&mut self) {
+        if let Some(step_writes) = &mut self.step_writes {
+            step_writes.clear();
+        }
+
+        self.replay_inputs();
+
+        if let Some(history) = &mut self.history {
+            if history.is_checkpoint(self.instructions) {
+                history.push_checkpoint(Checkpoint {
+                    instruction: self.instructions,
+                    registers: self.registers.state(),
+                    cycles: self.cycles,
+                    irq_pending: self.irq_pending,
+                    nmi_pending: self.nmi_pending,
+                    memory: self.memory.slice(..).to_vec(),
+                    devices: self.memory.snapshot_devices(),
+                    call_stack: self.call_stack.clone(),
+                });
+            }
+
+            // Devices written from outside the CPU since the last instruction
+            if let Some(devices) = self.memory.take_devices_before() {
+                history.record_input_devices(devices);
+            }
+
+            history.begin(Delta {
+                registers: self.registers.state(),
+                cycles: self.cycles,
+                irq_pending: self.irq_pending,
+                nmi_pending: self.nmi_pending,
+                ..Delta::default()
+            });
+        }
+
+        self.instruction_bank = self.memory.bank(self.registers.pc);
+
+        if let Some(tracer) = &mut self.tracer {
+            if tracer.update(self.registers.pc, self.cycles) {
+                let line = self.trace_line();
+
+                if let Some(tracer) = &mut self.tracer {
+                    tracer.write_line(&line);
+                }
+            }
+        }
+
+        self.breakpoints.take_accesses();
+    }
+
+    /// Called after the opcode fetch, before the instruction is executed.
+    pub(super) fn on_execute(&mut self, opcode: u8) {
+        if let Some(sanitizer) = &mut self.sanitizer {
+            if let Some(violation) = sanitizer.record_execute(self.instruction_pc, self.cycles) {
+                self.debugger.debug(&violation.to_string(), DebugKind::Warn);
+            }
+        }
+
+        // Recorded before executing, so an instruction patching its own operand is reported
+        if let Some(self_modifying) = &mut self.self_modifying {
+            self_modifying.record_execute(self.instruction_pc, opcode, instruction_length(opcode));
+        }
+    }
+
+    /// Called after the instruction is executed, before any interrupt is taken.
+    pub(super) fn on_executed(&mut self, opcode: u8, start_cycles: u64, start_sp: u8) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(
+                self.instruction_pc,
+                opcode,
+                self.cycles - start_cycles,
+                self.registers.pc,
+            );
+        }
+
+        if let Some(stack_sanitizer) = &mut self.stack_sanitizer {
+            if let Some(issue) = stack_sanitizer.record(
+                self.instruction_pc,
+                opcode,
+                start_sp,
+                self.registers.pc,
+                self.registers.sp,
+            ) {
+                self.debugger.debug(&issue.to_string(), issue.kind());
+            }
+        }
+
+        // RTI
+        if let (0x40, Some(clobber_checker)) = (opcode, &mut self.clobber_checker) {
+            let memory = &self.memory;
+
+            if let Some(clobber) =
+                clobber_checker.exit(self.instruction_pc, &self.registers, |address| {
+                    memory.peek(address)
+                })
+            {
+                self.debugger.debug(&clobber.to_string(), DebugKind::Warn);
+            }
+        }
+
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(self.instruction_pc, branch_taken(opcode, &self.registers));
+        }
+
+        if let Some(call_stack) = &mut self.call_stack {
+            let desyncs = call_stack.update(
+                self.instruction_pc,
+                opcode,
+                self.registers.sp,
+                self.registers.pc,
+                self.instructions,
+                &mut self.call_stack_change,
+            );
+
+            for desync in desyncs {
+                self.debugger.debug(&desync.to_string(), DebugKind::Warn);
+            }
+        }
+    }
+
+    /// Called once the instruction and any interrupt it led to are done.
+    pub(super) fn on_commit(&mut self, opcode: u8) {
+        if let Some(provenance) = &mut self.provenance {
+            provenance.commit(
+                self.instruction_pc,
+                opcode,
+                self.instructions,
+                &self.registers,
+            );
+        }
+
+        let change = std::mem::take(&mut self.call_stack_change);
+
+        if let Some(history) = &mut self.history {
+            if let Some(devices) = self.memory.take_devices_before() {
+                history.record_devices(devices);
+            }
+
+            history.record_call_stack(change);
+            history.commit();
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    #[inline(always)]
+    pub(super) fn reset_hooks(&mut self) {}
+
+    #[inline(always)]
+    pub(crate) fn on_load(&mut self, _address: u16, _len: usize, _code: bool) {}
+
+    #[inline(always)]
+    pub(super) fn on_read(&mut self, _address: u16, _data: u8) {}
+
+    #[inline(always)]
+    pub(super) fn on_fetch(&mut self, _address: u16, _data: u8) {}
+
+    #[inline(always)]
+    pub(super) fn on_write(&mut self, _address: u16, _data: u8) {}
+
+    #[inline(always)]
+    pub(super) fn on_stack_push(&mut self) {}
+
+    #[inline(always)]
+    pub(super) fn on_stack_pop(&mut self) {}
+
+    #[inline(always)]
+    pub(super) fn on_index_register(&mut self, _register: Register) {}
+
+    #[inline(always)]
+    pub(super) fn on_interrupt(&mut self, _nmi: bool, _caller: u16, _sp: u8) {}
+
+    #[inline(always)]
+    pub(super) fn on_begin(&mut self) {}
+
+    #[inline(always)]
+    pub(super) fn on_execute(&mut self, _opcode: u8) {}
+
+    #[inline(always)]
+    pub(super) fn on_executed(&mut self, _opcode: u8, _start_cycles: u64, _start_sp: u8) {}
+
+    #[inline(always)]
+    pub(super) fn on_commit(&mut self, _opcode: u8) {}
+}
+
+/// Whether a conditional branch was taken, `None` for other opcodes.
+///
+/// Branches do not change flags, so the condition can be checked after execution.
+#[cfg(feature = "std")]
+fn branch_taken<R: Debugger>(opcode: u8, registers: &Registers<R>) -> Option<bool> {
+    if !is_branch(opcode) {
+        return None;
+    }
+
+    let flag = match opcode >> 6 {
+        0 => Flag::Negative,
+        1 => Flag::Overflow,
+        2 => Flag::Carry,
+        _ => Flag::Zero,
+    };
+
+    Some(registers.get_flag(flag) == (opcode & 0x20 != 0))
+}
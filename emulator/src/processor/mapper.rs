@@ -0,0 +1,497 @@
+use crate::memory_map::Device;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Selected bank, shared between a [`BankedRom`] and the [`BankLatch`] that switches it.
+#[derive(Clone, Debug, Default)]
+pub struct BankRegister(Arc<AtomicUsize>);
+
+impl BankRegister {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, bank: usize) {
+        self.0.store(bank, Ordering::Relaxed);
+    }
+}
+
+/// What a window of a [`BankedRom`] shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    /// Always the given bank, negative counting from the last bank (`-1` is the last one).
+    Fixed(isize),
+    /// The bank selected by the bank register.
+    Switchable,
+}
+
+/// # Banked ROM
+///
+/// A ROM image larger than its region, paged in banks of `bank_size` bytes.
+/// The region is split in windows of one bank each, either fixed or switchable.
+///
+/// The bank is selected by writes to ROM space when [`BankedRom::with_select_on_write`] is set,
+/// as on discrete logic cartridges, or through a [`BankLatch`] mapped in I/O space.
+/// Bank numbers wrap around the number of banks.
+///
+/// ```ignore
+/// let rom = BankedRom::switchable_16k(image);
+/// let latch = rom.latch();
+/// let memory = MemoryMap::<NoneDebugger>::new()
+///     .ram(0x0000..=0x7FFF)
+///     .device(0x6000..=0x6000, Box::new(latch))
+///     .device(0x8000..=0xFFFF, Box::new(rom));
+/// ```
+pub struct BankedRom {
+    data: Vec<u8>,
+    bank_size: usize,
+    windows: Vec<Window>,
+    register: BankRegister,
+    select_on_write: bool,
+}
+
+impl BankedRom {
+    /// Pads `data` to whole banks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bank_size` is zero or `windows` is empty.
+    pub fn new(mut data: Vec<u8>, bank_size: usize, windows: Vec<Window>) -> BankedRom {
+        assert!(bank_size > 0, "bank size must not be zero");
+        assert!(
+            !windows.is_empty(),
+            "a banked ROM needs at least one window"
+        );
+
+        let banks = data.len().div_ceil(bank_size).max(1);
+        data.resize(banks * bank_size, 0);
+
+        BankedRom {
+            data,
+            bank_size,
+            windows,
+            register: BankRegister::default(),
+            select_on_write: false,
+        }
+    }
+
+    /// A switchable 16K window at `$8000`, and the last bank fixed at `$C000`.
+    /// Writes to ROM space select the bank.
+    pub fn switchable_16k(data: Vec<u8>) -> BankedRom {
+        BankedRom::new(data, 0x4000, vec![Window::Switchable, Window::Fixed(-1)])
+            .with_select_on_write(true)
+    }
+
+    /// A switchable 8K window at `$8000`, and the last three banks fixed at `$A000`-`$FFFF`.
+    /// Writes to ROM space select the bank.
+    pub fn switchable_8k(data: Vec<u8>) -> BankedRom {
+        BankedRom::new(
+            data,
+            0x2000,
+            vec![
+                Window::Switchable,
+                Window::Fixed(-3),
+                Window::Fixed(-2),
+                Window::Fixed(-1),
+            ],
+        )
+        .with_select_on_write(true)
+    }
+
+    /// Whether writes to ROM space select the bank.
+    pub fn with_select_on_write(mut self, select_on_write: bool) -> BankedRom {
+        self.select_on_write = select_on_write;
+        self
+    }
+
+    pub fn banks(&self) -> usize {
+        self.data.len() / self.bank_size
+    }
+
+    pub fn register(&self) -> BankRegister {
+        self.register.clone()
+    }
+
+    /// A latch selecting the bank of this ROM, to map in I/O space.
+    pub fn latch(&self) -> BankLatch {
+        BankLatch {
+            register: self.register(),
+        }
+    }
+
+    /// Bank mapped at `offset`, and the index of `offset` in the image.
+    fn locate(&self, offset: u16) -> (usize, usize) {
+        let offset = offset as usize;
+        let banks = self.banks() as isize;
+
+        let bank = match self.windows[(offset / self.bank_size) % self.windows.len()] {
+            Window::Fixed(bank) => bank.rem_euclid(banks) as usize,
+            Window::Switchable => self.register.get() % self.banks(),
+        };
+
+        (bank, bank * self.bank_size + offset % self.bank_size)
+    }
+}
+
+impl Device for BankedRom {
+    fn read(&mut self, offset: u16) -> u8 {
+        self.peek(offset)
+    }
+
+    fn write(&mut self, _offset: u16, data: u8) {
+        if self.select_on_write {
+            self.register.set(data as usize);
+        }
+    }
+
+    fn peek(&self, offset: u16) -> u8 {
+        self.data[self.locate(offset).1]
+    }
+
+    /// Patches the image in the bank mapped at `offset`, without selecting a bank.
+    fn poke(&mut self, offset: u16, data: u8) {
+        let index = self.locate(offset).1;
+        self.data[index] = data;
+    }
+
+    fn reset(&mut self) {
+        self.register.set(0);
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        (self.register.get() as u64).to_le_bytes().to_vec()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        if let Ok(bytes) = snapshot.try_into() {
+            self.register.set(u64::from_le_bytes(bytes) as usize);
+        }
+    }
+
+    fn bank(&self, offset: u16) -> Option<usize> {
+        Some(self.locate(offset).0)
+    }
+}
+
+/// # Bank Latch
+///
+/// A register in I/O space selecting the bank of a [`BankedRom`], created with [`BankedRom::latch`].
+/// Every address of its region reads and writes the register.
+pub struct BankLatch {
+    register: BankRegister,
+}
+
+impl Device for BankLatch {
+    fn read(&mut self, offset: u16) -> u8 {
+        self.peek(offset)
+    }
+
+    fn write(&mut self, _offset: u16, data: u8) {
+        self.register.set(data as usize);
+    }
+
+    fn peek(&self, _offset: u16) -> u8 {
+        self.register.get() as u8
+    }
+}
+
+/// # Battery RAM
+///
+/// RAM kept across runs in a file, as battery-backed save RAM on cartridges.
+///
+/// It is loaded when opened and saved on [`Device::save`] and when dropped. Resets keep its contents.
+///
+/// Like the file it is saved to, its contents are not part of the history's checkpoints:
+/// stepping back undoes writes to it, seeking past the kept steps does not.
+pub struct BatteryRam {
+    data: Vec<u8>,
+    path: PathBuf,
+}
+
+impl BatteryRam {
+    /// Opens `size` bytes of RAM saved at `path`, blank if the file does not exist yet.
+    /// A file of another size is truncated or padded.
+    pub fn open(path: impl AsRef<Path>, size: usize) -> io::Result<BatteryRam> {
+        let path = path.as_ref().to_path_buf();
+
+        let mut data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        data.resize(size, 0);
+
+        Ok(BatteryRam { data, path })
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Device for BatteryRam {
+    fn read(&mut self, offset: u16) -> u8 {
+        self.peek(offset)
+    }
+
+    fn write(&mut self, offset: u16, data: u8) {
+        if let Some(byte) = self.data.get_mut(offset as usize) {
+            *byte = data;
+        }
+    }
+
+    fn peek(&self, offset: u16) -> u8 {
+        self.data.get(offset as usize).copied().unwrap_or(0)
+    }
+
+    fn poke(&mut self, offset: u16, data: u8) {
+        self.write(offset, data);
+    }
+
+    fn save(&self) -> io::Result<()> {
+        fs::write(&self.path, &self.data)
+    }
+}
+
+impl Drop for BatteryRam {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        breakpoint::{Breakpoint, WatchKind},
+        cpu::Cpu,
+        memory::MemoryBus,
+        memory_map::MemoryMap,
+        replay::{InputEvent, Replay},
+        CpuDebugger, DebugKind, Debugger, NoneDebugger, StopReason,
+    };
+
+    /// Four 16K banks, the switchable ones starting with their number.
+    fn image() -> Vec<u8> {
+        let mut image = vec![0; 4 * 0x4000];
+        for bank in 0..3 {
+            image[bank * 0x4000] = bank as u8;
+        }
+
+        // Last bank, fixed at $C000: select bank 2 and read its first byte
+        image[3 * 0x4000..3 * 0x4000 + 8].copy_from_slice(&[
+            0xA9, 0x02, // $C000 LDA #$02
+            0x8D, 0x00, 0x80, // $C002 STA $8000
+            0xAD, 0x00, 0x80, // $C005 LDA $8000
+        ]);
+        image
+    }
+
+    #[derive(Default)]
+    struct Messages(Vec<String>);
+
+    impl Debugger for Messages {
+        fn debug(&mut self, message: &str, _kind: DebugKind) {
+            self.0.push(message.to_string());
+        }
+    }
+
+    #[test]
+    fn test_select_on_write() {
+        let memory = MemoryMap::<NoneDebugger>::new()
+            .ram(0x0000..=0x7FFF)
+            .device(
+                0x8000..=0xFFFF,
+                Box::new(BankedRom::switchable_16k(image())),
+            );
+        let mut cpu: Cpu<_, Messages, NoneDebugger> = Cpu::new(memory);
+        cpu.reset();
+        cpu.registers.pc = 0xC000;
+
+        assert_eq!(cpu.memory.peek(0x8000), 0x00);
+        assert_eq!(cpu.memory.bank(0x8000), Some(0));
+        assert_eq!(cpu.memory.bank(0xC000), Some(3));
+        assert_eq!(cpu.memory.bank(0x0000), None);
+
+        for _ in 0..3 {
+            cpu.execute_next();
+        }
+
+        assert_eq!(cpu.registers.a, 0x02);
+        assert_eq!(cpu.memory.bank(0x8000), Some(2));
+        assert_eq!(cpu.bank_address(0x8000).to_string(), "0x02:8000");
+        assert_eq!(cpu.bank_address(0x0200).to_string(), "0x0200");
+        assert!(cpu
+            .debugger
+            .0
+            .contains(&"Execute 0x8D at 0x03:C002".to_string()));
+
+        cpu.reset();
+        assert_eq!(cpu.memory.bank(0x8000), Some(0));
+    }
+
+    #[test]
+    fn test_seek_restores_bank() {
+        // Deltas for every instruction, then checkpoints only
+        for (capacity, checkpoint_interval) in [(10, 10), (0, 1)] {
+            let memory = MemoryMap::<NoneDebugger>::new()
+                .ram(0x0000..=0x7FFF)
+                .device(
+                    0x8000..=0xFFFF,
+                    Box::new(BankedRom::switchable_16k(image())),
+                );
+            let mut cpu: Cpu<_, NoneDebugger, NoneDebugger> = Cpu::new(memory);
+            cpu.reset();
+            cpu.registers.pc = 0xC000;
+            cpu.enable_history(capacity, checkpoint_interval);
+
+            // Loading patches the mapped bank, it does not select one
+            cpu.load_at(0x8001, &[0x42]);
+            assert_eq!(cpu.memory.bank(0x8000), Some(0));
+
+            for _ in 0..3 {
+                cpu.execute_next();
+            }
+            assert_eq!(cpu.memory.bank(0x8000), Some(2));
+            assert_eq!(cpu.registers.a, 0x02);
+
+            // Back before `STA $8000`
+            assert!(cpu.seek(1));
+            assert_eq!(cpu.memory.bank(0x8000), Some(0));
+            assert_eq!(cpu.memory.peek(0x8000), 0x00);
+            assert_eq!(cpu.memory.peek(0x8001), 0x42);
+
+            // Undoing the ROM write left bank 2 intact
+            cpu.memory.write(0x8000, 0x02);
+            assert_eq!(cpu.memory.peek(0x8000), 0x02);
+            assert_eq!(cpu.memory.peek(0x8001), 0x00);
+        }
+    }
+
+    #[test]
+    fn test_input_write() {
+        let memory = MemoryMap::<NoneDebugger>::new()
+            .ram(0x0000..=0x7FFF)
+            .device(
+                0x8000..=0xFFFF,
+                Box::new(BankedRom::switchable_16k(image())),
+            );
+        let mut cpu: Cpu<_, NoneDebugger, NoneDebugger> = Cpu::new(memory);
+        cpu.reset();
+        cpu.registers.pc = 0xC000;
+        cpu.enable_history(10, 10);
+        cpu.replay = Some(Replay::recording());
+
+        // A poke does not reach the bank register, a write selects the bank
+        cpu.execute_next();
+        cpu.input(InputEvent::Poke {
+            address: 0x8001,
+            data: 0x42,
+        });
+        assert_eq!(cpu.memory.bank(0x8000), Some(0));
+        cpu.input(InputEvent::Write {
+            address: 0x8000,
+            data: 0x01,
+        });
+        assert_eq!(cpu.memory.bank(0x8000), Some(1));
+        assert_eq!(cpu.memory.peek(0x8000), 0x01);
+        assert_eq!(
+            cpu.replay.as_ref().unwrap().to_string(),
+            "# instruction cycle event\n1 2 poke 8001 42\n1 2 write 8000 01\n"
+        );
+
+        cpu.execute_next();
+        assert!(cpu.seek(1));
+        assert_eq!(cpu.memory.bank(0x8000), Some(1));
+        assert!(cpu.seek(0));
+        assert_eq!(cpu.memory.bank(0x8000), Some(0));
+
+        // Undone right away, before the next instruction
+        cpu.execute_next();
+        cpu.input(InputEvent::Write {
+            address: 0x8000,
+            data: 0x01,
+        });
+        assert!(cpu.step_back());
+        assert_eq!(cpu.memory.bank(0x8000), Some(0));
+        assert_eq!(cpu.memory.peek(0x8001), 0x00);
+    }
+
+    #[test]
+    fn test_watchpoint_bank() {
+        // Code in bank 1 switching to bank 2
+        let mut image = vec![0; 4 * 0x4000];
+        image[0x4000..0x4005].copy_from_slice(&[
+            0xA9, 0x02, // $8000 LDA #$02
+            0x8D, 0x00, 0x80, // $8002 STA $8000
+        ]);
+
+        let memory = MemoryMap::<NoneDebugger>::new()
+            .ram(0x0000..=0x7FFF)
+            .device(0x8000..=0xFFFF, Box::new(BankedRom::switchable_16k(image)));
+        let mut cpu: Cpu<_, NoneDebugger, NoneDebugger> = Cpu::new(memory);
+        cpu.reset();
+        cpu.memory.write(0x8000, 1);
+        cpu.registers.pc = 0x8000;
+        cpu.breakpoints
+            .add(Breakpoint::watch(0x8000..=0x8000, WatchKind::Write));
+
+        // The store is reported in the bank it was executed from
+        let StopReason::Breakpoint(hit) = cpu.run() else {
+            panic!("watchpoint not hit");
+        };
+        assert_eq!((hit.pc, hit.bank), (0x8002, Some(1)));
+        assert_eq!(cpu.memory.bank(0x8000), Some(2));
+    }
+
+    #[test]
+    fn test_latch() {
+        let rom = BankedRom::switchable_8k(vec![0xEA; 6 * 0x2000]);
+        let register = rom.register();
+        let mut memory = MemoryMap::<NoneDebugger>::new()
+            .device(0x6000..=0x6000, Box::new(rom.latch()))
+            .device(0x8000..=0xFFFF, Box::new(rom.with_select_on_write(false)));
+
+        // Only the latch selects the bank
+        memory.write(0x8000, 1);
+        assert_eq!(memory.bank(0x8000), Some(0));
+
+        memory.write(0x6000, 9);
+        assert_eq!(register.get(), 9);
+        assert_eq!(memory.read(0x6000), 9);
+        // Banks wrap around the six banks, the last three are fixed
+        assert_eq!(memory.bank(0x8000), Some(3));
+        assert_eq!(memory.bank(0xA000), Some(3));
+        assert_eq!(memory.bank(0xC000), Some(4));
+        assert_eq!(memory.bank(0xFFFF), Some(5));
+    }
+
+    #[test]
+    fn test_battery_ram() {
+        let path = std::env::temp_dir().join(format!("battery-{}.sav", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        {
+            let mut memory = MemoryMap::<NoneDebugger>::new().device(
+                0x6000..=0x7FFF,
+                Box::new(BatteryRam::open(&path, 0x2000).unwrap()),
+            );
+            memory.write(0x6001, 0x42);
+            memory.reset();
+            assert_eq!(memory.read(0x6001), 0x42);
+        }
+
+        let ram = BatteryRam::open(&path, 0x2000).unwrap();
+        assert_eq!(ram.data().len(), 0x2000);
+        assert_eq!(ram.peek(0x0001), 0x42);
+
+        drop(ram);
+        fs::remove_file(&path).unwrap();
+    }
+}
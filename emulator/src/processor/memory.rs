@@ -1,9 +1,25 @@
-use std::slice::SliceIndex;
+use core::{fmt, slice::SliceIndex};
 
-use crate::{DebugKind, Debugger, NoneDebugger};
+#[cfg(feature = "std")]
+use crate::NoneDebugger;
+use crate::{DebugKind, Debugger};
 
 pub const STACK_BASE: u16 = 0x0100;
 pub const ORG: u16 = 0x8000;
+pub const MEMORY_SIZE: usize = 0x10000;
+
+pub const NMI_VECTOR: u16 = 0xFFFA;
+pub const RESET_VECTOR: u16 = 0xFFFC;
+pub const IRQ_VECTOR: u16 = 0xFFFE;
+
+/// Kind of a memory access made by the CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    Read,
+    Write,
+    /// Opcode or operand fetch
+    Execute,
+}
 
 /// # Memory Bus
 ///
@@ -18,10 +34,52 @@ pub trait MemoryBus {
     fn read(&mut self, addr: Self::Addr) -> Self::Data;
     fn write_addr(&mut self, addr: Self::Addr, data: Self::Addr);
     fn read_addr(&mut self, addr: Self::Addr) -> Self::Addr;
+    fn peek(&self, addr: Self::Addr) -> Self::Data;
+    fn poke(&mut self, addr: Self::Addr, data: Self::Data);
     fn slice(
         &mut self,
         range: impl SliceIndex<[Self::Data], Output = [Self::Data]>,
     ) -> &[Self::Data];
+    /// Overwrites the memory `slice(..)` returns with `data`, without side effects.
+    fn restore(&mut self, data: &[Self::Data]);
+    /// Advances devices on the bus by `cycles` CPU cycles, returns whether one requests an interrupt.
+    fn tick(&mut self, _cycles: u64) -> bool {
+        false
+    }
+    /// Bank mapped at `addr`, if the memory pages banks there.
+    fn bank(&self, _addr: Self::Addr) -> Option<usize> {
+        None
+    }
+    /// Internal state of the devices on the bus, such as bank registers, saved by the execution history.
+    #[cfg(feature = "std")]
+    fn snapshot_devices(&self) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
+    /// Restores the state returned by [`MemoryBus::snapshot_devices`].
+    #[cfg(feature = "std")]
+    fn restore_devices(&mut self, _snapshot: &[Vec<u8>]) {}
+    /// The device state before the first device write since the last call, `None` if no device was written.
+    /// Lets the execution history save device state only when it may have changed.
+    #[cfg(feature = "std")]
+    fn take_devices_before(&mut self) -> Option<Vec<Vec<u8>>> {
+        None
+    }
+}
+
+/// An address with the bank mapped at it, shown as `0x03:8000` for bank 3, or `0x8000` outside banked memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BankAddress {
+    pub bank: Option<usize>,
+    pub address: u16,
+}
+
+impl fmt::Display for BankAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "0x{:02X}:{:04X}", bank, self.address),
+            None => write!(f, "0x{:04X}", self.address),
+        }
+    }
 }
 
 /// # Memory Map
@@ -33,16 +91,18 @@ pub trait MemoryBus {
 /// * `0x8000` ~ `0xFFFF`: ROM
 ///
 /// The actual ROM memory map of the MOS 6502 ranges from `0x8000` - `0xFFF9`, and interrupt vectors are stored in `0xFFFA` - `0xFFFF`.
-/// however, since it does not implement interrupts, it is currently not used.
+///
+/// The address space is a fixed-size array, so memory needs no allocator.
+/// For write-protected ROM, mirrors and devices, use a [`MemoryMap`](crate::memory_map::MemoryMap).
 pub struct Memory<T: Debugger> {
-    pub mem: [u8; 0xFFFF],
+    pub mem: [u8; MEMORY_SIZE],
     pub debugger: T,
 }
 
 impl<T: Debugger> Memory<T> {
     pub fn new() -> Memory<T> {
         Memory {
-            mem: [0; 0xFFFF],
+            mem: [0; MEMORY_SIZE],
             debugger: T::default(),
         }
     }
@@ -55,13 +115,13 @@ impl<T: Debugger> Memory<T> {
 impl<T: Debugger> Default for Memory<T> {
     fn default() -> Memory<T> {
         Memory {
-            mem: [0; 0xFFFF],
+            mem: [0; MEMORY_SIZE],
             debugger: T::default(),
         }
     }
 }
 
-impl<T: Debugger> std::ops::Index<u16> for Memory<T> {
+impl<T: Debugger> core::ops::Index<u16> for Memory<T> {
     type Output = u8;
 
     fn index(&self, index: u16) -> &Self::Output {
@@ -69,7 +129,7 @@ impl<T: Debugger> std::ops::Index<u16> for Memory<T> {
     }
 }
 
-impl<T: Debugger> std::ops::IndexMut<u16> for Memory<T> {
+impl<T: Debugger> core::ops::IndexMut<u16> for Memory<T> {
     fn index_mut(&mut self, index: u16) -> &mut Self::Output {
         &mut self.mem[index as usize]
     }
@@ -81,58 +141,73 @@ impl<T: Debugger> MemoryBus for Memory<T> {
 
     /// `rom` function loads the program from address `0x8000`.
     fn rom(&mut self, program: &[Self::Data]) {
-        self.debug(format!("Load ROM ({} bytes)", program.len()).as_str());
+        debug!(self, "Load ROM ({} bytes)", program.len());
         self.mem[ORG as usize..ORG as usize + program.len()].copy_from_slice(program);
     }
 
     /// Resets the memory.
     fn reset(&mut self) {
         self.debug("Reset Memory");
-        self.mem = [0; 0xFFFF];
+        self.mem = [0; MEMORY_SIZE];
     }
 
     /// Write data to memory address
     fn write(&mut self, address: Self::Addr, data: Self::Data) {
-        self.debug(&format!("Write 0x{:04X} = 0x{:02X}", address, data));
+        debug!(self, "Write 0x{:04X} = 0x{:02X}", address, data);
         self[address] = data;
     }
 
     /// Read data from memory address
     fn read(&mut self, address: Self::Addr) -> Self::Data {
         let data = self[address];
-        self.debug(&format!("Read 0x{:04X} = 0x{:02X}", address, data));
+        debug!(self, "Read 0x{:04X} = 0x{:02X}", address, data);
         data
     }
 
     /// Write 16-bit data to memory address (little endian)
     fn write_addr(&mut self, address: Self::Addr, data: Self::Addr) {
-        self.debug(&format!("Write 0x{:04X} = 0x{:04X}", address, data));
+        debug!(self, "Write 0x{:04X} = 0x{:04X}", address, data);
         let [lsb, msb] = data.to_le_bytes();
 
         self.write(address, lsb);
-        self.write(address + 1, msb);
+        self.write(address.wrapping_add(1), msb);
     }
 
     /// Read 16-bit data from memory address (little endian)
     fn read_addr(&mut self, address: Self::Addr) -> Self::Addr {
-        self.debug(&format!("Read 0x{:04X}", address));
+        debug!(self, "Read 0x{:04X}", address);
         let lsb = self.read(address);
-        let msb = self.read(address + 1);
+        let msb = self.read(address.wrapping_add(1));
 
         u16::from_le_bytes([lsb, msb])
     }
 
+    /// Read data from memory address without any side effects (no debug output)
+    fn peek(&self, address: Self::Addr) -> Self::Data {
+        self[address]
+    }
+
+    /// Write data to memory address without any side effects (no debug output)
+    fn poke(&mut self, address: Self::Addr, data: Self::Data) {
+        self[address] = data;
+    }
+
     fn slice(
         &mut self,
         range: impl SliceIndex<[Self::Data], Output = [Self::Data]>,
     ) -> &[Self::Data] {
         &self.mem[range]
     }
+
+    fn restore(&mut self, data: &[Self::Data]) {
+        self.mem.copy_from_slice(data);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::NoneDebugger;
 
     #[test]
     fn test_read_write() {
@@ -155,12 +230,30 @@ mod tests {
         assert_eq!(memory.read(0x0001), 0x12);
         assert_eq!(memory.read_addr(0x0000), 0x1234);
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_hexdump_end() {
+        let mut memory = [0; MEMORY_SIZE];
+        memory[0xFFFF] = 0x41;
+
+        let dump = memory_hexdump(memory, 0xFFF0, 0xFFFF);
+        assert_eq!(dump.len(), 1);
+        assert_eq!((dump[0].1[15], dump[0].2[15]), (0x41, 'A'));
+
+        assert_eq!(
+            memory_hexdump_string(memory, 0xFFF8, 0xFFFF),
+            "[0xFFF8] 00 00 00 00 00 00 00 41    | .......A  |"
+        );
+    }
 }
 
 /// | 0x0000 | 00 00 .. 00 00 | ................ |
+#[cfg(feature = "std")]
 pub type MemoryDumpResult = Vec<(u16, [u8; 16], [char; 16])>;
 
-pub fn memory_hexdump(memory: [u8; 0xFFFF], start: u16, end: u16) -> MemoryDumpResult {
+#[cfg(feature = "std")]
+pub fn memory_hexdump(memory: [u8; MEMORY_SIZE], start: u16, end: u16) -> MemoryDumpResult {
     let mut memory: Memory<NoneDebugger> = Memory {
         mem: memory,
         ..Default::default()
@@ -171,10 +264,10 @@ pub fn memory_hexdump(memory: [u8; 0xFFFF], start: u16, end: u16) -> MemoryDumpR
         let mut line = ([0; 16], [' '; 16]);
 
         for i in 0..16 {
-            if addr + i > u16::MAX - 1 {
+            let Some(address) = addr.checked_add(i) else {
                 break;
-            }
-            let data = memory.read(addr + i);
+            };
+            let data = memory.read(address);
 
             line.0[i as usize] = data;
 
@@ -191,7 +284,8 @@ pub fn memory_hexdump(memory: [u8; 0xFFFF], start: u16, end: u16) -> MemoryDumpR
     result
 }
 
-pub fn memory_hexdump_string(memory: [u8; 0xFFFF], start: u16, end: u16) -> String {
+#[cfg(feature = "std")]
+pub fn memory_hexdump_string(memory: [u8; MEMORY_SIZE], start: u16, end: u16) -> String {
     let mut memory: Memory<NoneDebugger> = Memory {
         mem: memory,
         ..Default::default()
@@ -202,22 +296,22 @@ pub fn memory_hexdump_string(memory: [u8; 0xFFFF], start: u16, end: u16) -> Stri
         let mut line = format!("[0x{:04X}] ", addr);
 
         for i in 0..16 {
-            if addr + i > u16::MAX - 1 {
+            let Some(address) = addr.checked_add(i) else {
                 line.push_str("   ");
                 break;
-            }
-            let data = memory.read(addr + i);
+            };
+            let data = memory.read(address);
             line.push_str(&format!("{:02X} ", data));
         }
 
         line.push_str("| ");
 
         for i in 0..16 {
-            if addr + i > u16::MAX - 1 {
+            let Some(address) = addr.checked_add(i) else {
                 line.push(' ');
                 break;
-            }
-            let data = memory.read(addr + i);
+            };
+            let data = memory.read(address);
             if data.is_ascii_control() {
                 line.push('.');
             } else {
@@ -0,0 +1,497 @@
+use crate::{
+    memory::{MemoryBus, MEMORY_SIZE, ORG},
+    DebugKind, Debugger,
+};
+use std::{io, ops::RangeInclusive, slice::SliceIndex};
+
+/// # Device
+///
+/// A memory-mapped peripheral. Addresses are given as offsets from the start of the device's region.
+///
+/// Devices are `Send`, so a CPU with a [`MemoryMap`] can be moved to another thread.
+pub trait Device: Send {
+    fn read(&mut self, offset: u16) -> u8;
+    fn write(&mut self, offset: u16, data: u8);
+    /// Reads without side effects, for debuggers. Devices without readable state return `0`.
+    fn peek(&self, _offset: u16) -> u8 {
+        0
+    }
+    /// Writes without side effects, for loaders and debuggers: stores into backing memory
+    /// without triggering registers. Devices without backing memory ignore it.
+    fn poke(&mut self, _offset: u16, _data: u8) {}
+    /// Advances the device by `cycles` CPU cycles, returns whether its interrupt line is asserted.
+    fn tick(&mut self, _cycles: u64) -> bool {
+        false
+    }
+    fn reset(&mut self) {}
+    /// Bank mapped at `offset`, for devices paging banks into their region.
+    fn bank(&self, _offset: u16) -> Option<usize> {
+        None
+    }
+    /// Internal state not reachable through `peek` and `poke`, such as bank registers.
+    /// The execution history takes it before instructions writing a device, so it should be small,
+    /// and it should only change in [`Device::write`].
+    fn snapshot(&self) -> Vec<u8> {
+        Vec::new()
+    }
+    /// Restores the state returned by [`Device::snapshot`].
+    fn restore(&mut self, _snapshot: &[u8]) {}
+    /// Persists non-volatile state, such as battery-backed RAM.
+    fn save(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub enum RegionKind {
+    Ram,
+    /// Read-only memory, writes of the CPU are ignored.
+    Rom,
+    /// Repeats the region starting at `target`, every `size` bytes.
+    Mirror {
+        target: u16,
+        size: u16,
+    },
+    Device(Box<dyn Device>),
+    /// Nothing is connected: reads return the last value on the data bus, writes are ignored.
+    OpenBus,
+}
+
+pub struct Region {
+    pub range: RangeInclusive<u16>,
+    pub kind: RegionKind,
+}
+
+/// Where an address ends up after resolving mirrors.
+enum Target {
+    Ram(u16),
+    Rom(u16),
+    Device(usize, u16),
+    OpenBus,
+}
+
+/// # Memory Map
+///
+/// A 64 KiB address space assembled from regions of RAM, ROM, mirrors, devices and open bus.
+///
+/// Later regions take precedence over earlier ones they overlap, and unmapped addresses are open bus.
+/// RAM and ROM contents live at their own addresses of a flat backing store, which is what [`MemoryBus::slice`] returns.
+/// [`MemoryBus::poke`] writes ROM too, so programs and test inputs can be loaded, and goes to [`Device::poke`] for devices.
+///
+/// ```ignore
+/// let memory = MemoryMap::<NoneDebugger>::new()
+///     .ram(0x0000..=0x07FF)
+///     .mirror(0x0800..=0x1FFF, 0x0000..=0x07FF)
+///     .device(0x6000..=0x6003, Box::new(Timer::default()))
+///     .rom(0x8000..=0xFFFF);
+/// ```
+pub struct MemoryMap<D: Debugger> {
+    pub regions: Vec<Region>,
+    pub debugger: D,
+    mem: Box<[u8; MEMORY_SIZE]>,
+    /// Last value on the data bus, read back from open bus.
+    bus: u8,
+    /// See [`MemoryBus::take_devices_before`].
+    devices_before: Option<Vec<Vec<u8>>>,
+}
+
+impl<D: Debugger> Default for MemoryMap<D> {
+    /// RAM at `0x0000` ~ `0x7FFF` and ROM at `0x8000` ~ `0xFFFF`.
+    fn default() -> Self {
+        MemoryMap::new().ram(0x0000..=0x7FFF).rom(ORG..=0xFFFF)
+    }
+}
+
+impl<D: Debugger> MemoryMap<D> {
+    /// Creates an empty memory map, where every address is open bus.
+    pub fn new() -> MemoryMap<D> {
+        MemoryMap {
+            regions: Vec::new(),
+            debugger: D::default(),
+            mem: Box::new([0; MEMORY_SIZE]),
+            bus: 0,
+            devices_before: None,
+        }
+    }
+
+    pub fn with_region(mut self, range: RangeInclusive<u16>, kind: RegionKind) -> MemoryMap<D> {
+        self.regions.push(Region { range, kind });
+        self
+    }
+
+    pub fn ram(self, range: RangeInclusive<u16>) -> MemoryMap<D> {
+        self.with_region(range, RegionKind::Ram)
+    }
+
+    pub fn rom(self, range: RangeInclusive<u16>) -> MemoryMap<D> {
+        self.with_region(range, RegionKind::Rom)
+    }
+
+    /// Maps `range` to repeat `target`.
+    pub fn mirror(self, range: RangeInclusive<u16>, target: RangeInclusive<u16>) -> MemoryMap<D> {
+        let size = target.end().wrapping_sub(*target.start()).wrapping_add(1);
+
+        self.with_region(
+            range,
+            RegionKind::Mirror {
+                target: *target.start(),
+                size,
+            },
+        )
+    }
+
+    pub fn device(self, range: RangeInclusive<u16>, device: Box<dyn Device>) -> MemoryMap<D> {
+        self.with_region(range, RegionKind::Device(device))
+    }
+
+    pub fn open_bus(self, range: RangeInclusive<u16>) -> MemoryMap<D> {
+        self.with_region(range, RegionKind::OpenBus)
+    }
+
+    fn debug(&mut self, message: &str) {
+        self.debugger.debug(message, DebugKind::Info);
+    }
+
+    fn region(&self, address: u16) -> Option<(usize, &Region)> {
+        self.regions
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, region)| region.range.contains(&address))
+    }
+
+    fn resolve(&self, address: u16) -> Target {
+        let mut address = address;
+
+        // Bounded, so mirrors of mirrors cannot loop forever
+        for _ in 0..self.regions.len().max(1) {
+            let Some((index, region)) = self.region(address) else {
+                return Target::OpenBus;
+            };
+
+            let offset = address - region.range.start();
+
+            match region.kind {
+                RegionKind::Ram => return Target::Ram(address),
+                RegionKind::Rom => return Target::Rom(address),
+                RegionKind::Device(_) => return Target::Device(index, offset),
+                RegionKind::OpenBus => return Target::OpenBus,
+                RegionKind::Mirror { target, size } => {
+                    let offset = if size == 0 { offset } else { offset % size };
+                    address = target.wrapping_add(offset);
+                }
+            }
+        }
+
+        Target::OpenBus
+    }
+
+    /// Saves the device state before the first device write, see [`MemoryBus::take_devices_before`].
+    fn save_devices(&mut self) {
+        if self.devices_before.is_none() {
+            self.devices_before = Some(self.snapshot_devices());
+        }
+    }
+
+    fn device_ref(&self, index: usize) -> &dyn Device {
+        match &self.regions[index].kind {
+            RegionKind::Device(device) => device.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn device_mut(&mut self, index: usize) -> &mut dyn Device {
+        match &mut self.regions[index].kind {
+            RegionKind::Device(device) => device.as_mut(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Saves the non-volatile state of every device.
+    pub fn save(&self) -> io::Result<()> {
+        for region in &self.regions {
+            if let RegionKind::Device(device) = &region.kind {
+                device.save()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies `data` to `address`, writing ROM too.
+    pub fn load(&mut self, address: u16, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            self.poke(address.wrapping_add(i as u16), *byte);
+        }
+    }
+}
+
+impl<D: Debugger> MemoryBus for MemoryMap<D> {
+    type Data = u8;
+    type Addr = u16;
+
+    /// Loads the program from address `0x8000`.
+    fn rom(&mut self, program: &[Self::Data]) {
+        debug!(self, "Load ROM ({} bytes)", program.len());
+        self.load(ORG, program);
+    }
+
+    /// Clears RAM and resets the devices. ROM is kept.
+    fn reset(&mut self) {
+        self.debug("Reset Memory");
+
+        for region in &mut self.regions {
+            if let RegionKind::Device(device) = &mut region.kind {
+                device.reset();
+            }
+        }
+
+        for address in 0..=u16::MAX {
+            if let Target::Ram(address) = self.resolve(address) {
+                self.mem[address as usize] = 0;
+            }
+        }
+
+        self.bus = 0;
+        self.devices_before = None;
+    }
+
+    fn write(&mut self, address: Self::Addr, data: Self::Data) {
+        debug!(self, "Write 0x{:04X} = 0x{:02X}", address, data);
+        self.bus = data;
+
+        match self.resolve(address) {
+            Target::Ram(address) => self.mem[address as usize] = data,
+            Target::Device(index, offset) => {
+                self.save_devices();
+                self.device_mut(index).write(offset, data);
+            }
+            Target::Rom(_) | Target::OpenBus => {}
+        }
+    }
+
+    fn read(&mut self, address: Self::Addr) -> Self::Data {
+        let data = match self.resolve(address) {
+            Target::Ram(address) | Target::Rom(address) => self.mem[address as usize],
+            Target::Device(index, offset) => self.device_mut(index).read(offset),
+            Target::OpenBus => self.bus,
+        };
+
+        debug!(self, "Read 0x{:04X} = 0x{:02X}", address, data);
+        self.bus = data;
+        data
+    }
+
+    fn write_addr(&mut self, address: Self::Addr, data: Self::Addr) {
+        let [lsb, msb] = data.to_le_bytes();
+
+        self.write(address, lsb);
+        self.write(address.wrapping_add(1), msb);
+    }
+
+    fn read_addr(&mut self, address: Self::Addr) -> Self::Addr {
+        let lsb = self.read(address);
+        let msb = self.read(address.wrapping_add(1));
+
+        u16::from_le_bytes([lsb, msb])
+    }
+
+    fn peek(&self, address: Self::Addr) -> Self::Data {
+        match self.resolve(address) {
+            Target::Ram(address) | Target::Rom(address) => self.mem[address as usize],
+            Target::Device(index, offset) => self.device_ref(index).peek(offset),
+            Target::OpenBus => self.bus,
+        }
+    }
+
+    /// Writes RAM, ROM or a device's backing memory without side effects.
+    fn poke(&mut self, address: Self::Addr, data: Self::Data) {
+        match self.resolve(address) {
+            Target::Ram(address) | Target::Rom(address) => self.mem[address as usize] = data,
+            Target::Device(index, offset) => self.device_mut(index).poke(offset, data),
+            Target::OpenBus => {}
+        }
+    }
+
+    fn slice(
+        &mut self,
+        range: impl SliceIndex<[Self::Data], Output = [Self::Data]>,
+    ) -> &[Self::Data] {
+        &self.mem[range]
+    }
+
+    /// Restores RAM and ROM. Devices are not affected.
+    fn restore(&mut self, data: &[Self::Data]) {
+        self.mem.copy_from_slice(data);
+    }
+
+    fn bank(&self, address: Self::Addr) -> Option<usize> {
+        match self.resolve(address) {
+            Target::Device(index, offset) => self.device_ref(index).bank(offset),
+            _ => None,
+        }
+    }
+
+    fn snapshot_devices(&self) -> Vec<Vec<u8>> {
+        self.regions
+            .iter()
+            .filter_map(|region| match &region.kind {
+                RegionKind::Device(device) => Some(device.snapshot()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn restore_devices(&mut self, snapshot: &[Vec<u8>]) {
+        let devices = self
+            .regions
+            .iter_mut()
+            .filter_map(|region| match &mut region.kind {
+                RegionKind::Device(device) => Some(device),
+                _ => None,
+            });
+
+        for (device, state) in devices.zip(snapshot) {
+            device.restore(state);
+        }
+
+        self.devices_before = None;
+    }
+
+    fn take_devices_before(&mut self) -> Option<Vec<Vec<u8>>> {
+        self.devices_before.take()
+    }
+
+    fn tick(&mut self, cycles: u64) -> bool {
+        let mut irq = false;
+
+        for region in &mut self.regions {
+            if let RegionKind::Device(device) = &mut region.kind {
+                irq |= device.tick(cycles);
+            }
+        }
+
+        irq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu::NoneDebuggerCpu, memory::IRQ_VECTOR, NoneDebugger};
+    use std::sync::{Arc, Mutex};
+
+    type Map = MemoryMap<NoneDebugger>;
+
+    /// Output port recording writes, with a timer asserting IRQ every `period` cycles.
+    #[derive(Default)]
+    struct Port {
+        written: Arc<Mutex<Vec<(u16, u8)>>>,
+        period: u64,
+        elapsed: u64,
+    }
+
+    impl Device for Port {
+        fn read(&mut self, offset: u16) -> u8 {
+            offset as u8 | 0x80
+        }
+
+        fn write(&mut self, offset: u16, data: u8) {
+            self.written.lock().unwrap().push((offset, data));
+        }
+
+        fn tick(&mut self, cycles: u64) -> bool {
+            self.elapsed += cycles;
+
+            if self.period != 0 && self.elapsed >= self.period {
+                self.elapsed -= self.period;
+                return true;
+            }
+
+            false
+        }
+    }
+
+    #[test]
+    fn test_regions() {
+        let mut memory = Map::new()
+            .ram(0x0000..=0x07FF)
+            .mirror(0x0800..=0x1FFF, 0x0000..=0x07FF)
+            .rom(0x8000..=0xFFFF);
+
+        memory.write(0x0801, 0x12);
+        assert_eq!(memory.read(0x0001), 0x12);
+        assert_eq!(memory.read(0x1801), 0x12);
+
+        memory.load(0xFFFE, &[0x34, 0x12]);
+        memory.write(0xFFFF, 0x00);
+        assert_eq!(memory.read_addr(0xFFFE), 0x1234);
+
+        // Open bus returns the last value on the data bus
+        assert_eq!(memory.read(0x4000), 0x12);
+        memory.write(0x4000, 0x56);
+        assert_eq!(memory.read(0x4000), 0x56);
+        assert_eq!(memory.peek(0x4000), 0x56);
+
+        memory.reset();
+        assert_eq!(memory.peek(0x0001), 0x00);
+        assert_eq!(memory.peek(0xFFFF), 0x12);
+    }
+
+    #[test]
+    fn test_reset_vector() {
+        let memory = Map::new().ram(0x0000..=0xBFFF).rom(0xC000..=0xFFFF);
+        let mut cpu = NoneDebuggerCpu::new(memory);
+        cpu.load_at(0xC000, &[0xE8, 0x00]); // $C000 INX, BRK
+        cpu.set_reset_vector(0xC000);
+        cpu.registers.pc = 0x0600;
+
+        cpu.reset();
+        assert_eq!(cpu.registers.pc, 0xC000);
+
+        cpu.execute();
+        cpu.reset();
+        assert_eq!(cpu.registers.pc, 0xC000);
+        assert_eq!(cpu.registers.x, 0);
+
+        // Cleared RAM has no vector
+        let mut cpu = NoneDebuggerCpu::new(Map::new().ram(0x0000..=0xFFFF));
+        cpu.set_reset_vector(0xC000);
+        cpu.reset();
+        assert_eq!(cpu.registers.pc, ORG);
+    }
+
+    #[test]
+    fn test_device() {
+        let port = Port {
+            period: 50,
+            ..Default::default()
+        };
+        let written = port.written.clone();
+
+        let memory = Map::default().device(0x6000..=0x6003, Box::new(port));
+        let mut cpu = NoneDebuggerCpu::new(memory);
+        cpu.reset();
+        cpu.load(&[
+            0xAD, 0x02, 0x60, // $8000 LDA $6002
+            0x8D, 0x01, 0x60, // $8003 STA $6001
+            0x58, // $8006 CLI
+            0x4C, 0x07, 0x80, // $8007 JMP $8007
+        ]);
+        cpu.memory.load(0x9000, &[0xE8, 0x40]); // $9000 INX, RTI
+        cpu.memory.load(IRQ_VECTOR, &[0x00, 0x90]);
+        cpu.registers.sp = 0xFF;
+
+        for _ in 0..40 {
+            cpu.execute_next();
+        }
+
+        // Poking the port has no side effects
+        cpu.memory.poke(0x6000, 0x12);
+
+        assert_eq!(*written.lock().unwrap(), [(0x0001, 0x82)]);
+        // 139 cycles: the timer interrupted twice, each time running the INX handler
+        assert_eq!(cpu.cycles, 139);
+        assert_eq!(cpu.registers.x, 2);
+        assert_eq!(cpu.registers.pc, 0x8007);
+    }
+}
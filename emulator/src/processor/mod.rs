@@ -1,4 +1,13 @@
 pub mod addressing_mode;
+#[cfg(feature = "std")]
+pub mod batch;
 pub mod cpu;
+#[cfg(feature = "std")]
+pub mod mapper;
 pub mod memory;
+#[cfg(feature = "std")]
+pub mod memory_map;
+pub mod opcode;
+#[cfg(feature = "std")]
+pub mod program;
 pub mod registers;
@@ -0,0 +1,310 @@
+/// Operand of an instruction, as written in assembly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+    Relative,
+}
+
+impl Operand {
+    /// Number of operand bytes following the opcode.
+    pub fn bytes(&self) -> u8 {
+        match self {
+            Operand::Implied | Operand::Accumulator => 0,
+            Operand::Immediate
+            | Operand::ZeroPage
+            | Operand::ZeroPageX
+            | Operand::ZeroPageY
+            | Operand::IndirectX
+            | Operand::IndirectY
+            | Operand::Relative => 1,
+            Operand::Absolute | Operand::AbsoluteX | Operand::AbsoluteY | Operand::Indirect => 2,
+        }
+    }
+}
+
+/// A documented opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub operand: Operand,
+}
+
+/// Decodes an opcode. Returns `None` for undocumented opcodes, which are executed as `NOP`s.
+pub fn decode(opcode: u8) -> Option<Opcode> {
+    use Operand::*;
+
+    let (mnemonic, operand) = match opcode {
+        0x69 => ("ADC", Immediate),
+        0x65 => ("ADC", ZeroPage),
+        0x75 => ("ADC", ZeroPageX),
+        0x6D => ("ADC", Absolute),
+        0x7D => ("ADC", AbsoluteX),
+        0x79 => ("ADC", AbsoluteY),
+        0x61 => ("ADC", IndirectX),
+        0x71 => ("ADC", IndirectY),
+
+        0x29 => ("AND", Immediate),
+        0x25 => ("AND", ZeroPage),
+        0x35 => ("AND", ZeroPageX),
+        0x2D => ("AND", Absolute),
+        0x3D => ("AND", AbsoluteX),
+        0x39 => ("AND", AbsoluteY),
+        0x21 => ("AND", IndirectX),
+        0x31 => ("AND", IndirectY),
+
+        0x0A => ("ASL", Accumulator),
+        0x06 => ("ASL", ZeroPage),
+        0x16 => ("ASL", ZeroPageX),
+        0x0E => ("ASL", Absolute),
+        0x1E => ("ASL", AbsoluteX),
+
+        0x90 => ("BCC", Relative),
+
+        0xB0 => ("BCS", Relative),
+
+        0xF0 => ("BEQ", Relative),
+
+        0x24 => ("BIT", ZeroPage),
+        0x2C => ("BIT", Absolute),
+
+        0x30 => ("BMI", Relative),
+
+        0xD0 => ("BNE", Relative),
+
+        0x10 => ("BPL", Relative),
+
+        0x50 => ("BVC", Relative),
+
+        0x70 => ("BVS", Relative),
+
+        0x18 => ("CLC", Implied),
+
+        0xD8 => ("CLD", Implied),
+
+        0x58 => ("CLI", Implied),
+
+        0xB8 => ("CLV", Implied),
+
+        0xC9 => ("CMP", Immediate),
+        0xC5 => ("CMP", ZeroPage),
+        0xD5 => ("CMP", ZeroPageX),
+        0xCD => ("CMP", Absolute),
+        0xDD => ("CMP", AbsoluteX),
+        0xD9 => ("CMP", AbsoluteY),
+        0xC1 => ("CMP", IndirectX),
+        0xD1 => ("CMP", IndirectY),
+
+        0xE0 => ("CPX", Immediate),
+        0xE4 => ("CPX", ZeroPage),
+        0xEC => ("CPX", Absolute),
+
+        0xC0 => ("CPY", Immediate),
+        0xC4 => ("CPY", ZeroPage),
+        0xCC => ("CPY", Absolute),
+
+        0xC6 => ("DEC", ZeroPage),
+        0xD6 => ("DEC", ZeroPageX),
+        0xCE => ("DEC", Absolute),
+        0xDE => ("DEC", AbsoluteX),
+
+        0xCA => ("DEX", Implied),
+
+        0x88 => ("DEY", Implied),
+
+        0x49 => ("EOR", Immediate),
+        0x45 => ("EOR", ZeroPage),
+        0x55 => ("EOR", ZeroPageX),
+        0x4D => ("EOR", Absolute),
+        0x5D => ("EOR", AbsoluteX),
+        0x59 => ("EOR", AbsoluteY),
+        0x41 => ("EOR", IndirectX),
+        0x51 => ("EOR", IndirectY),
+
+        0xE6 => ("INC", ZeroPage),
+        0xF6 => ("INC", ZeroPageX),
+        0xEE => ("INC", Absolute),
+        0xFE => ("INC", AbsoluteX),
+
+        0xE8 => ("INX", Implied),
+
+        0xC8 => ("INY", Implied),
+
+        0x4C => ("JMP", Absolute),
+        0x6C => ("JMP", Indirect),
+
+        0x20 => ("JSR", Absolute),
+
+        0xA9 => ("LDA", Immediate),
+        0xA5 => ("LDA", ZeroPage),
+        0xB5 => ("LDA", ZeroPageX),
+        0xAD => ("LDA", Absolute),
+        0xBD => ("LDA", AbsoluteX),
+        0xB9 => ("LDA", AbsoluteY),
+        0xA1 => ("LDA", IndirectX),
+        0xB1 => ("LDA", IndirectY),
+
+        0xA2 => ("LDX", Immediate),
+        0xA6 => ("LDX", ZeroPage),
+        0xB6 => ("LDX", ZeroPageY),
+        0xAE => ("LDX", Absolute),
+        0xBE => ("LDX", AbsoluteY),
+
+        0xA0 => ("LDY", Immediate),
+        0xA4 => ("LDY", ZeroPage),
+        0xB4 => ("LDY", ZeroPageX),
+        0xAC => ("LDY", Absolute),
+        0xBC => ("LDY", AbsoluteX),
+
+        0x4A => ("LSR", Accumulator),
+        0x46 => ("LSR", ZeroPage),
+        0x56 => ("LSR", ZeroPageX),
+        0x4E => ("LSR", Absolute),
+        0x5E => ("LSR", AbsoluteX),
+
+        0xEA => ("NOP", Implied),
+
+        0x09 => ("ORA", Immediate),
+        0x05 => ("ORA", ZeroPage),
+        0x15 => ("ORA", ZeroPageX),
+        0x0D => ("ORA", Absolute),
+        0x1D => ("ORA", AbsoluteX),
+        0x19 => ("ORA", AbsoluteY),
+        0x01 => ("ORA", IndirectX),
+        0x11 => ("ORA", IndirectY),
+
+        0x48 => ("PHA", Implied),
+
+        0x08 => ("PHP", Implied),
+
+        0x68 => ("PLA", Implied),
+
+        0x28 => ("PLP", Implied),
+
+        0x2A => ("ROL", Accumulator),
+        0x26 => ("ROL", ZeroPage),
+        0x36 => ("ROL", ZeroPageX),
+        0x2E => ("ROL", Absolute),
+        0x3E => ("ROL", AbsoluteX),
+
+        0x6A => ("ROR", Accumulator),
+        0x66 => ("ROR", ZeroPage),
+        0x76 => ("ROR", ZeroPageX),
+        0x6E => ("ROR", Absolute),
+        0x7E => ("ROR", AbsoluteX),
+
+        0x40 => ("RTI", Implied),
+
+        0x60 => ("RTS", Implied),
+
+        0xE9 => ("SBC", Immediate),
+        0xE5 => ("SBC", ZeroPage),
+        0xF5 => ("SBC", ZeroPageX),
+        0xED => ("SBC", Absolute),
+        0xFD => ("SBC", AbsoluteX),
+        0xF9 => ("SBC", AbsoluteY),
+        0xE1 => ("SBC", IndirectX),
+        0xF1 => ("SBC", IndirectY),
+
+        0x38 => ("SEC", Implied),
+
+        0xF8 => ("SED", Implied),
+
+        0x78 => ("SEI", Implied),
+
+        0x85 => ("STA", ZeroPage),
+        0x95 => ("STA", ZeroPageX),
+        0x8D => ("STA", Absolute),
+        0x9D => ("STA", AbsoluteX),
+        0x99 => ("STA", AbsoluteY),
+        0x81 => ("STA", IndirectX),
+        0x91 => ("STA", IndirectY),
+
+        0x86 => ("STX", ZeroPage),
+        0x96 => ("STX", ZeroPageY),
+        0x8E => ("STX", Absolute),
+
+        0x84 => ("STY", ZeroPage),
+        0x94 => ("STY", ZeroPageX),
+        0x8C => ("STY", Absolute),
+
+        0xAA => ("TAX", Implied),
+
+        0xA8 => ("TAY", Implied),
+
+        0xBA => ("TSX", Implied),
+
+        0x8A => ("TXA", Implied),
+
+        0x9A => ("TXS", Implied),
+
+        0x98 => ("TYA", Implied),
+
+        0x00 => ("BRK", Implied),
+
+        _ => return None,
+    };
+
+    Some(Opcode { mnemonic, operand })
+}
+
+/// Length in bytes of the instruction starting with `opcode`, including its operand.
+///
+/// Unknown opcodes are 1 byte long, like the `NOP`s they are executed as.
+pub fn instruction_length(opcode: u8) -> u8 {
+    decode(opcode).map_or(1, |opcode| 1 + opcode.operand.bytes())
+}
+
+/// Base cycle count of `opcode`.
+pub fn base_cycles(opcode: u8) -> u8 {
+    CYCLES[opcode as usize]
+}
+
+/// Base cycle count of each opcode.
+///
+/// Page crossing and taken branches add cycles on top of this, unknown opcodes are executed as 2-cycle `NOP`s.
+#[rustfmt::skip]
+const CYCLES: [u8; 0x100] = [
+    7, 6, 2, 2, 2, 3, 5, 2, 3, 2, 2, 2, 2, 4, 6, 2, // 0x0_
+    2, 5, 2, 2, 2, 4, 6, 2, 2, 4, 2, 2, 2, 4, 7, 2, // 0x1_
+    6, 6, 2, 2, 3, 3, 5, 2, 4, 2, 2, 2, 4, 4, 6, 2, // 0x2_
+    2, 5, 2, 2, 2, 4, 6, 2, 2, 4, 2, 2, 2, 4, 7, 2, // 0x3_
+    6, 6, 2, 2, 2, 3, 5, 2, 3, 2, 2, 2, 3, 4, 6, 2, // 0x4_
+    2, 5, 2, 2, 2, 4, 6, 2, 2, 4, 2, 2, 2, 4, 7, 2, // 0x5_
+    6, 6, 2, 2, 2, 3, 5, 2, 4, 2, 2, 2, 5, 4, 6, 2, // 0x6_
+    2, 5, 2, 2, 2, 4, 6, 2, 2, 4, 2, 2, 2, 4, 7, 2, // 0x7_
+    2, 6, 2, 2, 3, 3, 3, 2, 2, 2, 2, 2, 4, 4, 4, 2, // 0x8_
+    2, 6, 2, 2, 4, 4, 4, 2, 2, 5, 2, 2, 2, 5, 2, 2, // 0x9_
+    2, 6, 2, 2, 3, 3, 3, 2, 2, 2, 2, 2, 4, 4, 4, 2, // 0xA_
+    2, 5, 2, 2, 4, 4, 4, 2, 2, 4, 2, 2, 4, 4, 4, 2, // 0xB_
+    2, 6, 2, 2, 3, 3, 5, 2, 2, 2, 2, 2, 4, 4, 6, 2, // 0xC_
+    2, 5, 2, 2, 2, 4, 6, 2, 2, 4, 2, 2, 2, 4, 7, 2, // 0xD_
+    2, 6, 2, 2, 3, 3, 5, 2, 2, 2, 2, 2, 4, 4, 6, 2, // 0xE_
+    2, 5, 2, 2, 2, 4, 6, 2, 2, 4, 2, 2, 2, 4, 7, 2, // 0xF_
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_length() {
+        assert_eq!(instruction_length(0x00), 1); // BRK
+        assert_eq!(instruction_length(0x0A), 1); // ASL A
+        assert_eq!(instruction_length(0xA9), 2); // LDA #
+        assert_eq!(instruction_length(0xD0), 2); // BNE
+        assert_eq!(instruction_length(0x6C), 3); // JMP ()
+        assert_eq!(instruction_length(0x02), 1); // unknown
+        assert_eq!(base_cycles(0x6C), 5);
+    }
+}
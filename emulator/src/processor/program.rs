@@ -0,0 +1,325 @@
+use crate::{
+    cpu::Cpu,
+    memory::{MemoryBus, MEMORY_SIZE, RESET_VECTOR},
+    Debugger,
+};
+use std::fmt;
+
+mod ihex;
+mod o65;
+mod srec;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgramError {
+    /// A record that cannot be parsed, with its line number.
+    InvalidRecord(usize),
+    /// A record whose checksum does not match, with its line number.
+    Checksum(usize),
+    /// The file ends inside a header, segment or table.
+    UnexpectedEnd,
+    /// Data or an entry point beyond the 64 KiB address space.
+    OutOfRange(u32),
+    /// A feature of the format that cannot be loaded.
+    Unsupported(String),
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramError::InvalidRecord(line) => write!(f, "Invalid record at line {line}"),
+            ProgramError::Checksum(line) => write!(f, "Checksum mismatch at line {line}"),
+            ProgramError::UnexpectedEnd => write!(f, "Unexpected end of file"),
+            ProgramError::OutOfRange(address) => {
+                write!(f, "Address out of range: 0x{address:X}")
+            }
+            ProgramError::Unsupported(feature) => write!(f, "Unsupported: {feature}"),
+        }
+    }
+}
+
+pub type ProgramResult<T> = Result<T, ProgramError>;
+
+/// File format of a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Intel HEX records, `:10800000...`.
+    IntelHex,
+    /// Motorola S-records, `S1138000...`.
+    SRecord,
+    /// Commodore program file, the load address followed by the data.
+    Prg,
+    /// Relocatable object of the `xa` cross assembler.
+    O65,
+}
+
+impl Format {
+    /// Detects the format from the start of the file.
+    ///
+    /// PRG files have no signature, they are only recognized by [`Format::from_extension`].
+    pub fn detect(data: &[u8]) -> Option<Format> {
+        if data.starts_with(o65::MAGIC) {
+            return Some(Format::O65);
+        }
+
+        let start = data.iter().position(|byte| !byte.is_ascii_whitespace())?;
+        match &data[start..] {
+            [b':', ..] => Some(Format::IntelHex),
+            [b'S', b'0'..=b'9', ..] => Some(Format::SRecord),
+            _ => None,
+        }
+    }
+
+    /// Format of the usual file extensions, without the dot.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            "hex" | "ihx" | "ihex" => Some(Format::IntelHex),
+            "s19" | "s28" | "s37" | "srec" | "mot" => Some(Format::SRecord),
+            "prg" => Some(Format::Prg),
+            "o65" => Some(Format::O65),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the hex digit pairs of a record, `None` if it has other characters or an odd length.
+fn hex_bytes(record: &[u8]) -> Option<Vec<u8>> {
+    if !record.len().is_multiple_of(2) {
+        return None;
+    }
+
+    record
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+/// Non-empty lines of a text format, with their line numbers.
+fn records(data: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    data.split(|&byte| byte == b'\n')
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_ascii()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+/// # Program
+///
+/// A program image made of segments loaded at their own addresses, with an optional entry point.
+///
+/// ```ignore
+/// let program = Program::new()
+///     .with_segment(0x0080, &[0x00, 0x02]) // zero page pointer
+///     .with_segment(0x0600, &code)
+///     .with_entry(0x0600);
+/// cpu.load_program(&program);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Program {
+    /// `(address, bytes)` blocks, loaded in order, so later segments overwrite earlier ones.
+    pub segments: Vec<(u16, Vec<u8>)>,
+    /// Where execution starts. Without one, loading leaves the program counter as is.
+    pub entry: Option<u16>,
+}
+
+impl Program {
+    pub fn new() -> Program {
+        Program::default()
+    }
+
+    /// Raw bytes loaded at `address`, starting there.
+    pub fn raw(address: u16, data: &[u8]) -> Program {
+        Program::new()
+            .with_segment(address, data)
+            .with_entry(address)
+    }
+
+    /// A ROM image ending at `$FFFF`, starting at its reset vector.
+    /// Returns `None` if the image is empty or larger than the address space.
+    pub fn rom(data: &[u8]) -> Option<Program> {
+        if data.is_empty() || data.len() > MEMORY_SIZE {
+            return None;
+        }
+
+        let address = (MEMORY_SIZE - data.len()) as u16;
+        let program = Program::new().with_segment(address, data);
+
+        // Images of less than 4 bytes do not reach down to the reset vector
+        if address > RESET_VECTOR {
+            return Some(program);
+        }
+
+        let vector = (RESET_VECTOR - address) as usize;
+        Some(program.with_entry(u16::from_le_bytes([data[vector], data[vector + 1]])))
+    }
+
+    pub fn with_segment(mut self, address: u16, data: &[u8]) -> Program {
+        self.segments.push((address, data.to_vec()));
+        self
+    }
+
+    pub fn with_entry(mut self, entry: u16) -> Program {
+        self.entry = Some(entry);
+        self
+    }
+
+    /// Parses a program file, relocatable formats are loaded at the address they were assembled for.
+    pub fn parse(data: &[u8], format: Format) -> ProgramResult<Program> {
+        match format {
+            Format::IntelHex => Program::intel_hex(data),
+            Format::SRecord => Program::srecord(data),
+            Format::Prg => Program::prg(data),
+            Format::O65 => Program::o65(data, None),
+        }
+    }
+
+    /// Parses a Commodore PRG file into a segment at its load address.
+    ///
+    /// PRG files have no entry point: the usual load address `$0801` holds a BASIC stub, not code.
+    pub fn prg(data: &[u8]) -> ProgramResult<Program> {
+        let [lsb, msb, data @ ..] = data else {
+            return Err(ProgramError::UnexpectedEnd);
+        };
+
+        let address = u16::from_le_bytes([*lsb, *msb]);
+        let mut program = Program::new();
+        program.push_data(address as u32, data)?;
+
+        Ok(program)
+    }
+
+    /// Adds `data` at a 32-bit `address` of a file format, to the last segment if it continues it.
+    fn push_data(&mut self, address: u32, data: &[u8]) -> ProgramResult<()> {
+        if address as usize + data.len() > MEMORY_SIZE {
+            return Err(ProgramError::OutOfRange(address));
+        }
+
+        match self.segments.last_mut() {
+            Some((start, segment)) if *start as usize + segment.len() == address as usize => {
+                segment.extend_from_slice(data);
+            }
+            _ => self.segments.push((address as u16, data.to_vec())),
+        }
+
+        Ok(())
+    }
+
+    /// Sets the entry point from a 32-bit start address of a file format.
+    fn set_entry(&mut self, entry: u32) -> ProgramResult<()> {
+        let entry = u16::try_from(entry).map_err(|_| ProgramError::OutOfRange(entry))?;
+        self.entry = Some(entry);
+
+        Ok(())
+    }
+}
+
+impl<T, D, R> Cpu<T, D, R>
+where
+    T: MemoryBus<Data = u8, Addr = u16> + Default,
+    D: Debugger,
+    R: Debugger,
+{
+    /// Loads every segment of `program` as code, then jumps to its entry point if it has one.
+    /// The reset vector is not changed, set it with [`Cpu::set_reset_vector`].
+    pub fn load_program(&mut self, program: &Program) {
+        for (address, data) in &program.segments {
+            self.load_at(*address, data);
+            self.on_load(*address, data.len(), true);
+        }
+
+        if let Some(entry) = program.entry {
+            self.registers.pc = entry;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu::NoneDebuggerCpu, memory::Memory, NoneDebugger};
+
+    type TestCpu = NoneDebuggerCpu<Memory<NoneDebugger>>;
+
+    #[test]
+    fn test_load_program() {
+        let mut cpu = TestCpu::default();
+        cpu.reset();
+
+        let program = Program::new()
+            .with_segment(0x0080, &[0x34, 0x12])
+            .with_segment(
+                0x0600,
+                &[
+                    0xA0, 0x01, // $0600 LDY #$01
+                    0xB1, 0x80, // $0602 LDA ($80),Y
+                    0x00, // $0604 BRK
+                ],
+            )
+            .with_segment(0x1235, &[0x42])
+            .with_entry(0x0600);
+        cpu.load_program(&program);
+        cpu.set_reset_vector(0xC000);
+
+        assert_eq!(cpu.registers.pc, 0x0600);
+        assert_eq!(cpu.reset_vector(), 0xC000);
+
+        // Execution starts at the entry point, not at the reset vector
+        cpu.execute();
+        assert_eq!(cpu.registers.a, 0x42);
+        assert_eq!(cpu.instructions, 3);
+    }
+
+    #[test]
+    fn test_load_at_wraps() {
+        let mut cpu = TestCpu::default();
+        cpu.load_at(0xFFFF, &[0x01, 0x02]);
+
+        assert_eq!(cpu.memory.peek(0xFFFF), 0x01);
+        assert_eq!(cpu.memory.peek(0x0000), 0x02);
+    }
+
+    #[test]
+    fn test_rom() {
+        let mut image = vec![0xEA; 0x4000];
+        image[0x3FFC..].copy_from_slice(&[0x00, 0xC0, 0x00, 0x00]);
+
+        let program = Program::rom(&image).unwrap();
+        assert_eq!(program.segments[0].0, 0xC000);
+        assert_eq!(program.entry, Some(0xC000));
+
+        // Too small to hold the reset vector
+        let program = Program::rom(&[0xEA; 2]).unwrap();
+        assert_eq!(program.segments[0].0, 0xFFFE);
+        assert_eq!(program.entry, None);
+
+        assert_eq!(Program::rom(&[]), None);
+        assert_eq!(Program::rom(&vec![0; 0x10001]), None);
+    }
+
+    #[test]
+    fn test_prg() {
+        let program = Program::prg(&[0x01, 0x08, 0x0B, 0x08]).unwrap();
+        assert_eq!(program.segments, [(0x0801, vec![0x0B, 0x08])]);
+        assert_eq!(program.entry, None);
+
+        assert_eq!(Program::prg(&[0x01]), Err(ProgramError::UnexpectedEnd));
+        assert_eq!(
+            Program::prg(&[0xFF, 0xFF, 0x01, 0x02]),
+            Err(ProgramError::OutOfRange(0xFFFF))
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect(b"\r\n:00000001FF"), Some(Format::IntelHex));
+        assert_eq!(Format::detect(b"S9030600F6"), Some(Format::SRecord));
+        assert_eq!(Format::detect(b"\x01\x00o65\x00"), Some(Format::O65));
+        assert_eq!(Format::detect(&[0x01, 0x08, 0x0B, 0x08]), None);
+        assert_eq!(Format::detect(b"SEI"), None);
+
+        assert_eq!(Format::from_extension("PRG"), Some(Format::Prg));
+        assert_eq!(Format::from_extension("s19"), Some(Format::SRecord));
+        assert_eq!(Format::from_extension("bin"), None);
+    }
+}
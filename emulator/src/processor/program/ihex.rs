@@ -0,0 +1,98 @@
+use super::{hex_bytes, records, Program, ProgramError, ProgramResult};
+
+impl Program {
+    /// Parses Intel HEX records.
+    ///
+    /// Extended segment and linear address records are followed as long as the data stays in the
+    /// 64 KiB address space, and start address records set the entry point.
+    pub fn intel_hex(data: &[u8]) -> ProgramResult<Program> {
+        let mut program = Program::new();
+        let mut base = 0;
+
+        for (line, record) in records(data) {
+            let bytes = match record {
+                [b':', record @ ..] => hex_bytes(record),
+                _ => None,
+            }
+            .ok_or(ProgramError::InvalidRecord(line))?;
+
+            let [len, offset_msb, offset_lsb, kind, ..] = bytes[..] else {
+                return Err(ProgramError::InvalidRecord(line));
+            };
+            if bytes.len() != len as usize + 5 {
+                return Err(ProgramError::InvalidRecord(line));
+            }
+            if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+                return Err(ProgramError::Checksum(line));
+            }
+
+            let offset = u16::from_be_bytes([offset_msb, offset_lsb]) as u32;
+            let payload = &bytes[4..bytes.len() - 1];
+
+            match (kind, payload) {
+                (0x00, _) => program.push_data(base + offset, payload)?,
+                (0x01, _) => break,
+                (0x02, [msb, lsb]) => base = (u16::from_be_bytes([*msb, *lsb]) as u32) << 4,
+                (0x03, [cs_msb, cs_lsb, ip_msb, ip_lsb]) => {
+                    let cs = u16::from_be_bytes([*cs_msb, *cs_lsb]) as u32;
+                    let ip = u16::from_be_bytes([*ip_msb, *ip_lsb]) as u32;
+                    program.set_entry((cs << 4) + ip)?;
+                }
+                (0x04, [msb, lsb]) => base = (u16::from_be_bytes([*msb, *lsb]) as u32) << 16,
+                (0x05, [a, b, c, d]) => program.set_entry(u32::from_be_bytes([*a, *b, *c, *d]))?,
+                _ => return Err(ProgramError::InvalidRecord(line)),
+            }
+        }
+
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intel_hex() {
+        let program = Program::intel_hex(
+            b":020000040000FA
+              :05800000A9018D000242
+              :01800500007A\r
+              :02FFFC00008083
+
+              :040000050000800077
+              :00000001FF
+              garbage after the end of file",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program.segments,
+            [
+                (0x8000, vec![0xA9, 0x01, 0x8D, 0x00, 0x02, 0x00]),
+                (0xFFFC, vec![0x00, 0x80])
+            ]
+        );
+        assert_eq!(program.entry, Some(0x8000));
+    }
+
+    #[test]
+    fn test_intel_hex_errors() {
+        assert_eq!(
+            Program::intel_hex(b":01800500007B"),
+            Err(ProgramError::Checksum(1))
+        );
+        assert_eq!(
+            Program::intel_hex(b"\n:0180050000"),
+            Err(ProgramError::InvalidRecord(2))
+        );
+        assert_eq!(
+            Program::intel_hex(b"01800500007A"),
+            Err(ProgramError::InvalidRecord(1))
+        );
+        assert_eq!(
+            Program::intel_hex(b":020000040001F9\n:0100000001FE"),
+            Err(ProgramError::OutOfRange(0x10000))
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use super::{Program, ProgramError, ProgramResult};
+
+/// Non-C64 marker, magic number and version 0.
+pub const MAGIC: &[u8] = b"\x01\x00o65\x00";
+
+const MODE_SIZE: u16 = 0x2000;
+const MODE_PAGED: u16 = 0x4000;
+const MODE_BSS_ZERO: u16 = 0x0200;
+
+const SEGMENT_UNDEFINED: u8 = 0;
+const SEGMENT_TEXT: u8 = 2;
+const SEGMENT_DATA: u8 = 3;
+const SEGMENT_BSS: u8 = 4;
+
+const RELOC_WORD: u8 = 0x80;
+const RELOC_HIGH: u8 = 0x40;
+const RELOC_LOW: u8 = 0x20;
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> ProgramResult<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(ProgramError::UnexpectedEnd);
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> ProgramResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn word(&mut self) -> ProgramResult<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn name(&mut self) -> ProgramResult<&'a [u8]> {
+        let len = self
+            .data
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(ProgramError::UnexpectedEnd)?;
+
+        let name = self.bytes(len)?;
+        self.byte()?;
+        Ok(name)
+    }
+}
+
+/// Where the text, data and bss segments were assembled for, and where they are loaded.
+struct Bases {
+    old: [u16; 3],
+    new: [u16; 3],
+}
+
+impl Bases {
+    /// Amount added to addresses in `segment`.
+    fn offset(&self, segment: u8) -> u16 {
+        match segment {
+            SEGMENT_TEXT | SEGMENT_DATA | SEGMENT_BSS => {
+                let i = (segment - SEGMENT_TEXT) as usize;
+                self.new[i].wrapping_sub(self.old[i])
+            }
+            // Absolute and zero page addresses stay in place
+            _ => 0,
+        }
+    }
+}
+
+/// Applies a relocation table to `segment`.
+fn relocate(
+    reader: &mut Reader,
+    segment: &mut [u8],
+    bases: &Bases,
+    paged: bool,
+    undefined: &[&[u8]],
+) -> ProgramResult<()> {
+    // Offsets are relative to the byte before the segment
+    let mut position = -1isize;
+
+    loop {
+        match reader.byte()? {
+            0 => return Ok(()),
+            255 => {
+                position += 254;
+                continue;
+            }
+            offset => position += offset as isize,
+        }
+
+        let kind = reader.byte()?;
+        if kind & 0x0F == SEGMENT_UNDEFINED {
+            let name = undefined
+                .get(reader.word()? as usize)
+                .copied()
+                .unwrap_or_default();
+            return Err(ProgramError::Unsupported(format!(
+                "undefined reference to {}",
+                String::from_utf8_lossy(name)
+            )));
+        }
+
+        let offset = bases.offset(kind & 0x0F);
+        let at = usize::try_from(position).map_err(|_| ProgramError::UnexpectedEnd)?;
+        let out_of_segment = || ProgramError::Unsupported("relocation outside its segment".into());
+
+        match kind & 0xE0 {
+            RELOC_WORD => {
+                let bytes = segment.get_mut(at..at + 2).ok_or_else(out_of_segment)?;
+                let value = u16::from_le_bytes([bytes[0], bytes[1]]).wrapping_add(offset);
+                bytes.copy_from_slice(&value.to_le_bytes());
+            }
+            RELOC_HIGH => {
+                let lsb = if paged { 0 } else { reader.byte()? };
+                let byte = segment.get_mut(at).ok_or_else(out_of_segment)?;
+                let value = u16::from_le_bytes([lsb, *byte]).wrapping_add(offset);
+                *byte = value.to_le_bytes()[1];
+            }
+            RELOC_LOW => {
+                let byte = segment.get_mut(at).ok_or_else(out_of_segment)?;
+                *byte = byte.wrapping_add(offset as u8);
+            }
+            _ => {
+                return Err(ProgramError::Unsupported(format!(
+                    "relocation type 0x{:02X}",
+                    kind & 0xE0
+                )))
+            }
+        }
+    }
+}
+
+impl Program {
+    /// Parses an o65 relocatable object, relocated so its text segment starts at `base`,
+    /// or at the address it was assembled for.
+    ///
+    /// When relocated, the data and bss segments follow the text segment. The zero page segment stays in place.
+    /// The entry point is the start of the text segment. Objects with undefined references,
+    /// and 65816 objects with 32-bit sizes, cannot be loaded.
+    pub fn o65(data: &[u8], base: Option<u16>) -> ProgramResult<Program> {
+        let mut reader = Reader { data };
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(ProgramError::Unsupported("not an o65 object".into()));
+        }
+
+        let mode = reader.word()?;
+        if mode & MODE_SIZE != 0 {
+            return Err(ProgramError::Unsupported("32-bit o65 object".into()));
+        }
+
+        let (tbase, tlen) = (reader.word()?, reader.word()?);
+        let (dbase, dlen) = (reader.word()?, reader.word()?);
+        let (bbase, blen) = (reader.word()?, reader.word()?);
+        // Zero page segment and stack size
+        reader.bytes(6)?;
+
+        // Header options, each starting with its length including the length byte
+        loop {
+            match reader.byte()? {
+                0 => break,
+                len => reader.bytes(len as usize - 1)?,
+            };
+        }
+
+        let mut text = reader.bytes(tlen as usize)?.to_vec();
+        let mut data = reader.bytes(dlen as usize)?.to_vec();
+
+        let undefined = (0..reader.word()?)
+            .map(|_| reader.name())
+            .collect::<ProgramResult<Vec<_>>>()?;
+
+        let old = [tbase, dbase, bbase];
+        let bases = Bases {
+            old,
+            new: base.map_or(old, |base| {
+                let data_base = base.wrapping_add(tlen);
+                [base, data_base, data_base.wrapping_add(dlen)]
+            }),
+        };
+        let [text_base, data_base, bss_base] = bases.new;
+
+        let paged = mode & MODE_PAGED != 0;
+        relocate(&mut reader, &mut text, &bases, paged, &undefined)?;
+        relocate(&mut reader, &mut data, &bases, paged, &undefined)?;
+
+        let mut program = Program::new().with_entry(text_base);
+        program.push_data(text_base as u32, &text)?;
+        program.push_data(data_base as u32, &data)?;
+
+        if mode & MODE_BSS_ZERO != 0 {
+            program.push_data(bss_base as u32, &vec![0; blen as usize])?;
+        }
+
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text at `$1000`: `LDA data`, `LDX #>data`, `LDY #<data`, `JMP $1000`; one data byte at `$2000`.
+    fn object(undefined: &[u8], relocations: &[u8]) -> Vec<u8> {
+        let mut object = MAGIC.to_vec();
+        object.extend_from_slice(&[
+            0x00, 0x00, // mode
+            0x00, 0x10, 0x0B, 0x00, // text at $1000, 11 bytes
+            0x00, 0x20, 0x01, 0x00, // data at $2000, 1 byte
+            0x00, 0x30, 0x00, 0x00, // bss
+            0x00, 0x00, 0x00, 0x00, // zero page
+            0x00, 0x00, // stack
+            0x05, 0x00, b'x', b'a', 0x00, // filename option
+            0x00, // end of options
+        ]);
+        object.extend_from_slice(&[
+            0xAD, 0x00, 0x20, // $1000 LDA $2000
+            0xA2, 0x20, // $1003 LDX #$20
+            0xA0, 0x00, // $1005 LDY #$00
+            0x4C, 0x00, 0x10, // $1007 JMP $1000
+            0x00, // $100A BRK
+        ]);
+        object.push(0x42);
+        object.extend_from_slice(undefined);
+        object.extend_from_slice(relocations);
+        object.extend_from_slice(&[
+            0x00, // end of text relocations
+            0x00, // end of data relocations
+            0x00, 0x00, // no exported globals
+        ]);
+        object
+    }
+
+    #[test]
+    fn test_o65() {
+        let object = object(
+            &[0x00, 0x00],
+            &[
+                0x02, 0x83, // $1001 WORD data
+                0x03, 0x43, 0x00, // $1004 HIGH data, low byte $00
+                0x02, 0x23, // $1006 LOW data
+                0x02, 0x82, // $1008 WORD text
+            ],
+        );
+
+        let program = Program::o65(&object, None).unwrap();
+        assert_eq!(program.entry, Some(0x1000));
+        assert_eq!(
+            program.segments,
+            [(0x1000, object[32..43].to_vec()), (0x2000, vec![0x42])]
+        );
+
+        // Data follows the text when relocated
+        let program = Program::o65(&object, Some(0xC000)).unwrap();
+        assert_eq!(program.entry, Some(0xC000));
+        assert_eq!(
+            program.segments,
+            [(
+                0xC000,
+                vec![0xAD, 0x0B, 0xC0, 0xA2, 0xC0, 0xA0, 0x0B, 0x4C, 0x00, 0xC0, 0x00, 0x42]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_o65_errors() {
+        // $1006 LOW of the undefined symbol #0
+        let undefined = object(
+            &[0x01, 0x00, b'f', b'o', b'o', 0x00],
+            &[0x07, 0x20, 0x00, 0x00],
+        );
+        assert_eq!(
+            Program::o65(&undefined, None),
+            Err(ProgramError::Unsupported(
+                "undefined reference to foo".into()
+            ))
+        );
+
+        let object = object(&[0x00, 0x00], &[]);
+        assert!(Program::o65(&object, None).is_ok());
+        assert_eq!(
+            Program::o65(&object[..40], None),
+            Err(ProgramError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Program::o65(b"\x01\x00o64\x00", None),
+            Err(ProgramError::Unsupported("not an o65 object".into()))
+        );
+    }
+}
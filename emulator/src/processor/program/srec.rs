@@ -0,0 +1,98 @@
+use super::{hex_bytes, records, Program, ProgramError, ProgramResult};
+
+/// Number of address bytes of each S-record type, `None` for reserved types.
+fn address_len(kind: u8) -> Option<usize> {
+    match kind {
+        b'0' | b'1' | b'5' | b'9' => Some(2),
+        b'2' | b'6' | b'8' => Some(3),
+        b'3' | b'7' => Some(4),
+        _ => None,
+    }
+}
+
+impl Program {
+    /// Parses Motorola S-records.
+    ///
+    /// Data records of any address size are accepted as long as the data stays in the
+    /// 64 KiB address space, and start address records (`S7`, `S8`, `S9`) set the entry point.
+    /// Header and count records are ignored.
+    pub fn srecord(data: &[u8]) -> ProgramResult<Program> {
+        let mut program = Program::new();
+
+        for (line, record) in records(data) {
+            let (kind, bytes) = match record {
+                [b'S', kind, record @ ..] => (*kind, hex_bytes(record)),
+                _ => (0, None),
+            };
+            let (Some(address_len), Some(bytes)) = (address_len(kind), bytes) else {
+                return Err(ProgramError::InvalidRecord(line));
+            };
+
+            let [count, ..] = bytes[..] else {
+                return Err(ProgramError::InvalidRecord(line));
+            };
+            if bytes.len() != count as usize + 1 || (count as usize) < address_len + 1 {
+                return Err(ProgramError::InvalidRecord(line));
+            }
+            if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0xFF {
+                return Err(ProgramError::Checksum(line));
+            }
+
+            let address = bytes[1..=address_len]
+                .iter()
+                .fold(0, |address, byte| address << 8 | *byte as u32);
+            let payload = &bytes[address_len + 1..bytes.len() - 1];
+
+            match kind {
+                b'1'..=b'3' => program.push_data(address, payload)?,
+                b'7'..=b'9' => program.set_entry(address)?,
+                _ => {}
+            }
+        }
+
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srecord() {
+        let program = Program::srecord(
+            b"S00700007465737438
+              S1060600A9010049
+              S20500020042B6
+              S5030001FB
+              S9030600F6",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program.segments,
+            [(0x0600, vec![0xA9, 0x01, 0x00]), (0x0200, vec![0x42])]
+        );
+        assert_eq!(program.entry, Some(0x0600));
+    }
+
+    #[test]
+    fn test_srecord_errors() {
+        assert_eq!(
+            Program::srecord(b"S1060600A9010048"),
+            Err(ProgramError::Checksum(1))
+        );
+        assert_eq!(
+            Program::srecord(b"S4030001FB"),
+            Err(ProgramError::InvalidRecord(1))
+        );
+        assert_eq!(
+            Program::srecord(b"S10306"),
+            Err(ProgramError::InvalidRecord(1))
+        );
+        assert_eq!(
+            Program::srecord(b"S3060001000042B6"),
+            Err(ProgramError::OutOfRange(0x10000))
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{memory::ORG, DebugKind, Debugger};
-use std::fmt;
+use core::fmt;
 
 /// # Registers
 ///
@@ -31,6 +31,69 @@ pub struct Registers<T: Debugger> {
     pub debugger: T,
 }
 
+/// Names a register. Used by the analyses and the debugger to refer to registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    P,
+    SP,
+    PC,
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::A => write!(f, "A"),
+            Register::X => write!(f, "X"),
+            Register::Y => write!(f, "Y"),
+            Register::P => write!(f, "P"),
+            Register::SP => write!(f, "SP"),
+            Register::PC => write!(f, "PC"),
+        }
+    }
+}
+
+/// Names a bit of the status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Flag {
+    Negative,
+    Overflow,
+    Break,
+    Decimal,
+    InterruptDisable,
+    Zero,
+    Carry,
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Flag::Negative => write!(f, "N"),
+            Flag::Overflow => write!(f, "V"),
+            Flag::Break => write!(f, "B"),
+            Flag::Decimal => write!(f, "D"),
+            Flag::InterruptDisable => write!(f, "I"),
+            Flag::Zero => write!(f, "Z"),
+            Flag::Carry => write!(f, "C"),
+        }
+    }
+}
+
+/// # Register State
+///
+/// A copy of the register values, without the debugger.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RegisterState {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub p: u8,
+    pub sp: u8,
+    pub pc: u16,
+}
+
 impl<T: Debugger> Default for Registers<T> {
     fn default() -> Registers<T> {
         Registers {
@@ -82,6 +145,52 @@ impl<T: Debugger> Registers<T> {
         self.debugger.debug(message, DebugKind::Info);
     }
 
+    pub fn state(&self) -> RegisterState {
+        RegisterState {
+            a: self.a,
+            x: self.x,
+            y: self.y,
+            p: self.p,
+            sp: self.sp,
+            pc: self.pc,
+        }
+    }
+
+    pub fn get(&self, register: Register) -> u16 {
+        match register {
+            Register::A => self.a as u16,
+            Register::X => self.x as u16,
+            Register::Y => self.y as u16,
+            Register::P => self.p as u16,
+            Register::SP => self.sp as u16,
+            Register::PC => self.pc,
+        }
+    }
+
+    pub fn get_flag(&self, flag: Flag) -> bool {
+        match flag {
+            Flag::Negative => self.get_flag_negative(),
+            Flag::Overflow => self.get_flag_overflow(),
+            Flag::Break => self.get_flag_break(),
+            Flag::Decimal => self.get_flag_decimal(),
+            Flag::InterruptDisable => self.get_flag_interrupt_disable(),
+            Flag::Zero => self.get_flag_zero(),
+            Flag::Carry => self.get_flag_carry(),
+        }
+    }
+
+    /// Restore the registers from a previously captured [`RegisterState`].
+    pub fn restore(&mut self, state: RegisterState) {
+        let RegisterState { a, x, y, p, sp, pc } = state;
+
+        self.a = a;
+        self.x = x;
+        self.y = y;
+        self.p = p;
+        self.sp = sp;
+        self.pc = pc;
+    }
+
     /// Set the flag for the negative bit.
     /// if `value` is `true`, set the negative bit to `1` (`1XXX_XXXX`b).
     pub fn set_flag_negative(&mut self, value: bool) {
@@ -91,7 +200,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b0111_1111
         };
 
-        self.debug(&format!("Set flag negative: {} -> {}", self.p, data));
+        debug!(self, "Set flag negative: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -109,7 +218,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b1011_1111
         };
 
-        self.debug(&format!("Set flag overflow: {} -> {}", self.p, data));
+        debug!(self, "Set flag overflow: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -127,7 +236,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b1110_1111
         };
 
-        self.debug(&format!("Set flag break: {} -> {}", self.p, data));
+        debug!(self, "Set flag break: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -145,7 +254,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b1111_0111
         };
 
-        self.debug(&format!("Set flag decimal: {} -> {}", self.p, data));
+        debug!(self, "Set flag decimal: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -163,10 +272,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b1111_1011
         };
 
-        self.debug(&format!(
-            "Set flag interrupt disable: {} -> {}",
-            self.p, data
-        ));
+        debug!(self, "Set flag interrupt disable: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -184,7 +290,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b1111_1101
         };
 
-        self.debug(&format!("Set flag zero: {} -> {}", self.p, data));
+        debug!(self, "Set flag zero: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -202,7 +308,7 @@ impl<T: Debugger> Registers<T> {
             self.p & 0b1111_1110
         };
 
-        self.debug(&format!("Set flag carry: {} -> {}", self.p, data));
+        debug!(self, "Set flag carry: {} -> {}", self.p, data);
 
         self.p = data;
     }
@@ -1,7 +1,19 @@
 use assembler::Assembler;
 use emulator::{
+    breakpoint::{Breakpoint, BreakpointId, WatchKind},
+    call_stack::CallStack,
+    clobber::ClobberChecker,
+    coverage::Coverage,
     cpu::Cpu,
+    expression::Expression,
     memory::{memory_hexdump_string, Memory},
+    profiler::Profiler,
+    replay::{InputEvent, Replay},
+    sanitizer::Sanitizer,
+    self_modifying::SelfModifyingCode,
+    stack_sanitizer::StackSanitizer,
+    symbols::Symbols,
+    trace_diff::TraceDiff,
     Cpu6502, CpuDebugger, DebugKind, Debugger,
 };
 use js_sys::Function;
@@ -34,6 +46,24 @@ impl Debugger for WasmDebugger {
     }
 }
 
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum WatchpointKind {
+    Read,
+    Write,
+    Access,
+}
+
+impl From<WatchpointKind> for WatchKind {
+    fn from(kind: WatchpointKind) -> WatchKind {
+        match kind {
+            WatchpointKind::Read => WatchKind::Read,
+            WatchpointKind::Write => WatchKind::Write,
+            WatchpointKind::Access => WatchKind::Access,
+        }
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Clone)]
 pub enum AssemblerResultKind {
@@ -105,6 +135,18 @@ impl Emulator {
         self.cpu.load(&data);
     }
 
+    pub fn load_at(&mut self, address: u16, data: Vec<u8>) {
+        self.cpu.load_at(address, &data);
+    }
+
+    pub fn set_reset_vector(&mut self, address: u16) {
+        self.cpu.set_reset_vector(address);
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.cpu.registers.pc = pc;
+    }
+
     pub fn reset(&mut self) {
         self.cpu.reset();
     }
@@ -113,8 +155,267 @@ impl Emulator {
         self.cpu.execute();
     }
 
-    pub fn step(&mut self) {
-        self.cpu.step();
+    /// Executes one instruction. Returns the stop reason, if execution should stop.
+    pub fn step(&mut self) -> Option<String> {
+        self.cpu.step().map(|reason| reason.to_string())
+    }
+
+    /// Executes until a `BRK`, a breakpoint or a sanitizer violation, and returns the stop reason.
+    pub fn run(&mut self) -> String {
+        self.cpu.run().to_string()
+    }
+
+    pub fn add_breakpoint(&mut self, pc: u16) -> BreakpointId {
+        self.cpu.breakpoints.add(Breakpoint::execute(pc))
+    }
+
+    /// Adds a breakpoint that only stops when `condition` (e.g. `A == $10 && X > 3`) is non-zero.
+    pub fn add_conditional_breakpoint(
+        &mut self,
+        pc: u16,
+        condition: &str,
+    ) -> Result<BreakpointId, JsValue> {
+        let condition =
+            Expression::parse(condition).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        Ok(self
+            .cpu
+            .breakpoints
+            .add(Breakpoint::execute(pc).with_condition(condition)))
+    }
+
+    /// Evaluates a debugger expression (e.g. `word[$FB] >= $C000`) against the CPU.
+    pub fn evaluate(&self, expression: &str) -> Result<f64, JsValue> {
+        self.cpu
+            .evaluate_str(expression)
+            .map(|value| value as f64)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn add_temporary_breakpoint(&mut self, pc: u16) -> BreakpointId {
+        self.cpu
+            .breakpoints
+            .add(Breakpoint::execute(pc).temporary())
+    }
+
+    pub fn add_watchpoint(
+        &mut self,
+        start: u16,
+        end: u16,
+        kind: WatchpointKind,
+        value: Option<u8>,
+    ) -> BreakpointId {
+        let mut watchpoint = Breakpoint::watch(start..=end, kind.into());
+
+        if let Some(value) = value {
+            watchpoint = watchpoint.with_value(value);
+        }
+
+        self.cpu.breakpoints.add(watchpoint)
+    }
+
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        self.cpu.breakpoints.remove(id).is_some()
+    }
+
+    pub fn set_breakpoint_enabled(&mut self, id: BreakpointId, enabled: bool) -> bool {
+        self.cpu.breakpoints.set_enabled(id, enabled)
+    }
+
+    /// Every breakpoint, one per line with its hit count.
+    pub fn breakpoints(&self) -> String {
+        self.cpu
+            .breakpoints
+            .iter()
+            .map(|breakpoint| format!("{breakpoint} ({} hits)", breakpoint.hits))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn enable_history(&mut self, capacity: usize, checkpoint_interval: u64) {
+        self.cpu.enable_history(capacity, checkpoint_interval);
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        self.cpu.step_back()
+    }
+
+    pub fn run_back_to(&mut self, pc: u16) -> bool {
+        self.cpu.run_back_to(pc)
+    }
+
+    pub fn irq(&mut self) {
+        self.cpu.input(InputEvent::Irq);
+    }
+
+    pub fn nmi(&mut self) {
+        self.cpu.input(InputEvent::Nmi);
+    }
+
+    /// Writes memory from outside the CPU, e.g. a key press at `0x00FF`.
+    pub fn poke(&mut self, address: u16, data: u8) {
+        self.cpu.input(InputEvent::Poke { address, data });
+    }
+
+    /// Records inputs given through `irq`, `nmi` and `poke` from now on.
+    pub fn record_inputs(&mut self) {
+        self.cpu.replay = Some(Replay::recording());
+    }
+
+    /// The recorded inputs, in the replay file format.
+    pub fn recorded_inputs(&self) -> String {
+        self.cpu
+            .replay
+            .as_ref()
+            .map(|replay| replay.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Replays inputs saved by `recorded_inputs`, counting from the last reset.
+    pub fn replay_inputs(&mut self, replay: &str) -> Result<(), JsValue> {
+        self.cpu.replay = Some(Replay::parse(replay).map_err(|e| JsValue::from_str(&e))?);
+        Ok(())
+    }
+
+    pub fn enable_call_stack(&mut self) {
+        self.cpu.call_stack = Some(CallStack::default());
+    }
+
+    /// The active calls, innermost first, one per line.
+    pub fn backtrace(&self) -> String {
+        self.cpu
+            .backtrace()
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| format!("#{i} {frame}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn enable_profiler(&mut self) {
+        self.cpu.profiler = Some(Profiler::default());
+    }
+
+    /// Routines and the `limit` hottest addresses, sorted by cycles.
+    pub fn profile_report(&self, limit: usize) -> Option<String> {
+        self.cpu
+            .profiler
+            .as_ref()
+            .map(|profiler| profiler.report(&self.cpu.symbols, limit))
+    }
+
+    /// Cycles per call stack, in the folded format of flame graph tools.
+    pub fn profile_folded(&self) -> Option<String> {
+        self.cpu
+            .profiler
+            .as_ref()
+            .map(|profiler| profiler.folded(&self.cpu.symbols))
+    }
+
+    pub fn enable_coverage(&mut self) {
+        self.cpu.coverage = Some(Coverage::default());
+    }
+
+    /// Line and branch coverage of the assembled source in LCOV format, named `path`.
+    pub fn coverage_lcov(&self, path: &str) -> Option<String> {
+        self.cpu.coverage_lcov(path)
+    }
+
+    /// `source` annotated with the execution count of each line.
+    pub fn coverage_report(&self, source: &str) -> Option<String> {
+        self.cpu.coverage_report(source)
+    }
+
+    pub fn enable_self_modifying(&mut self) {
+        self.cpu.self_modifying = Some(SelfModifyingCode::default());
+    }
+
+    /// Writes into executed instructions, oldest first, one per line.
+    pub fn self_modifications(&self) -> String {
+        self.cpu
+            .self_modifying
+            .iter()
+            .flat_map(|self_modifying| self_modifying.modifications())
+            .map(|modification| modification.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Flags uninitialized reads, ROM writes and data execution. With `stop`, execution stops at new violations.
+    pub fn enable_sanitizer(&mut self, stop: bool) {
+        let mut sanitizer = Sanitizer::default();
+        sanitizer.stop = stop;
+        self.cpu.sanitizer = Some(sanitizer);
+    }
+
+    /// Sanitizer violations, oldest first, one per line.
+    pub fn sanitizer_violations(&self) -> String {
+        self.cpu
+            .sanitizer
+            .iter()
+            .flat_map(|sanitizer| sanitizer.violations())
+            .map(|violation| violation.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn enable_stack_sanitizer(&mut self) {
+        self.cpu.stack_sanitizer = Some(StackSanitizer::default());
+    }
+
+    /// Stack issues, oldest first, one per line.
+    pub fn stack_issues(&self) -> String {
+        self.cpu
+            .stack_sanitizer
+            .iter()
+            .flat_map(|stack_sanitizer| stack_sanitizer.issues())
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The lowest stack address pushed to, if the stack sanitizer is enabled and anything was pushed.
+    pub fn stack_high_water_mark(&self) -> Option<u16> {
+        self.cpu
+            .stack_sanitizer
+            .as_ref()
+            .and_then(|stack_sanitizer| stack_sanitizer.high_water_mark())
+    }
+
+    /// Checks that interrupt handlers preserve A, X, Y and the `zero_page` locations.
+    pub fn enable_clobber_checker(&mut self, zero_page: Vec<u8>) {
+        let mut clobber_checker = ClobberChecker::default();
+        clobber_checker.zero_page = zero_page;
+        self.cpu.clobber_checker = Some(clobber_checker);
+    }
+
+    /// Handlers that returned with changed registers or zero page locations, oldest first, one per line.
+    pub fn clobbers(&self) -> String {
+        self.cpu
+            .clobber_checker
+            .iter()
+            .flat_map(|clobber_checker| clobber_checker.clobbers())
+            .map(|clobber| clobber.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The nestest-style trace line of the next instruction.
+    pub fn trace_line(&self) -> String {
+        self.cpu.trace_line()
+    }
+
+    pub fn disassemble(&self, pc: u16) -> String {
+        self.cpu.disassemble(pc)
+    }
+
+    /// Compares two traces written by `trace_line`, one line per instruction.
+    pub fn diff_traces(left: &str, right: &str) -> String {
+        match TraceDiff::default().compare(left.as_bytes(), right.as_bytes()) {
+            Ok(Some(divergence)) => divergence.to_string(),
+            Ok(None) => "Traces are identical".to_string(),
+            Err(e) => e.to_string(),
+        }
     }
 
     pub fn memory_hexdump(&self, start: u16, end: u16) -> String {
@@ -125,15 +426,25 @@ impl Emulator {
         format!("{}", self.cpu)
     }
 
-    pub fn assemble(&self, source: &str) -> AssemblerResult {
-        let src = Assembler::new(source).assemble();
+    /// Assembles `source`. On success, its labels and source lines become the debugger symbols.
+    pub fn assemble(&mut self, source: &str) -> AssemblerResult {
+        let mut assembler = Assembler::new(source);
+        let src = assembler.assemble();
 
         match src {
-            Ok(src) => AssemblerResult {
-                kind: AssemblerResultKind::Ok,
-                value: Some(src),
-                error: None,
-            },
+            Ok(src) => {
+                self.cpu.symbols = Symbols::new(assembler.symbols());
+
+                for (address, line) in assembler.lines() {
+                    self.cpu.symbols.insert_line(*address, *line);
+                }
+
+                AssemblerResult {
+                    kind: AssemblerResultKind::Ok,
+                    value: Some(src),
+                    error: None,
+                }
+            }
             Err(err) => AssemblerResult {
                 kind: AssemblerResultKind::Err,
                 value: None,